@@ -112,6 +112,16 @@ async function loadRace(file) {
     // ── Stages ─────────────────────────────────────────────────────────────────
 
     renderStages(full.stages, full.waypoints);
+
+    // ── Splits ─────────────────────────────────────────────────────────────────
+
+    const splits = trace.splits({
+      basePaceSPerKm: 500,
+      kFatigue: 0.002,
+      splitKm: 1,
+      startTime: full.waypoints[0]?.time ?? null,
+    });
+    renderSplits(splits);
   } finally {
     // ── Free WASM memory — all array data already copied to JS ─────────────────
     trace?.free();
@@ -206,6 +216,16 @@ function renderWaypoints(waypoints) {
     </table>`;
 }
 
+function formatSlack(minutes) {
+  if (minutes == null) return "—";
+  const sign = minutes < 0 ? "−" : "+";
+  return sign + formatDuration(Math.abs(minutes) * 60);
+}
+
+function slackClass(risk) {
+  return risk === "missed" || risk === "atRisk" ? "loss" : risk === "tight" ? "muted" : "";
+}
+
 function renderSections(sections, waypoints) {
   const el = document.getElementById("sections-list");
   if (!el) return;
@@ -226,6 +246,7 @@ function renderSections(sections, waypoints) {
           <th>Gain</th>
           <th>Loss</th>
           <th>Est. time</th>
+          <th>Slack</th>
           <th>Difficulty</th>
         </tr>
       </thead>
@@ -244,6 +265,7 @@ function renderSections(sections, waypoints) {
             <td class="num gain" data-label="Gain">+${Math.round(s.totalElevationGainM)} m</td>
             <td class="num loss" data-label="Loss">−${Math.round(s.totalElevationLossM)} m</td>
             <td class="num" data-label="Est. time">${formatDuration(s.estimatedDurationS)}</td>
+            <td class="num ${slackClass(s.cutoffRisk)}" data-label="Slack">${formatSlack(s.cutoffSlackMin)}</td>
             <td class="num stars" data-label="Difficulty">${stars(s.difficulty)}</td>
           </tr>`;
           })
@@ -300,6 +322,48 @@ function renderStages(stages, waypoints) {
     </table>`;
 }
 
+function renderSplits(splits) {
+  const el = document.getElementById("splits-list");
+  if (!el) return;
+  if (!splits || splits.length === 0) {
+    el.innerHTML = '<p class="empty">NO SPLITS.</p>';
+    return;
+  }
+
+  el.innerHTML = `
+    <table class="data-table">
+      <thead>
+        <tr>
+          <th>Km</th>
+          <th>Dist.</th>
+          <th>Gain</th>
+          <th>Loss</th>
+          <th>Grade</th>
+          <th>Pace factor</th>
+          <th>Est. time</th>
+          <th>Cum.</th>
+        </tr>
+      </thead>
+      <tbody>
+        ${splits
+          .map(
+            (s) => `
+          <tr>
+            <td class="num" data-label="Km">${s.id + 1}</td>
+            <td class="num" data-label="Dist.">${s.distanceKm.toFixed(2)} km</td>
+            <td class="num gain" data-label="Gain">+${Math.round(s.elevationGainM)} m</td>
+            <td class="num loss" data-label="Loss">−${Math.round(s.elevationLossM)} m</td>
+            <td class="num" data-label="Grade">${s.avgSlope.toFixed(1)} %</td>
+            <td class="num" data-label="Pace factor">×${s.paceFactor.toFixed(2)}</td>
+            <td class="num" data-label="Est. time">${formatDuration(s.estimatedDurationS)}</td>
+            <td class="num muted" data-label="Cum.">${formatDuration(s.cumulativeDurationS)}</td>
+          </tr>`,
+          )
+          .join("")}
+      </tbody>
+    </table>`;
+}
+
 function drawProfile(canvas, locsFlat, dists, peaks, valleys, climbs) {
   const DPR = window.devicePixelRatio || 1;
   const W = canvas.parentElement.clientWidth;
@@ -1,9 +1,18 @@
-use crate::location::Location;
-use crate::pace_model::{AnalysisOptions, WeatherLookup, RECOVERY_LIFE_BASE};
-use crate::segment::{self, SegmentParams, SegmentState};
+use std::ops::Range;
+
+use crate::cutoff::{self, CutoffRisk};
+use crate::pace_model::{AnalysisOptions, PaceModel};
+use crate::route::ResolvedRoute;
+use crate::segment::{self, SegmentParams, SegmentSink, SegmentState};
+use crate::sleep::{self, SleepPlan, SleepTracker};
+use crate::surface::SurfaceMap;
 use crate::trace::Trace;
 use crate::waypoint::Waypoint;
 
+pub mod fit;
+pub mod history;
+pub mod passages;
+
 /// Calibration is ignored until the model predicts at least this many seconds of covered effort.
 pub const MIN_CALIBRATION_PREDICTION_S: f64 = 300.0;
 
@@ -19,7 +28,7 @@ pub enum BoundaryKind {
     Stage,
 }
 
-fn is_boundary(wpt: &Waypoint, kind: &BoundaryKind) -> bool {
+pub(crate) fn is_boundary(wpt: &Waypoint, kind: &BoundaryKind) -> bool {
     match kind {
         BoundaryKind::Section => wpt.is_section_boundary(),
         BoundaryKind::Stage => wpt.is_stage_boundary(),
@@ -36,6 +45,13 @@ pub struct RecalibratedEta {
     pub remaining_duration_s: f64,
     /// Running sum of `remaining_duration_s` to the end of this interval (0 for completed).
     pub cumulative_remaining_s: f64,
+    /// Stop (and sleep) at the end checkpoint, included in `remaining_duration_s`
+    /// (0 for completed intervals).
+    pub stop_s: f64,
+    /// End checkpoint's absolute cutoff minus the recalibrated arrival (minutes).
+    /// `None` for completed intervals or when the start or end `<time>` is missing.
+    pub cutoff_slack_min: Option<f64>,
+    pub cutoff_risk: Option<CutoffRisk>,
 }
 
 /// Result of a live recalibration. Caller owns this value.
@@ -52,57 +68,110 @@ pub struct Recalibration {
     pub etas: Vec<RecalibratedEta>,
 }
 
-struct ResolvedRange<'a> {
-    id: usize,
-    start_index: usize,
-    end_index: usize,
-    end_wpt_name: &'a str,
+pub(crate) struct ResolvedRange<'a> {
+    pub(crate) id: usize,
+    pub(crate) start_index: usize,
+    pub(crate) end_index: usize,
+    pub(crate) start_wpt_name: &'a str,
+    pub(crate) end_wpt_name: &'a str,
     end_wpt_type: Option<&'a str>,
     end_wpt_stop_duration: Option<u32>,
+    end_wpt_time: Option<i64>,
 }
 
 /// Parameters shared across all `advance_range` calls within one recalibration.
 struct AdvanceParams<'a> {
+    options: &'a AnalysisOptions,
+    /// Overrides `options.base_pace_s_per_km` (calibrated pace).
     pace_s_per_km: f64,
-    k_fatigue: f64,
+    /// Overrides `options.pace_model` (per-grade multipliers).
+    pace_model: &'a dyn PaceModel,
     clock_start: Option<i64>,
-    life_base_stop_s: u32,
-    weather: &'a WeatherLookup,
+    surfaces: &'a SurfaceMap,
+}
+
+impl<'a> AdvanceParams<'a> {
+    /// `options` at their original pace.
+    fn new(
+        options: &'a AnalysisOptions,
+        clock_start: Option<i64>,
+        surfaces: &'a SurfaceMap,
+    ) -> Self {
+        AdvanceParams {
+            options,
+            pace_s_per_km: options.base_pace_s_per_km,
+            pace_model: options.pace_model.as_ref(),
+            clock_start,
+            surfaces,
+        }
+    }
+}
+
+/// The waypoint ending interval `id` of a recalibration over `kind` boundaries.
+pub(crate) fn end_waypoint<'a>(
+    waypoints: &'a [Waypoint],
+    kind: &BoundaryKind,
+    id: usize,
+) -> Option<&'a Waypoint> {
+    waypoints
+        .iter()
+        .filter(|w| is_boundary(w, kind))
+        .nth(id + 1)
 }
 
-/// Advance the physiological model across `[from, end)` of one resolved range.
+/// Advance the physiological model across `span` of one resolved range.
 ///
-/// Returns `(moving_time, stop_time)`. When `apply_end_effects` is true (runner
-/// reaches the checkpoint), applies LifeBase fatigue recovery and computes stop.
+/// Returns `(moving_time, stop_time)`. When the span ends at the range's end
+/// (runner reaches the checkpoint), takes any due sleep block, applies
+/// LifeBase/sleep fatigue recovery and computes the stop, which it adds to
+/// the race clock.
 fn advance_range(
     trace: &Trace,
     range: &ResolvedRange<'_>,
-    from: usize,
-    end: usize,
+    span: Range<usize>,
     ap: &AdvanceParams<'_>,
-    apply_end_effects: bool,
+    sleep_tracker: &mut SleepTracker<'_>,
     state: &mut SegmentState,
+    on_segment: Option<SegmentSink<'_>>,
 ) -> (f64, f64) {
-    let range_weather = ap.weather.find(range.end_wpt_name);
     let params = SegmentParams {
         base_pace_s_per_km: ap.pace_s_per_km,
-        k_fatigue: ap.k_fatigue,
-        clock_start: ap.clock_start,
-        weather: range_weather,
+        pace_model: ap.pace_model,
+        rested_until: sleep_tracker.rested_until,
+        ..SegmentParams::from_options(ap.options, ap.clock_start, Some(ap.surfaces)).interval(
+            ap.options,
+            trace,
+            range.start_wpt_name,
+            range.end_wpt_name,
+            range.start_index,
+            range.end_index,
+        )
+    };
+    let metrics = match on_segment {
+        Some(sink) => segment::compute_traced(trace, span.start, span.end, &params, state, sink),
+        None => segment::compute(trace, span.start, span.end, &params, state),
     };
-    let metrics = segment::compute(trace, from, end, &params, state);
 
-    let stop = if apply_end_effects {
-        if range.end_wpt_type == Some("LifeBase") {
-            state.d_eff_m *= 1.0 - RECOVERY_LIFE_BASE;
-        }
-        if let Some(sd) = range.end_wpt_stop_duration {
+    let stop = if span.end == range.end_index {
+        let is_life_base = range.end_wpt_type == Some("LifeBase");
+        let sleep_s = sleep_tracker.at_checkpoint(
+            range.end_wpt_name,
+            ap.clock_start.map(|t0| t0 + state.race_s as i64),
+        );
+        state.d_eff_m *= 1.0 - sleep::end_recovery(is_life_base, sleep_s);
+        let planned = if let Some(sd) = range.end_wpt_stop_duration {
             sd as f64
-        } else if range.end_wpt_type == Some("LifeBase") {
-            ap.life_base_stop_s as f64
+        } else if is_life_base {
+            ap.options.life_base_stop_s as f64
         } else {
             0.0
+        };
+        let stop = planned + sleep_s as f64;
+        state.race_s += stop;
+        if sleep_s > 0 {
+            sleep_tracker.rested_until = sleep::rested_until(ap.clock_start, state.race_s);
         }
+        stop
     } else {
         0.0
     };
@@ -110,90 +179,73 @@ fn advance_range(
     (metrics.total_time, stop)
 }
 
-/// Recalibrate remaining-interval ETAs from the runner's live progress.
-///
-/// `kind` selects section- vs stage-granularity boundaries. `current_index` is the
-/// runner's current trace point; `actual_elapsed_s` is real seconds elapsed since race start.
+/// `kind` ranges of `route`, with the end waypoint's fields borrowed.
 ///
-/// Phase 1 replays covered intervals at the original `base_pace_s_per_km` to get the
-/// model's prediction so far. Phase 2 forward-predicts remaining intervals at the
-/// calibrated pace, re-seeding the circadian clock to `actual_elapsed_s`.
-///
-/// Returns `None` when fewer than 2 boundaries exist.
-pub fn recalibrate_from_current(
-    trace: &Trace,
-    waypoints: &[Waypoint],
-    kind: BoundaryKind,
-    current_index: usize,
-    actual_elapsed_s: f64,
-    options: &AnalysisOptions,
-) -> Option<Recalibration> {
-    let base_pace_s_per_km = options.base_pace_s_per_km;
-    let k_fatigue = options.k_fatigue;
-    let life_base_stop_s = options.life_base_stop_s;
-    let weather = &options.weather;
-    // ── Resolve boundary waypoints onto trace index ranges ─────────────────────
-    let boundary_wpts: Vec<&Waypoint> =
-        waypoints.iter().filter(|w| is_boundary(w, &kind)).collect();
-    if boundary_wpts.len() < 2 {
+/// Returns the ranges and the first boundary's `<time>`, or `None` when
+/// fewer than 2 boundaries exist.
+pub(crate) fn resolve_ranges<'a>(
+    route: &ResolvedRoute<'a>,
+    kind: &BoundaryKind,
+) -> Option<(Vec<ResolvedRange<'a>>, Option<i64>)> {
+    if route.boundary_count(kind) < 2 {
         return None;
     }
-    let mut resolved: Vec<ResolvedRange<'_>> = Vec::new();
-    let mut search_start = 0usize;
-
-    for i in 0..boundary_wpts.len() - 1 {
-        let start_wpt = boundary_wpts[i];
-        let end_wpt = boundary_wpts[i + 1];
-        let start_target = Location {
-            longitude: start_wpt.longitude,
-            latitude: start_wpt.latitude,
-            altitude: 0.0,
-        };
-        let end_target = Location {
-            longitude: end_wpt.longitude,
-            latitude: end_wpt.latitude,
-            altitude: 0.0,
-        };
-        let start_index = match trace.find_closest_point_from(&start_target, search_start) {
-            Some((_, idx, _)) => idx,
-            None => continue,
-        };
-        let end_index = match trace.find_closest_point_from(&end_target, start_index + 1) {
-            Some((_, idx, _)) => idx,
-            None => continue,
-        };
-        search_start = end_index;
-        if start_index >= end_index {
-            continue;
-        }
-        resolved.push(ResolvedRange {
-            id: i,
-            start_index,
-            end_index,
-            end_wpt_name: end_wpt.name.as_str(),
-            end_wpt_type: end_wpt.wpt_type.as_deref(),
-            end_wpt_stop_duration: end_wpt.stop_duration,
-        });
-    }
+    let waypoints = route.waypoints();
+    let resolved = route
+        .spans(kind)
+        .iter()
+        .map(|span| {
+            let (start_wpt, end_wpt) = (&waypoints[span.start_wpt], &waypoints[span.end_wpt]);
+            ResolvedRange {
+                id: span.id,
+                start_index: span.start_index,
+                end_index: span.end_index,
+                start_wpt_name: start_wpt.name.as_str(),
+                end_wpt_name: end_wpt.name.as_str(),
+                end_wpt_type: end_wpt.wpt_type.as_deref(),
+                end_wpt_stop_duration: end_wpt.stop_duration,
+                end_wpt_time: end_wpt.time,
+            }
+        })
+        .collect();
+    let clock_start = route.boundaries(kind).next().and_then(|w| w.time);
+    Some((resolved, clock_start))
+}
 
-    let clock_start = boundary_wpts[0].time;
+/// Model state after replaying the covered part of the route.
+struct Replay<'p> {
+    /// Moving time predicted up to the current index.
+    predicted_so_far: f64,
+    /// Stop (and sleep) predicted at the end of each covered range, in order.
+    stops: Vec<f64>,
+    /// Range containing the current index; `resolved.len()` when past the last.
+    current_range: usize,
+    found_current: bool,
+    sleep_tracker: SleepTracker<'p>,
+    state: SegmentState,
+    /// Rest of a stop still being taken at the current checkpoint; 0 unless
+    /// the caller sets it before [`forward_etas`].
+    pending_stop_s: f64,
+}
 
-    // ── Phase 1: replay covered intervals at the original pace ──���──────────────
-    let mut state = SegmentState {
-        d_eff_m: 0.0,
-        elapsed_s: 0.0,
-    };
-    let mut predicted_so_far = 0.0_f64;
-    let mut predicted_stops_so_far = 0.0_f64;
-    let mut current_range: usize = resolved.len();
-    let mut found_current = false;
-
-    let ap = AdvanceParams {
-        pace_s_per_km: base_pace_s_per_km,
-        k_fatigue,
-        clock_start,
-        life_base_stop_s,
-        weather,
+/// Phase 1: replay covered ranges up to `current_index` at `ap`'s pace,
+/// forwarding every covered segment to `on_segment`.
+fn replay_covered<'p>(
+    trace: &Trace,
+    resolved: &[ResolvedRange<'_>],
+    current_index: usize,
+    ap: &AdvanceParams<'_>,
+    sleep: &'p SleepPlan,
+    mut on_segment: Option<SegmentSink<'_>>,
+) -> Replay<'p> {
+    let mut replay = Replay {
+        predicted_so_far: 0.0,
+        stops: Vec::new(),
+        current_range: resolved.len(),
+        found_current: false,
+        sleep_tracker: SleepTracker::new(sleep),
+        state: SegmentState::new(),
+        pending_stop_s: 0.0,
     };
 
     for (idx, rs) in resolved.iter().enumerate() {
@@ -201,69 +253,81 @@ pub fn recalibrate_from_current(
             let (moving, stop) = advance_range(
                 trace,
                 rs,
-                rs.start_index,
-                rs.end_index,
-                &ap,
-                true,
-                &mut state,
+                rs.start_index..rs.end_index,
+                ap,
+                &mut replay.sleep_tracker,
+                &mut replay.state,
+                on_segment
+                    .as_mut()
+                    .map(|sink| &mut **sink as SegmentSink<'_>),
             );
-            predicted_so_far += moving;
-            predicted_stops_so_far += stop;
+            replay.predicted_so_far += moving;
+            replay.stops.push(stop);
         } else {
-            current_range = idx;
-            found_current = true;
+            replay.current_range = idx;
+            replay.found_current = true;
             if current_index > rs.start_index {
                 let (moving, _) = advance_range(
                     trace,
                     rs,
-                    rs.start_index,
-                    current_index,
-                    &ap,
-                    false,
-                    &mut state,
+                    rs.start_index..current_index,
+                    ap,
+                    &mut replay.sleep_tracker,
+                    &mut replay.state,
+                    on_segment
+                        .as_mut()
+                        .map(|sink| &mut **sink as SegmentSink<'_>),
                 );
-                predicted_so_far += moving;
+                replay.predicted_so_far += moving;
             }
             break;
         }
     }
+    replay
+}
 
-    // ── Solve calibration factor ───────────────────────────────────────────────
-    // Compare moving time to moving time: strip planned stops already incurred.
-    let calibration_factor = {
-        let moving_elapsed = actual_elapsed_s - predicted_stops_so_far;
-        if moving_elapsed > 0.0 && predicted_so_far >= MIN_CALIBRATION_PREDICTION_S {
-            (moving_elapsed / predicted_so_far).clamp(CALIBRATION_MIN, CALIBRATION_MAX)
-        } else {
-            1.0
-        }
-    };
-    let calibrated_pace = base_pace_s_per_km * calibration_factor;
-
-    // ── Phase 2: forward-predict remaining intervals at the calibrated pace ────
-    // Re-seed the circadian clock to the runner's real elapsed time.
-    state.elapsed_s = actual_elapsed_s;
-    let ap2 = AdvanceParams {
-        pace_s_per_km: calibrated_pace,
-        k_fatigue,
-        clock_start,
-        life_base_stop_s,
-        weather,
-    };
-
+/// Phase 2: forward-predict the remaining ranges with `ap`, re-seeding the
+/// circadian clock to the runner's real elapsed time. A pending stop delays
+/// every remaining arrival.
+fn forward_etas(
+    trace: &Trace,
+    resolved: &[ResolvedRange<'_>],
+    mut replay: Replay<'_>,
+    current_index: usize,
+    actual_elapsed_s: f64,
+    ap: &AdvanceParams<'_>,
+) -> Vec<RecalibratedEta> {
+    replay.state.race_s = actual_elapsed_s + replay.pending_stop_s;
     let mut etas: Vec<RecalibratedEta> = Vec::with_capacity(resolved.len());
-    let mut cumulative = 0.0_f64;
+    let mut cumulative = replay.pending_stop_s;
 
     for (idx, rs) in resolved.iter().enumerate() {
-        let is_remaining = idx > current_range || (idx == current_range && found_current);
+        let is_remaining =
+            idx > replay.current_range || (idx == replay.current_range && replay.found_current);
+        let mut cutoff = None;
+        let mut stop_s = 0.0;
         let remaining = if is_remaining {
-            let from = if idx == current_range && current_index > rs.start_index {
+            let from = if idx == replay.current_range && current_index > rs.start_index {
                 current_index
             } else {
                 rs.start_index
             };
-            let (moving, stop) =
-                advance_range(trace, rs, from, rs.end_index, &ap2, true, &mut state);
+            let (moving, stop) = advance_range(
+                trace,
+                rs,
+                from..rs.end_index,
+                ap,
+                &mut replay.sleep_tracker,
+                &mut replay.state,
+                None,
+            );
+            // Cutoffs gate arrival, so the stop taken there doesn't count.
+            cutoff = cutoff::assess(
+                ap.clock_start,
+                rs.end_wpt_time,
+                actual_elapsed_s + cumulative + moving,
+            );
+            stop_s = stop;
             let r = moving + stop;
             cumulative += r;
             r
@@ -276,21 +340,120 @@ pub fn recalibrate_from_current(
             end_index: rs.end_index,
             remaining_duration_s: remaining,
             cumulative_remaining_s: if remaining > 0.0 { cumulative } else { 0.0 },
+            stop_s,
+            cutoff_slack_min: cutoff.map(|(slack, _)| slack),
+            cutoff_risk: cutoff.map(|(_, risk)| risk),
         });
     }
+    etas
+}
+
+/// Recalibrate remaining-interval ETAs from the runner's live progress.
+///
+/// `kind` selects section- vs stage-granularity boundaries. `current_index` is the
+/// runner's current trace point; `actual_elapsed_s` is real seconds elapsed since race start.
+///
+/// Phase 1 replays covered intervals at the original `base_pace_s_per_km` to get the
+/// model's prediction so far. Phase 2 forward-predicts remaining intervals at the
+/// calibrated pace, re-seeding the circadian clock to `actual_elapsed_s`.
+///
+/// Returns `None` when fewer than 2 boundaries exist.
+pub fn recalibrate_from_current(
+    trace: &Trace,
+    waypoints: &[Waypoint],
+    kind: BoundaryKind,
+    current_index: usize,
+    actual_elapsed_s: f64,
+    options: &AnalysisOptions,
+) -> Option<Recalibration> {
+    recalibrate_on_route(
+        &ResolvedRoute::new(trace, waypoints),
+        kind,
+        current_index,
+        actual_elapsed_s,
+        options,
+    )
+}
+
+/// [`recalibrate_from_current`] on a route resolved once, for callers
+/// recalibrating every few seconds.
+pub fn recalibrate_on_route(
+    route: &ResolvedRoute<'_>,
+    kind: BoundaryKind,
+    current_index: usize,
+    actual_elapsed_s: f64,
+    options: &AnalysisOptions,
+) -> Option<Recalibration> {
+    let trace = route.trace();
+    let (resolved, clock_start) = resolve_ranges(route, &kind)?;
+    let surfaces = route.surfaces().overlaid(&options.surfaces);
+    Some(recalibrate_resolved(
+        trace,
+        &resolved,
+        clock_start,
+        &surfaces,
+        current_index,
+        actual_elapsed_s,
+        options,
+    ))
+}
 
-    Some(Recalibration {
+/// [`recalibrate_from_current`] over ranges from [`resolve_ranges`] and
+/// surfaces already resolved.
+pub(crate) fn recalibrate_resolved(
+    trace: &Trace,
+    resolved: &[ResolvedRange<'_>],
+    clock_start: Option<i64>,
+    surfaces: &SurfaceMap,
+    current_index: usize,
+    actual_elapsed_s: f64,
+    options: &AnalysisOptions,
+) -> Recalibration {
+    let ap = AdvanceParams::new(options, clock_start, surfaces);
+
+    // ── Phase 1: replay covered intervals at the original pace ─────────────────
+    let replay = replay_covered(trace, resolved, current_index, &ap, &options.sleep, None);
+    let predicted_so_far = replay.predicted_so_far;
+
+    // ── Solve calibration factor ───────────────────────────────────────────────
+    // Compare moving time to moving time: strip planned stops already incurred.
+    let calibration_factor = {
+        let moving_elapsed = actual_elapsed_s - replay.stops.iter().sum::<f64>();
+        if moving_elapsed > 0.0 && predicted_so_far >= MIN_CALIBRATION_PREDICTION_S {
+            (moving_elapsed / predicted_so_far).clamp(CALIBRATION_MIN, CALIBRATION_MAX)
+        } else {
+            1.0
+        }
+    };
+    let calibrated_pace = options.base_pace_s_per_km * calibration_factor;
+
+    // ── Phase 2: forward-predict remaining intervals at the calibrated pace ────
+    let ap2 = AdvanceParams {
+        pace_s_per_km: calibrated_pace,
+        ..ap
+    };
+    let etas = forward_etas(
+        trace,
+        resolved,
+        replay,
+        current_index,
+        actual_elapsed_s,
+        &ap2,
+    );
+
+    Recalibration {
         calibration_factor,
         calibrated_base_pace_s_per_km: calibrated_pace,
         predicted_so_far_s: predicted_so_far,
         actual_elapsed_s,
         etas,
-    })
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::location::Location;
 
     fn default_opts() -> AnalysisOptions {
         AnalysisOptions::default().life_base_stop(0)
@@ -319,6 +482,8 @@ mod tests {
             wpt_type: wpt_type.map(String::from),
             time: None,
             stop_duration: None,
+            surface: None,
+            crew: false,
         }
     }
 
@@ -333,6 +498,36 @@ mod tests {
         ]
     }
 
+    #[test]
+    fn not_before_sleep_fires_at_the_same_checkpoint_as_sections() {
+        const T0: i64 = 1_763_640_000;
+        let trace = build_flat_trace(30);
+        let mut waypoints = recal_waypoints();
+        waypoints[0].time = Some(T0);
+        // A long LifeBase stop puts the race clock 3 h ahead of moving time.
+        let options = AnalysisOptions::default().life_base_stop(3 * 3600);
+        let plain = crate::section::compute_from_waypoints(&trace, &waypoints, &options).unwrap();
+        // Due just before LB2 on the race clock, hours after it on moving time.
+        let due = T0 + plain[2].predicted_arrival_s as i64 - 60;
+        let options =
+            options.sleep(SleepPlan::new().block(sleep::SleepBlock::not_before(due, 3600)));
+
+        let sections =
+            crate::section::compute_from_waypoints(&trace, &waypoints, &options).unwrap();
+        let slept: Vec<usize> = sections
+            .iter()
+            .filter(|s| s.sleep_s > 0.0)
+            .map(|s| s.section_id)
+            .collect();
+        assert_eq!(slept, vec![2]);
+
+        let cal =
+            recalibrate_from_current(&trace, &waypoints, BoundaryKind::Section, 0, 0.0, &options)
+                .unwrap();
+        let stops: Vec<f64> = cal.etas.iter().map(|e| e.stop_s).collect();
+        assert_eq!(stops, vec![0.0, 3.0 * 3600.0, 4.0 * 3600.0, 0.0]);
+    }
+
     #[test]
     fn returns_none_with_fewer_than_two_boundaries() {
         let trace = build_flat_trace(30);
@@ -417,6 +612,42 @@ mod tests {
         assert!(result.etas[3].cumulative_remaining_s > result.etas[2].cumulative_remaining_s);
     }
 
+    #[test]
+    fn recalibrated_cutoff_slack_shrinks_for_a_slow_runner() {
+        const T0: i64 = 1_763_606_000;
+        let trace = build_flat_trace(30);
+        let mut waypoints = recal_waypoints();
+        waypoints[0].time = Some(T0);
+        for w in &mut waypoints[1..] {
+            w.time = Some(T0 + 5_400);
+        }
+        let run = |elapsed| {
+            recalibrate_from_current(
+                &trace,
+                &waypoints,
+                BoundaryKind::Section,
+                15,
+                elapsed,
+                &default_opts(),
+            )
+            .unwrap()
+        };
+        let fast = run(600.0);
+        let slow = run(3000.0);
+
+        assert_eq!(fast.etas[0].cutoff_slack_min, None);
+        assert_eq!(fast.etas[1].cutoff_risk, None);
+
+        let eta = &slow.etas[2];
+        let expected = (5_400.0 - (3000.0 + eta.cumulative_remaining_s)) / 60.0;
+        assert!((eta.cutoff_slack_min.unwrap() - expected).abs() < 1e-6);
+
+        let (f, s) = (&fast.etas[3], &slow.etas[3]);
+        assert!(s.cutoff_slack_min.unwrap() < f.cutoff_slack_min.unwrap());
+        assert_eq!(f.cutoff_risk, Some(CutoffRisk::Safe));
+        assert_eq!(s.cutoff_risk, Some(CutoffRisk::Missed));
+    }
+
     #[test]
     fn stage_kind_ignores_time_barriers() {
         let trace = build_flat_trace(30);
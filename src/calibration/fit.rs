@@ -0,0 +1,554 @@
+//! Fit personal pace-model parameters from recorded activities.
+//!
+//! Every activity is replayed through a Minetti model with one multiplier per
+//! slope band ([`SlopeBandModel`]). For a fixed fatigue coefficient the
+//! predicted moving time of each ~0.5 km window is linear in
+//! `base_pace × band multiplier`, so those are solved by ordinary least
+//! squares; `k_fatigue` is then found by golden-section search on the
+//! residual sum of squares. Stopped time (aid stations, photos) is detected
+//! from the raw samples and excluded.
+
+use crate::elevation::cumulative_horizontal_distances;
+use crate::gpx::TimedTrack;
+use crate::minetti;
+use crate::pace_model::{AnalysisOptions, PaceModel};
+use crate::solar;
+use crate::trace::Trace;
+
+/// Upper edges (slope fraction) of the slope bands; the last band is open-ended.
+pub const SLOPE_BAND_EDGES: [f64; 6] = [-0.20, -0.10, -0.03, 0.03, 0.10, 0.20];
+pub const SLOPE_BAND_COUNT: usize = SLOPE_BAND_EDGES.len() + 1;
+/// The −3 %…+3 % band. Its multiplier is pinned to 1.0 so base pace stays identifiable.
+const FLAT_BAND: usize = 3;
+
+/// Raw samples slower than this (horizontal m/s) count as stopped time.
+pub const MIN_MOVING_SPEED_M_S: f64 = 0.15;
+/// Moving distance accumulated into one least-squares observation (km).
+const FIT_WINDOW_KM: f64 = 0.5;
+/// Search range for the fatigue coefficient.
+pub const K_FATIGUE_FIT_MAX: f64 = 0.01;
+/// Fitted band multipliers are clamped to this range — sparse bands are noisy.
+const BAND_ADJUSTMENT_MIN: f64 = 0.5;
+const BAND_ADJUSTMENT_MAX: f64 = 2.0;
+const GOLDEN_ITERATIONS: usize = 40;
+/// Band multipliers feed back into d_eff, so the linear solve is repeated.
+const REFIT_PASSES: usize = 3;
+
+/// Index of the slope band containing `slope_frac`.
+pub fn slope_band(slope_frac: f64) -> usize {
+    SLOPE_BAND_EDGES.partition_point(|&edge| edge <= slope_frac)
+}
+
+/// Minetti terrain cost scaled by a per-slope-band multiplier.
+///
+/// Fatigue, circadian and weather keep their built-in behaviour.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SlopeBandModel {
+    /// Multiplier per band, indexed by [`slope_band`]. 1.0 = plain Minetti.
+    pub adjustments: [f64; SLOPE_BAND_COUNT],
+}
+
+impl Default for SlopeBandModel {
+    fn default() -> Self {
+        SlopeBandModel {
+            adjustments: [1.0; SLOPE_BAND_COUNT],
+        }
+    }
+}
+
+impl PaceModel for SlopeBandModel {
+    fn terrain_factor(&self, slope_frac: f64) -> f64 {
+        minetti::pace_factor(slope_frac) * self.adjustments[slope_band(slope_frac)]
+    }
+}
+
+/// Fitted parameters and goodness of fit. Caller owns this value.
+pub struct PaceFit {
+    /// `base` options with the fitted pace, fatigue and [`SlopeBandModel`] applied.
+    pub options: AnalysisOptions,
+    pub base_pace_s_per_km: f64,
+    pub k_fatigue: f64,
+    pub model: SlopeBandModel,
+    /// Coefficient of determination over all windows.
+    pub r_squared: f64,
+    /// Root-mean-square window error (s).
+    pub rmse_s: f64,
+    /// Signed error of predicted vs. recorded moving time per usable track (%).
+    pub track_errors_pct: Vec<f64>,
+    /// Number of least-squares observations.
+    pub windows: usize,
+}
+
+/// One working-trace segment of a recorded activity.
+struct FitSegment {
+    dist_km: f64,
+    slope_frac: f64,
+    /// Recorded moving time; `None` when a timestamp inside is missing.
+    moving_s: Option<f64>,
+    /// Local solar time at the segment start (an epoch shifted by the
+    /// longitude offset), for the circadian factor.
+    clock: Option<i64>,
+}
+
+/// Observations for one fatigue coefficient: per-window band features and
+/// recorded moving time.
+struct Design {
+    rows: Vec<[f64; SLOPE_BAND_COUNT]>,
+    observed: Vec<f64>,
+    track_of_row: Vec<usize>,
+}
+
+struct Solution {
+    base_pace_s_per_km: f64,
+    model: SlopeBandModel,
+    sse: f64,
+    design: Design,
+}
+
+/// Fit base pace, fatigue coefficient and per-slope-band Minetti multipliers
+/// to `tracks` by least squares.
+///
+/// `base` supplies everything that is not fitted (LifeBase stop, weather).
+/// Tracks with fewer than two points or mismatched `times` are skipped.
+/// Returns `None` when no usable moving time remains or no flat ground was
+/// recorded (base pace is then not identifiable).
+pub fn fit_pace_model(tracks: &[TimedTrack], base: &AnalysisOptions) -> Option<PaceFit> {
+    let prepared: Vec<Vec<FitSegment>> = tracks.iter().filter_map(prepare_track).collect();
+    if prepared.is_empty() {
+        return None;
+    }
+
+    // ── Golden-section search on k_fatigue ─────────────────────────────────────
+    let inv_phi = (5.0_f64.sqrt() - 1.0) / 2.0;
+    let (mut lo, mut hi) = (0.0_f64, K_FATIGUE_FIT_MAX);
+    let mut x1 = hi - inv_phi * (hi - lo);
+    let mut x2 = lo + inv_phi * (hi - lo);
+    let mut f1 = solve_for_k(&prepared, x1).map_or(f64::INFINITY, |s| s.sse);
+    let mut f2 = solve_for_k(&prepared, x2).map_or(f64::INFINITY, |s| s.sse);
+    for _ in 0..GOLDEN_ITERATIONS {
+        if f1 <= f2 {
+            hi = x2;
+            x2 = x1;
+            f2 = f1;
+            x1 = hi - inv_phi * (hi - lo);
+            f1 = solve_for_k(&prepared, x1).map_or(f64::INFINITY, |s| s.sse);
+        } else {
+            lo = x1;
+            x1 = x2;
+            f1 = f2;
+            x2 = lo + inv_phi * (hi - lo);
+            f2 = solve_for_k(&prepared, x2).map_or(f64::INFINITY, |s| s.sse);
+        }
+    }
+    let k_fatigue = (lo + hi) / 2.0;
+    let solution = solve_for_k(&prepared, k_fatigue)?;
+
+    // ── Goodness of fit ────────────────────────────────────────────────────────
+    let design = &solution.design;
+    let n = design.observed.len();
+    let mean = design.observed.iter().sum::<f64>() / n as f64;
+    let sst: f64 = design.observed.iter().map(|y| (y - mean).powi(2)).sum();
+    let r_squared = if sst > 0.0 {
+        1.0 - solution.sse / sst
+    } else {
+        1.0
+    };
+
+    let mut predicted_by_track = vec![0.0_f64; prepared.len()];
+    let mut observed_by_track = vec![0.0_f64; prepared.len()];
+    for ((row, &y), &t) in design
+        .rows
+        .iter()
+        .zip(&design.observed)
+        .zip(&design.track_of_row)
+    {
+        predicted_by_track[t] += predict(row, solution.base_pace_s_per_km, &solution.model);
+        observed_by_track[t] += y;
+    }
+    let track_errors_pct = predicted_by_track
+        .iter()
+        .zip(&observed_by_track)
+        .filter(|(_, &obs)| obs > 0.0)
+        .map(|(pred, obs)| (pred - obs) / obs * 100.0)
+        .collect();
+
+    Some(PaceFit {
+        options: base
+            .clone()
+            .base_pace(solution.base_pace_s_per_km)
+            .fatigue(k_fatigue)
+            .pace_model(solution.model),
+        base_pace_s_per_km: solution.base_pace_s_per_km,
+        k_fatigue,
+        model: solution.model,
+        r_squared,
+        rmse_s: (solution.sse / n as f64).sqrt(),
+        track_errors_pct,
+        windows: n,
+    })
+}
+
+/// Build the working trace for one activity and measure the moving time of
+/// each of its segments from the raw samples.
+fn prepare_track(track: &TimedTrack) -> Option<Vec<FitSegment>> {
+    if track.locations.len() < 2 || track.times.len() != track.locations.len() {
+        return None;
+    }
+    let trace = Trace::new(&track.locations).ok()?;
+    let raw_cum = cumulative_horizontal_distances(&track.locations);
+    let src = trace.source_indices();
+
+    let segments = (0..trace.locations.len() - 1)
+        .map(|j| {
+            let mut moving_s = Some(0.0_f64);
+            for k in src[j]..src[j + 1] {
+                match (track.times[k], track.times[k + 1]) {
+                    (Some(t0), Some(t1)) => {
+                        let dt = (t1 - t0) as f64;
+                        let dist_m = (raw_cum[k + 1] - raw_cum[k]) * 1000.0;
+                        if dt > 0.0 && dist_m / dt >= MIN_MOVING_SPEED_M_S {
+                            moving_s = moving_s.map(|m| m + dt);
+                        }
+                    }
+                    _ => moving_s = None,
+                }
+            }
+            FitSegment {
+                dist_km: trace.cumulative_distances[j + 1] - trace.cumulative_distances[j],
+                slope_frac: trace.slopes[j] / 100.0,
+                moving_s,
+                clock: track.times[src[j]]
+                    .map(|t| t + solar::local_solar_offset_s(trace.locations[j].longitude, t)),
+            }
+        })
+        .collect();
+    Some(segments)
+}
+
+/// Window features for fatigue coefficient `k` under `model`.
+fn build_design(tracks: &[Vec<FitSegment>], model: &SlopeBandModel, k: f64) -> Design {
+    let mut design = Design {
+        rows: Vec::new(),
+        observed: Vec::new(),
+        track_of_row: Vec::new(),
+    };
+
+    for (t, segments) in tracks.iter().enumerate() {
+        let mut d_eff_m = 0.0_f64;
+        let mut row = [0.0_f64; SLOPE_BAND_COUNT];
+        let mut observed = 0.0_f64;
+        let mut window_km = 0.0_f64;
+
+        for seg in segments {
+            if let Some(moving_s) = seg.moving_s.filter(|&m| m > 0.0) {
+                let fatigue = model.fatigue_factor(d_eff_m / 1000.0, k);
+                let circadian = seg.clock.map_or(1.0, |c| model.circadian_factor(c));
+                row[slope_band(seg.slope_frac)] +=
+                    seg.dist_km * minetti::pace_factor(seg.slope_frac) * fatigue * circadian;
+                observed += moving_s;
+                window_km += seg.dist_km;
+                if window_km >= FIT_WINDOW_KM {
+                    design.rows.push(row);
+                    design.observed.push(observed);
+                    design.track_of_row.push(t);
+                    row = [0.0; SLOPE_BAND_COUNT];
+                    observed = 0.0;
+                    window_km = 0.0;
+                }
+            }
+            d_eff_m += seg.dist_km * 1000.0 * model.terrain_factor(seg.slope_frac);
+        }
+        if window_km > 0.0 {
+            design.rows.push(row);
+            design.observed.push(observed);
+            design.track_of_row.push(t);
+        }
+    }
+
+    design
+}
+
+fn predict(row: &[f64; SLOPE_BAND_COUNT], base_pace_s_per_km: f64, model: &SlopeBandModel) -> f64 {
+    row.iter()
+        .zip(&model.adjustments)
+        .map(|(x, a)| x * a * base_pace_s_per_km)
+        .sum()
+}
+
+/// Best base pace and band multipliers for a fixed `k`, or `None` when the
+/// flat band has no data.
+fn solve_for_k(tracks: &[Vec<FitSegment>], k: f64) -> Option<Solution> {
+    let mut model = SlopeBandModel::default();
+    let mut base_pace_s_per_km = 0.0_f64;
+    let mut design = build_design(tracks, &model, k);
+
+    for pass in 0..REFIT_PASSES {
+        if pass > 0 {
+            design = build_design(tracks, &model, k);
+        }
+        let coeffs = least_squares(&design.rows, &design.observed)?;
+        base_pace_s_per_km = coeffs[FLAT_BAND]?;
+        if base_pace_s_per_km <= 0.0 {
+            return None;
+        }
+        for (adj, c) in model.adjustments.iter_mut().zip(&coeffs) {
+            *adj = c.map_or(1.0, |c| {
+                (c / base_pace_s_per_km).clamp(BAND_ADJUSTMENT_MIN, BAND_ADJUSTMENT_MAX)
+            });
+        }
+        model.adjustments[FLAT_BAND] = 1.0;
+    }
+
+    let sse = design
+        .rows
+        .iter()
+        .zip(&design.observed)
+        .map(|(row, y)| (y - predict(row, base_pace_s_per_km, &model)).powi(2))
+        .sum();
+    Some(Solution {
+        base_pace_s_per_km,
+        model,
+        sse,
+        design,
+    })
+}
+
+/// Ordinary least squares via the normal equations. Bands without data are
+/// left out and reported as `None`.
+fn least_squares(
+    rows: &[[f64; SLOPE_BAND_COUNT]],
+    observed: &[f64],
+) -> Option<[Option<f64>; SLOPE_BAND_COUNT]> {
+    let mut ata = [[0.0_f64; SLOPE_BAND_COUNT]; SLOPE_BAND_COUNT];
+    let mut aty = [0.0_f64; SLOPE_BAND_COUNT];
+    for (row, &y) in rows.iter().zip(observed) {
+        for i in 0..SLOPE_BAND_COUNT {
+            aty[i] += row[i] * y;
+            for j in 0..SLOPE_BAND_COUNT {
+                ata[i][j] += row[i] * row[j];
+            }
+        }
+    }
+
+    let active: Vec<usize> = (0..SLOPE_BAND_COUNT)
+        .filter(|&i| ata[i][i] > 1e-12)
+        .collect();
+    let n = active.len();
+    if n == 0 {
+        return None;
+    }
+    // Augmented matrix over the active bands, solved by Gaussian elimination
+    // with partial pivoting.
+    let mut m: Vec<Vec<f64>> = active
+        .iter()
+        .map(|&i| {
+            let mut r: Vec<f64> = active.iter().map(|&j| ata[i][j]).collect();
+            r.push(aty[i]);
+            r
+        })
+        .collect();
+    for col in 0..n {
+        let pivot = (col..n).max_by(|&a, &b| m[a][col].abs().total_cmp(&m[b][col].abs()))?;
+        if m[pivot][col].abs() < 1e-12 {
+            return None;
+        }
+        m.swap(col, pivot);
+        let (upper, lower) = m.split_at_mut(col + 1);
+        let pivot_row = &upper[col];
+        for row in lower.iter_mut() {
+            let f = row[col] / pivot_row[col];
+            for (cell, p) in row[col..].iter_mut().zip(&pivot_row[col..]) {
+                *cell -= f * p;
+            }
+        }
+    }
+    let mut x = vec![0.0_f64; n];
+    for r in (0..n).rev() {
+        let tail: f64 = (r + 1..n).map(|c| m[r][c] * x[c]).sum();
+        x[r] = (m[r][n] - tail) / m[r][r];
+    }
+
+    let mut coeffs = [None; SLOPE_BAND_COUNT];
+    for (&band, value) in active.iter().zip(x) {
+        coeffs[band] = Some(value);
+    }
+    Some(coeffs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::location::Location;
+    use crate::pace_model::WEATHER_NEUTRAL;
+    use crate::segment::{self, SegmentParams, SegmentState};
+
+    const T0: i64 = 1_763_640_000; // 2025-11-20T12:00:00Z
+
+    fn route(n: usize) -> Vec<Location> {
+        route_at(n, 0.0)
+    }
+
+    fn route_at(n: usize, longitude: f64) -> Vec<Location> {
+        (0..n)
+            .map(|i| Location {
+                longitude,
+                latitude: i as f64 * 0.0005,
+                altitude: 500.0 + 150.0 * (i as f64 / 25.0).sin(),
+            })
+            .collect()
+    }
+
+    /// Record `locations` as if run by `model`, pausing `stop_s` after `stop_at`.
+    fn record(
+        locations: Vec<Location>,
+        model: &SlopeBandModel,
+        pace: f64,
+        k: f64,
+        start: i64,
+        stop_at: usize,
+        stop_s: i64,
+    ) -> TimedTrack {
+        let trace = Trace::new(&locations).unwrap();
+        let params = SegmentParams {
+            base_pace_s_per_km: pace,
+            k_fatigue: k,
+            clock_start: Some(start),
+            weather: WEATHER_NEUTRAL,
+            forecast: None,
+            pace_model: model,
+            darkness: false,
+            surfaces: None,
+            rested_until: None,
+            weather_scale: 1.0,
+            altitude_acclimatization_m: None,
+        };
+        let mut state = SegmentState::new();
+        let mut times = vec![Some(start)];
+        let mut paused = 0;
+        for j in 0..locations.len() - 1 {
+            segment::compute(&trace, j, j + 1, &params, &mut state);
+            if j == stop_at {
+                paused = stop_s;
+                state.race_s += stop_s as f64;
+            }
+            times.push(Some(start + state.elapsed_s.round() as i64 + paused));
+        }
+        TimedTrack { locations, times }
+    }
+
+    fn truth() -> SlopeBandModel {
+        SlopeBandModel {
+            adjustments: [1.3, 1.1, 0.9, 1.0, 1.05, 1.2, 1.0],
+        }
+    }
+
+    #[test]
+    fn slope_bands_partition_the_grade_axis() {
+        assert_eq!(slope_band(-0.5), 0);
+        assert_eq!(slope_band(-0.15), 1);
+        assert_eq!(slope_band(0.0), FLAT_BAND);
+        assert_eq!(slope_band(0.03), 4);
+        assert_eq!(slope_band(0.5), SLOPE_BAND_COUNT - 1);
+    }
+
+    #[test]
+    fn default_band_model_is_minetti() {
+        let m = SlopeBandModel::default();
+        for s in [-0.3, -0.05, 0.0, 0.08, 0.25] {
+            assert_eq!(m.terrain_factor(s), minetti::pace_factor(s));
+        }
+    }
+
+    #[test]
+    fn recovers_synthetic_parameters() {
+        let model = truth();
+        let tracks = vec![
+            record(route(600), &model, 420.0, 0.004, T0, 300, 900),
+            record(route(450), &model, 420.0, 0.004, T0 + 14 * 3600, 100, 600),
+        ];
+        let fit = fit_pace_model(&tracks, &AnalysisOptions::default()).unwrap();
+
+        assert!(
+            (fit.base_pace_s_per_km - 420.0).abs() < 10.0,
+            "pace {}",
+            fit.base_pace_s_per_km
+        );
+        assert!((fit.k_fatigue - 0.004).abs() < 0.001, "k {}", fit.k_fatigue);
+        for band in [1, 2, 4, 5] {
+            let got = fit.model.adjustments[band];
+            let want = model.adjustments[band];
+            assert!((got - want).abs() < 0.05, "band {band}: {got} vs {want}");
+        }
+        assert!(fit.r_squared > 0.95, "r² {}", fit.r_squared);
+        assert_eq!(fit.track_errors_pct.len(), 2);
+        for e in &fit.track_errors_pct {
+            assert!(e.abs() < 2.0, "track error {e}%");
+        }
+        assert_eq!(fit.options.base_pace_s_per_km, fit.base_pace_s_per_km);
+        assert_eq!(fit.options.k_fatigue, fit.k_fatigue);
+    }
+
+    #[test]
+    fn circadian_clock_is_local_solar_time() {
+        // 120° E: an 18:00 UTC start is 02:00 local, in the circadian low.
+        let model = SlopeBandModel::default();
+        let start = T0 + 6 * 3600;
+        let tracks = vec![record(
+            route_at(600, 120.0),
+            &model,
+            420.0,
+            0.002,
+            start,
+            0,
+            0,
+        )];
+        let fit = fit_pace_model(&tracks, &AnalysisOptions::default()).unwrap();
+        assert!(
+            (fit.base_pace_s_per_km - 420.0).abs() < 5.0,
+            "pace {}",
+            fit.base_pace_s_per_km
+        );
+        assert!(fit.track_errors_pct[0].abs() < 1.0);
+    }
+
+    #[test]
+    fn stops_are_not_counted_as_moving_time() {
+        let model = SlopeBandModel::default();
+        let clean = vec![record(route(400), &model, 480.0, 0.002, T0, 0, 0)];
+        let paused = vec![record(route(400), &model, 480.0, 0.002, T0, 200, 3600)];
+        let a = fit_pace_model(&clean, &AnalysisOptions::default()).unwrap();
+        let b = fit_pace_model(&paused, &AnalysisOptions::default()).unwrap();
+        assert!((a.base_pace_s_per_km - b.base_pace_s_per_km).abs() < 10.0);
+    }
+
+    #[test]
+    fn unusable_tracks_yield_none() {
+        let untimed = TimedTrack {
+            locations: route(50),
+            times: vec![None; 50],
+        };
+        let mismatched = TimedTrack {
+            locations: route(50),
+            times: vec![Some(T0); 10],
+        };
+        assert!(fit_pace_model(&[], &AnalysisOptions::default()).is_none());
+        assert!(fit_pace_model(&[untimed, mismatched], &AnalysisOptions::default()).is_none());
+    }
+
+    #[test]
+    fn least_squares_solves_exact_system() {
+        let mut rows = Vec::new();
+        let mut observed = Vec::new();
+        for i in 0..10 {
+            let mut row = [0.0; SLOPE_BAND_COUNT];
+            row[FLAT_BAND] = 1.0 + i as f64;
+            row[5] = (i % 3) as f64;
+            observed.push(400.0 * row[FLAT_BAND] + 600.0 * row[5]);
+            rows.push(row);
+        }
+        let c = least_squares(&rows, &observed).unwrap();
+        assert!((c[FLAT_BAND].unwrap() - 400.0).abs() < 1e-6);
+        assert!((c[5].unwrap() - 600.0).abs() < 1e-6);
+        assert!(c[0].is_none());
+    }
+}
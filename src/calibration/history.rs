@@ -0,0 +1,513 @@
+//! Recalibrate from the runner's recorded track so far.
+//!
+//! Where [`recalibrate_from_current`](super::recalibrate_from_current) solves
+//! one factor from a single elapsed time, this replays the timed track: every
+//! stretch between two recorded fixes is compared with the model's moving
+//! time over the same trace segments, split into downhill, flat and uphill
+//! ground. Stretches far behind the runner count for less, so a change of
+//! form late in the race shows up quickly. Forward ETAs carry a band that
+//! narrows as the share of the race already run grows.
+
+use super::fit::MIN_MOVING_SPEED_M_S;
+use super::{
+    forward_etas, replay_covered, resolve_ranges, AdvanceParams, BoundaryKind, RecalibratedEta,
+    CALIBRATION_MAX, CALIBRATION_MIN, MIN_CALIBRATION_PREDICTION_S,
+};
+use crate::gpx::TimedTrack;
+use crate::pace_model::{AnalysisOptions, PaceFactors, PaceModel, WeatherConditions};
+use crate::route::ResolvedRoute;
+use crate::surface::Surface;
+use crate::trace::Trace;
+use crate::waypoint::Waypoint;
+
+/// Slopes within ±3 % count as flat — the fitted flat band of [`super::fit`].
+pub const FLAT_GRADE_FRAC: f64 = 0.03;
+/// A stretch this far behind the runner counts half as much (km).
+pub const RECENCY_HALF_LIFE_KM: f64 = 20.0;
+/// Recorded fixes further than this from the trace are ignored (km).
+pub const MAX_SNAP_DISTANCE_KM: f64 = 0.25;
+/// Relative half-width of the ETA band at the start line…
+pub const ETA_SPREAD_START: f64 = 0.25;
+/// …shrinking linearly with progress to this at the finish.
+pub const ETA_SPREAD_FINISH: f64 = 0.05;
+
+/// Pace multipliers on top of the model, per grade.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "wasm", derive(serde::Serialize))]
+#[cfg_attr(feature = "wasm", serde(rename_all = "camelCase"))]
+pub struct GradeMultipliers {
+    /// Below −[`FLAT_GRADE_FRAC`].
+    pub downhill: f64,
+    pub flat: f64,
+    /// Above +[`FLAT_GRADE_FRAC`].
+    pub uphill: f64,
+}
+
+impl Default for GradeMultipliers {
+    fn default() -> Self {
+        GradeMultipliers {
+            downhill: 1.0,
+            flat: 1.0,
+            uphill: 1.0,
+        }
+    }
+}
+
+impl GradeMultipliers {
+    /// Multiplier for a segment at `slope_frac` (rise/run, not %).
+    pub fn at(&self, slope_frac: f64) -> f64 {
+        [self.downhill, self.flat, self.uphill][grade(slope_frac)]
+    }
+}
+
+/// 0 = downhill, 1 = flat, 2 = uphill.
+fn grade(slope_frac: f64) -> usize {
+    if slope_frac < -FLAT_GRADE_FRAC {
+        0
+    } else if slope_frac > FLAT_GRADE_FRAC {
+        2
+    } else {
+        1
+    }
+}
+
+/// Recalibrated ETA with its uncertainty band.
+#[cfg_attr(feature = "wasm", derive(serde::Serialize))]
+#[cfg_attr(feature = "wasm", serde(rename_all = "camelCase"))]
+pub struct BandedEta {
+    #[cfg_attr(feature = "wasm", serde(flatten))]
+    pub eta: RecalibratedEta,
+    /// Low end of `cumulative_remaining_s` (0 for completed intervals).
+    pub earliest_remaining_s: f64,
+    /// High end of `cumulative_remaining_s` (0 for completed intervals).
+    pub latest_remaining_s: f64,
+}
+
+/// Result of a recalibration from a recorded track. Caller owns this value.
+#[cfg_attr(feature = "wasm", derive(serde::Serialize))]
+#[cfg_attr(feature = "wasm", serde(rename_all = "camelCase"))]
+pub struct HistoryRecalibration {
+    /// Fitted multipliers; a grade with under 300 s of recorded moving time
+    /// takes the all-grade factor, 1.0 when that is not trusted either.
+    pub multipliers: GradeMultipliers,
+    /// Trace index of the last recorded fix.
+    pub current_index: usize,
+    /// Seconds from the race start to the last recorded fix.
+    pub actual_elapsed_s: f64,
+    /// Model's moving-time prediction for the covered distance, unscaled.
+    pub predicted_so_far_s: f64,
+    /// Share of the predicted race time already run, 0–1.
+    pub progress: f64,
+    /// Relative half-width of the ETA band at this progress.
+    pub eta_spread: f64,
+    pub etas: Vec<BandedEta>,
+}
+
+/// Recalibrate remaining-interval ETAs from the runner's timed track so far.
+///
+/// Fixes without a `<time>` or more than [`MAX_SNAP_DISTANCE_KM`] from the
+/// trace are ignored; the rest are snapped forward along the trace. Stretches
+/// slower than [`MIN_MOVING_SPEED_M_S`] (stops, signal loss) are left out of
+/// the fit. The race starts at the first boundary's `<time>`, or at the
+/// first fix without one.
+///
+/// Remaining intervals are forward-predicted from the last fix as in
+/// [`recalibrate_from_current`](super::recalibrate_from_current), with the
+/// pace model scaled by the fitted [`GradeMultipliers`].
+///
+/// Returns `None` when fewer than 2 boundaries exist or no fix snaps onto the trace.
+pub fn recalibrate_from_history(
+    trace: &Trace,
+    waypoints: &[Waypoint],
+    kind: BoundaryKind,
+    history: &TimedTrack,
+    options: &AnalysisOptions,
+) -> Option<HistoryRecalibration> {
+    let route = ResolvedRoute::new(trace, waypoints);
+    let (resolved, clock_start) = resolve_ranges(&route, &kind)?;
+    let fixes = snap_history(trace, history);
+    let (&(_, first_time), &(current_index, now)) = (fixes.first()?, fixes.last()?);
+    let clock_start = clock_start.or(Some(first_time));
+    let actual_elapsed_s = clock_start.map_or(0.0, |t0| (now - t0).max(0) as f64);
+
+    let surfaces = route.surfaces().overlaid(&options.surfaces);
+    let ap = AdvanceParams::new(options, clock_start, &surfaces);
+
+    // Model moving time of every covered segment, at the original pace.
+    let mut predicted: Vec<Option<f64>> = vec![None; trace.locations.len()];
+    let replay = replay_covered(
+        trace,
+        &resolved,
+        current_index,
+        &ap,
+        &options.sleep,
+        Some(&mut |sample, _| predicted[sample.index] = Some(sample.time_s)),
+    );
+    let predicted_so_far_s = replay.predicted_so_far;
+    let multipliers = fit_grades(trace, &fixes, &predicted);
+
+    let model = GradeScaled {
+        inner: options.pace_model.as_ref(),
+        multipliers,
+    };
+    let ap2 = AdvanceParams {
+        pace_model: &model,
+        ..ap
+    };
+    let etas = forward_etas(
+        trace,
+        &resolved,
+        replay,
+        current_index,
+        actual_elapsed_s,
+        &ap2,
+    );
+
+    let remaining_s = etas
+        .iter()
+        .map(|e| e.cumulative_remaining_s)
+        .fold(0.0, f64::max);
+    let progress = if actual_elapsed_s + remaining_s > 0.0 {
+        actual_elapsed_s / (actual_elapsed_s + remaining_s)
+    } else {
+        0.0
+    };
+    let eta_spread = ETA_SPREAD_FINISH + (ETA_SPREAD_START - ETA_SPREAD_FINISH) * (1.0 - progress);
+
+    Some(HistoryRecalibration {
+        multipliers,
+        current_index,
+        actual_elapsed_s,
+        predicted_so_far_s,
+        progress,
+        eta_spread,
+        etas: etas
+            .into_iter()
+            .map(|eta| BandedEta {
+                earliest_remaining_s: eta.cumulative_remaining_s * (1.0 - eta_spread),
+                latest_remaining_s: eta.cumulative_remaining_s * (1.0 + eta_spread),
+                eta,
+            })
+            .collect(),
+    })
+}
+
+/// Timed fixes as `(trace index, epoch)`, never moving backwards along the
+/// trace or in time.
+fn snap_history(trace: &Trace, history: &TimedTrack) -> Vec<(usize, i64)> {
+    let mut fixes: Vec<(usize, i64)> = Vec::new();
+    let mut from = 0usize;
+    for (loc, time) in history.locations.iter().zip(&history.times) {
+        let Some(time) = *time else { continue };
+        if fixes.last().is_some_and(|&(_, last)| time < last) {
+            continue;
+        }
+        if let Some((_, idx, dist_km)) = trace.find_closest_point_from(loc, from) {
+            if dist_km <= MAX_SNAP_DISTANCE_KM {
+                from = idx;
+                fixes.push((idx, time));
+            }
+        }
+    }
+    fixes
+}
+
+/// Recency-weighted ratio of recorded to predicted moving time, per grade.
+///
+/// A stretch spanning several grades shares its recorded time in proportion
+/// to the predicted time on each.
+fn fit_grades(
+    trace: &Trace,
+    fixes: &[(usize, i64)],
+    predicted: &[Option<f64>],
+) -> GradeMultipliers {
+    let here_km = fixes
+        .last()
+        .map_or(0.0, |&(idx, _)| trace.cumulative_distances[idx]);
+    let mut recorded = [0.0_f64; 3];
+    let mut modelled = [0.0_f64; 3];
+    // Unweighted, for the trust gate.
+    let mut covered = [0.0_f64; 3];
+
+    for pair in fixes.windows(2) {
+        let ((a, t0), (b, t1)) = (pair[0], pair[1]);
+        let dt = (t1 - t0) as f64;
+        if b <= a || dt <= 0.0 {
+            continue;
+        }
+        let dist_m = (trace.cumulative_distances[b] - trace.cumulative_distances[a]) * 1000.0;
+        if dist_m / dt < MIN_MOVING_SPEED_M_S {
+            continue;
+        }
+        let mut by_grade = [0.0_f64; 3];
+        let mut complete = true;
+        for j in a..b {
+            match predicted[j] {
+                Some(t) => by_grade[grade(trace.slopes[j] / 100.0)] += t,
+                None => complete = false,
+            }
+        }
+        let total: f64 = by_grade.iter().sum();
+        if !complete || total <= 0.0 {
+            continue;
+        }
+        let weight = 0.5_f64.powf((here_km - trace.cumulative_distances[b]) / RECENCY_HALF_LIFE_KM);
+        for g in 0..3 {
+            recorded[g] += weight * dt * by_grade[g] / total;
+            modelled[g] += weight * by_grade[g];
+            covered[g] += by_grade[g];
+        }
+    }
+
+    let ratio = |recorded: f64, modelled: f64, covered: f64, fallback: f64| {
+        if covered >= MIN_CALIBRATION_PREDICTION_S && modelled > 0.0 {
+            (recorded / modelled).clamp(CALIBRATION_MIN, CALIBRATION_MAX)
+        } else {
+            fallback
+        }
+    };
+    let pooled = ratio(
+        recorded.iter().sum(),
+        modelled.iter().sum(),
+        covered.iter().sum(),
+        1.0,
+    );
+    let [downhill, flat, uphill] =
+        [0, 1, 2].map(|g| ratio(recorded[g], modelled[g], covered[g], pooled));
+    GradeMultipliers {
+        downhill,
+        flat,
+        uphill,
+    }
+}
+
+/// Delegates to the configured model, scaling its combined factor per grade.
+///
+/// The terrain factor is untouched, so the effort distance driving fatigue is too.
+#[derive(Debug)]
+struct GradeScaled<'a> {
+    inner: &'a dyn PaceModel,
+    multipliers: GradeMultipliers,
+}
+
+impl PaceModel for GradeScaled<'_> {
+    fn terrain_factor(&self, slope_frac: f64) -> f64 {
+        self.inner.terrain_factor(slope_frac)
+    }
+
+    fn fatigue_factor(&self, d_eff_km: f64, k_fatigue: f64) -> f64 {
+        self.inner.fatigue_factor(d_eff_km, k_fatigue)
+    }
+
+    fn circadian_factor(&self, solar_time_s: i64) -> f64 {
+        self.inner.circadian_factor(solar_time_s)
+    }
+
+    fn darkness_factor(&self, sun_elevation_deg: f64) -> f64 {
+        self.inner.darkness_factor(sun_elevation_deg)
+    }
+
+    fn altitude_factor(&self, altitude_m: f64, acclimatization_m: f64) -> f64 {
+        self.inner.altitude_factor(altitude_m, acclimatization_m)
+    }
+
+    fn surface_factor(&self, surface: Surface) -> f64 {
+        self.inner.surface_factor(surface)
+    }
+
+    fn weather_factor(&self, conditions: WeatherConditions) -> f64 {
+        self.inner.weather_factor(conditions)
+    }
+
+    fn weather_factor_along(&self, conditions: WeatherConditions, bearing_deg: f64) -> f64 {
+        self.inner.weather_factor_along(conditions, bearing_deg)
+    }
+
+    fn compute_factors(
+        &self,
+        slope_frac: f64,
+        d_eff_km: f64,
+        k_fatigue: f64,
+        clock_start: Option<i64>,
+        now_s: f64,
+        weather_factor: f64,
+    ) -> PaceFactors {
+        let factors = self.inner.compute_factors(
+            slope_frac,
+            d_eff_km,
+            k_fatigue,
+            clock_start,
+            now_s,
+            weather_factor,
+        );
+        PaceFactors {
+            combined: factors.combined * self.multipliers.at(slope_frac),
+            ..factors
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calibration::recalibrate_from_current;
+    use crate::location::Location;
+    use crate::timeline;
+
+    const T0: i64 = 1_763_625_600; // 08:00 UTC, outside the circadian window
+
+    /// 20 flat points, 20 climbing, 20 descending (~18 % grades).
+    fn hill_trace() -> Trace {
+        let locs: Vec<Location> = (0..61)
+            .map(|i| Location {
+                longitude: 0.0,
+                latitude: i as f64 * 0.001,
+                altitude: match i {
+                    0..=20 => 100.0,
+                    21..=40 => 100.0 + (i - 20) as f64 * 20.0,
+                    _ => 500.0 - (i - 40) as f64 * 20.0,
+                },
+            })
+            .collect();
+        Trace::new(&locs).unwrap()
+    }
+
+    fn make_waypoint(lat: f64, name: &str, wpt_type: &str) -> Waypoint {
+        Waypoint {
+            latitude: lat,
+            longitude: 0.0,
+            elevation: None,
+            name: name.to_string(),
+            description: None,
+            comment: None,
+            symbol: None,
+            wpt_type: Some(wpt_type.to_string()),
+            time: None,
+            stop_duration: None,
+            surface: None,
+            crew: false,
+        }
+    }
+
+    fn waypoints() -> Vec<Waypoint> {
+        vec![
+            make_waypoint(0.0, "Start", "Start"),
+            make_waypoint(0.060, "Arrival", "Arrival"),
+        ]
+    }
+
+    /// A fix at every trace point up to `through`, running the model's
+    /// segment times scaled by `pace(index)`.
+    fn recorded(trace: &Trace, through: usize, pace: impl Fn(usize) -> f64) -> TimedTrack {
+        let tl = timeline::compute(trace, &AnalysisOptions::default(), Some(T0));
+        let mut t = 0.0_f64;
+        let mut times = vec![Some(T0)];
+        for j in 0..through {
+            t += (tl.cumulative_time_s[j + 1] - tl.cumulative_time_s[j]) * pace(j);
+            times.push(Some(T0 + t.round() as i64));
+        }
+        TimedTrack {
+            locations: trace.locations[..=through].to_vec(),
+            times,
+        }
+    }
+
+    #[test]
+    fn fits_separate_multipliers_per_grade() {
+        let trace = hill_trace();
+        let pace = |j: usize| match grade(trace.slopes[j] / 100.0) {
+            0 => 1.4,
+            1 => 1.0,
+            _ => 0.8,
+        };
+        let history = recorded(&trace, 55, pace);
+        let opts = AnalysisOptions::default();
+        let result =
+            recalibrate_from_history(&trace, &waypoints(), BoundaryKind::Section, &history, &opts)
+                .unwrap();
+        let m = result.multipliers;
+        assert!((m.downhill - 1.4).abs() < 0.01, "{m:?}");
+        assert!((m.flat - 1.0).abs() < 0.01, "{m:?}");
+        assert!((m.uphill - 0.8).abs() < 0.01, "{m:?}");
+        assert_eq!(result.current_index, 55);
+
+        // The remaining descent is forecast at the slow downhill pace, which a
+        // single factor dominated by the fast climb underestimates.
+        let single = recalibrate_from_current(
+            &trace,
+            &waypoints(),
+            BoundaryKind::Section,
+            55,
+            result.actual_elapsed_s,
+            &opts,
+        )
+        .unwrap();
+        assert!(result.etas[0].eta.cumulative_remaining_s > single.etas[0].cumulative_remaining_s);
+    }
+
+    #[test]
+    fn recent_stretches_dominate_and_stops_are_ignored() {
+        let trace = hill_trace();
+        // Fast first half of the flat, slow second half.
+        let mut history = recorded(&trace, 20, |j| if j < 10 { 0.8 } else { 1.2 });
+        // A 30-minute stop at the last point.
+        let last = *history.locations.last().unwrap();
+        let end = history.times.last().unwrap().unwrap();
+        history.locations.push(last);
+        history.times.push(Some(end + 1800));
+
+        let result = recalibrate_from_history(
+            &trace,
+            &waypoints(),
+            BoundaryKind::Section,
+            &history,
+            &AnalysisOptions::default(),
+        )
+        .unwrap();
+        let flat = result.multipliers.flat;
+        assert!(flat > 1.0 && flat < 1.2, "{flat}");
+        // Nothing recorded on slopes, so they take the flat ground's factor.
+        assert_eq!(result.multipliers.uphill, flat);
+        assert_eq!(result.multipliers.downhill, flat);
+    }
+
+    #[test]
+    fn eta_band_narrows_as_the_race_progresses() {
+        let trace = hill_trace();
+        let run = |through| {
+            recalibrate_from_history(
+                &trace,
+                &waypoints(),
+                BoundaryKind::Section,
+                &recorded(&trace, through, |_| 1.0),
+                &AnalysisOptions::default(),
+            )
+            .unwrap()
+        };
+        let early = run(10);
+        let late = run(50);
+        assert!(late.progress > early.progress);
+        assert!(late.eta_spread < early.eta_spread);
+
+        let width = |r: &HistoryRecalibration| {
+            let eta = &r.etas[0];
+            assert!(eta.earliest_remaining_s <= eta.eta.cumulative_remaining_s);
+            assert!(eta.latest_remaining_s >= eta.eta.cumulative_remaining_s);
+            eta.latest_remaining_s - eta.earliest_remaining_s
+        };
+        assert!(width(&late) < width(&early));
+
+        let empty = TimedTrack {
+            locations: Vec::new(),
+            times: Vec::new(),
+        };
+        assert!(recalibrate_from_history(
+            &trace,
+            &waypoints(),
+            BoundaryKind::Section,
+            &empty,
+            &AnalysisOptions::default()
+        )
+        .is_none());
+    }
+}
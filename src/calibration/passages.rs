@@ -0,0 +1,427 @@
+//! Recalibrate from official timing-mat passages.
+//!
+//! Races publish when each runner crossed each checkpoint mat. Instead of a
+//! GPS index and an elapsed time, [`recalibrate_from_passages`] takes those
+//! `(waypoint name, epoch)` pairs: a section's actual duration runs from
+//! leaving one checkpoint to reaching the next, and two passages at the same
+//! checkpoint (mat in, mat out) are the actual dwell there.
+
+use super::{
+    forward_etas, replay_covered, resolve_ranges, AdvanceParams, BoundaryKind, Recalibration,
+    CALIBRATION_MAX, CALIBRATION_MIN, MIN_CALIBRATION_PREDICTION_S,
+};
+use crate::pace_model::AnalysisOptions;
+use crate::route::ResolvedRoute;
+use crate::trace::Trace;
+use crate::waypoint::Waypoint;
+
+/// One timing-mat crossing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "wasm", derive(serde::Deserialize))]
+#[cfg_attr(feature = "wasm", serde(rename_all = "camelCase"))]
+pub struct Passage {
+    /// Waypoint name, matched exactly.
+    pub name: String,
+    /// Epoch s.
+    pub time: i64,
+}
+
+impl Passage {
+    pub fn new(name: impl Into<String>, time: i64) -> Self {
+        Self {
+            name: name.into(),
+            time,
+        }
+    }
+}
+
+/// Actual timing over one passed interval.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "wasm", derive(serde::Serialize))]
+#[cfg_attr(feature = "wasm", serde(rename_all = "camelCase"))]
+pub struct ActualSection {
+    pub id: usize,
+    /// Leaving the start checkpoint to reaching the end one (s). `None` when
+    /// either time is unknown: a mat was missed, or the start checkpoint has
+    /// a planned stop but a single passage.
+    pub duration_s: Option<f64>,
+    /// Time between the first and last passage at the end checkpoint (s);
+    /// `None` with a single passage.
+    pub dwell_s: Option<f64>,
+}
+
+/// Result of a recalibration from passages. Caller owns this value.
+#[cfg_attr(feature = "wasm", derive(serde::Serialize))]
+#[cfg_attr(feature = "wasm", serde(rename_all = "camelCase"))]
+pub struct PassageRecalibration {
+    /// Calibration and forward ETAs from the last checkpoint passed, as from
+    /// [`recalibrate_from_current`](super::recalibrate_from_current).
+    #[cfg_attr(feature = "wasm", serde(flatten))]
+    pub recalibration: Recalibration,
+    /// One entry per interval up to the last checkpoint passed.
+    pub sections: Vec<ActualSection>,
+}
+
+/// Recalibrate remaining-interval ETAs from timing-mat passages.
+///
+/// Passages are matched to boundaries in route order, so a loop whose start
+/// and finish share a name or an out-and-back checkpoint is handled. The
+/// race starts at the last passage at the first boundary, or at its
+/// `<time>` without one. The runner is at the last boundary with a passage.
+/// The calibration factor compares actual to predicted moving time up to
+/// there: the dwell at each checkpoint passed is the actual one when it was
+/// crossed twice, the planned stop (and sleep) otherwise. A runner crossed
+/// only once at their current checkpoint is still taking its planned stop,
+/// which delays every remaining ETA.
+///
+/// Returns `None` when fewer than 2 boundaries exist or the start time is unknown.
+pub fn recalibrate_from_passages(
+    trace: &Trace,
+    waypoints: &[Waypoint],
+    kind: BoundaryKind,
+    passages: &[Passage],
+    options: &AnalysisOptions,
+) -> Option<PassageRecalibration> {
+    let route = ResolvedRoute::new(trace, waypoints);
+    let (resolved, clock_start) = resolve_ranges(&route, &kind)?;
+    // First and last crossing of each boundary: the start, then each range end.
+    let first = resolved.first()?;
+    let names: Vec<&str> = std::iter::once(first.start_wpt_name)
+        .chain(resolved.iter().map(|rs| rs.end_wpt_name))
+        .collect();
+    let crossings = match_passages(&names, passages);
+
+    let start_time = crossings[0].map(|(_, last)| last).or(clock_start)?;
+    let passed = crossings[1..].iter().rposition(Option::is_some);
+    let current_index = passed.map_or(first.start_index, |k| resolved[k].end_index);
+
+    let surfaces = route.surfaces().overlaid(&options.surfaces);
+    let ap = AdvanceParams::new(options, Some(start_time), &surfaces);
+    let mut replay = replay_covered(trace, &resolved, current_index, &ap, &options.sleep, None);
+    let predicted_so_far = replay.predicted_so_far;
+
+    // ── Actual timing of the passed intervals ──────────────────────────────────
+    let mut sections = Vec::new();
+    let mut dwell_so_far = 0.0_f64;
+    let mut departed = Some(start_time);
+    let mut actual_elapsed_s = 0.0_f64;
+    for (k, rs) in resolved
+        .iter()
+        .enumerate()
+        .take(passed.map_or(0, |k| k + 1))
+    {
+        let crossed = crossings[k + 1];
+        let dwell_s = crossed
+            .filter(|(first, last)| last > first)
+            .map(|(first, last)| (last - first) as f64);
+        sections.push(ActualSection {
+            id: rs.id,
+            duration_s: departed
+                .zip(crossed)
+                .map(|(left, (arrived, _))| (arrived - left) as f64),
+            dwell_s,
+        });
+
+        let planned_stop = replay.stops[k];
+        if let (true, Some((arrived, left))) = (Some(k) == passed, crossed) {
+            actual_elapsed_s = (left - start_time) as f64;
+            // Moving time stops at the mat in.
+            dwell_so_far += (left - arrived) as f64;
+            if dwell_s.is_none() {
+                replay.pending_stop_s = planned_stop;
+            }
+        } else {
+            dwell_so_far += dwell_s.unwrap_or(planned_stop);
+        }
+        departed = match (crossed, dwell_s) {
+            (Some((_, left)), Some(_)) => Some(left),
+            (Some((arrived, _)), None) if planned_stop == 0.0 => Some(arrived),
+            _ => None,
+        };
+    }
+
+    // ── Solve calibration factor ───────────────────────────────────────────────
+    let calibration_factor = {
+        let moving_elapsed = actual_elapsed_s - dwell_so_far;
+        if moving_elapsed > 0.0 && predicted_so_far >= MIN_CALIBRATION_PREDICTION_S {
+            (moving_elapsed / predicted_so_far).clamp(CALIBRATION_MIN, CALIBRATION_MAX)
+        } else {
+            1.0
+        }
+    };
+    let calibrated_pace = options.base_pace_s_per_km * calibration_factor;
+
+    let ap2 = AdvanceParams {
+        pace_s_per_km: calibrated_pace,
+        ..ap
+    };
+    let etas = forward_etas(
+        trace,
+        &resolved,
+        replay,
+        current_index,
+        actual_elapsed_s,
+        &ap2,
+    );
+
+    Some(PassageRecalibration {
+        recalibration: Recalibration {
+            calibration_factor,
+            calibrated_base_pace_s_per_km: calibrated_pace,
+            predicted_so_far_s: predicted_so_far,
+            actual_elapsed_s,
+            etas,
+        },
+        sections,
+    })
+}
+
+/// First and last crossing of each boundary in `names` (route order).
+///
+/// Passages are taken in time order and each is consumed once: a boundary
+/// matches the next passage with its name after those already matched, plus
+/// the passages with the same name that immediately follow it (mat in, mat
+/// out). A start and finish sharing a name, or a checkpoint visited twice on
+/// an out-and-back, thus get one crossing per visit.
+fn match_passages(names: &[&str], passages: &[Passage]) -> Vec<Option<(i64, i64)>> {
+    let mut sorted: Vec<&Passage> = passages.iter().collect();
+    sorted.sort_by_key(|p| p.time);
+    let mut next = 0;
+    names
+        .iter()
+        .map(|&name| {
+            let at = next + sorted[next..].iter().position(|p| p.name == name)?;
+            let visit = sorted[at..].iter().take_while(|p| p.name == name).count();
+            next = at + visit;
+            Some((sorted[at].time, sorted[next - 1].time))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calibration::recalibrate_from_current;
+    use crate::location::Location;
+
+    const T0: i64 = 1_763_625_600;
+
+    fn flat_trace() -> Trace {
+        let locs: Vec<Location> = (0..60)
+            .map(|i| Location {
+                longitude: 0.0,
+                latitude: i as f64 * 0.001,
+                altitude: 100.0,
+            })
+            .collect();
+        Trace::new(&locs).unwrap()
+    }
+
+    fn make_waypoint(lat: f64, name: &str, wpt_type: &str) -> Waypoint {
+        Waypoint {
+            latitude: lat,
+            longitude: 0.0,
+            elevation: None,
+            name: name.to_string(),
+            description: None,
+            comment: None,
+            symbol: None,
+            wpt_type: Some(wpt_type.to_string()),
+            time: None,
+            stop_duration: None,
+            surface: None,
+            crew: false,
+        }
+    }
+
+    fn waypoints() -> Vec<Waypoint> {
+        vec![
+            make_waypoint(0.000, "Start", "Start"),
+            make_waypoint(0.010, "TB1", "TimeBarrier"),
+            make_waypoint(0.020, "LB1", "LifeBase"),
+            make_waypoint(0.040, "LB2", "LifeBase"),
+            make_waypoint(0.059, "Arrival", "Arrival"),
+        ]
+    }
+
+    /// Model moving time from the start to trace index `index`.
+    fn predicted_to(trace: &Trace, index: usize, options: &AnalysisOptions) -> f64 {
+        recalibrate_from_current(
+            trace,
+            &waypoints(),
+            BoundaryKind::Section,
+            index,
+            0.0,
+            options,
+        )
+        .unwrap()
+        .predicted_so_far_s
+    }
+
+    #[test]
+    fn actual_dwell_replaces_the_planned_stop() {
+        let trace = flat_trace();
+        let options = AnalysisOptions::default().life_base_stop(3600);
+        let to_tb1 = predicted_to(&trace, 10, &options);
+        let to_lb1 = predicted_to(&trace, 20, &options);
+        let at = |s: f64| T0 + (1.5 * s).round() as i64;
+        let passages = vec![
+            Passage::new("Start", T0),
+            Passage::new("TB1", at(to_tb1)),
+            Passage::new("LB1", at(to_lb1)),
+            Passage::new("LB1", at(to_lb1) + 1200),
+        ];
+
+        let result = recalibrate_from_passages(
+            &trace,
+            &waypoints(),
+            BoundaryKind::Section,
+            &passages,
+            &options,
+        )
+        .unwrap();
+        let cal = &result.recalibration;
+        assert!((cal.calibration_factor - 1.5).abs() < 0.01);
+        assert_eq!(cal.actual_elapsed_s, (at(to_lb1) + 1200 - T0) as f64);
+        assert_eq!(result.sections.len(), 2);
+        assert_eq!(result.sections[0].dwell_s, None);
+        assert!((result.sections[0].duration_s.unwrap() - 1.5 * to_tb1).abs() < 1.0);
+        assert_eq!(result.sections[1].dwell_s, Some(1200.0));
+        assert_eq!(cal.etas[1].remaining_duration_s, 0.0);
+        assert!(cal.etas[2].remaining_duration_s > 0.0);
+
+        // From the elapsed time alone, the planned hour at LB1 is assumed.
+        let from_elapsed = recalibrate_from_current(
+            &trace,
+            &waypoints(),
+            BoundaryKind::Section,
+            20,
+            cal.actual_elapsed_s,
+            &options,
+        )
+        .unwrap();
+        assert!(from_elapsed.calibration_factor < 1.2);
+    }
+
+    #[test]
+    fn single_passage_at_the_current_checkpoint_keeps_the_planned_stop() {
+        let trace = flat_trace();
+        let options = AnalysisOptions::default().life_base_stop(1800);
+        let arrived = T0 + predicted_to(&trace, 20, &options).round() as i64;
+        let run = |passages: &[Passage]| {
+            recalibrate_from_passages(
+                &trace,
+                &waypoints(),
+                BoundaryKind::Section,
+                passages,
+                &options,
+            )
+            .unwrap()
+            .recalibration
+        };
+
+        let in_only = run(&[Passage::new("Start", T0), Passage::new("LB1", arrived)]);
+        let out = run(&[
+            Passage::new("Start", T0),
+            Passage::new("LB1", arrived),
+            Passage::new("LB1", arrived + 1800),
+        ]);
+        assert!((in_only.calibration_factor - out.calibration_factor).abs() < 1e-12);
+        let (a, b) = (&in_only.etas[3], &out.etas[3]);
+        assert!((a.cumulative_remaining_s - (b.cumulative_remaining_s + 1800.0)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn needs_a_start_time() {
+        let trace = flat_trace();
+        let passages = vec![Passage::new("TB1", T0 + 600)];
+        let run = |waypoints: &[Waypoint]| {
+            recalibrate_from_passages(
+                &trace,
+                waypoints,
+                BoundaryKind::Section,
+                &passages,
+                &AnalysisOptions::default(),
+            )
+        };
+        assert!(run(&waypoints()).is_none());
+
+        let mut timed = waypoints();
+        timed[0].time = Some(T0);
+        let result = run(&timed).unwrap();
+        assert_eq!(result.recalibration.actual_elapsed_s, 600.0);
+        assert_eq!(result.sections.len(), 1);
+    }
+
+    #[test]
+    fn start_and_arrival_may_share_a_name() {
+        let trace = flat_trace();
+        let mut loop_wpts = waypoints();
+        loop_wpts[0].name = "Chamonix".into();
+        loop_wpts[4].name = "Chamonix".into();
+        let options = AnalysisOptions::default();
+        let at_tb1 = T0 + predicted_to(&trace, 10, &options).round() as i64;
+        let passages = vec![Passage::new("Chamonix", T0), Passage::new("TB1", at_tb1)];
+
+        let result = recalibrate_from_passages(
+            &trace,
+            &loop_wpts,
+            BoundaryKind::Section,
+            &passages,
+            &options,
+        )
+        .unwrap();
+        let cal = &result.recalibration;
+        assert_eq!(cal.actual_elapsed_s, (at_tb1 - T0) as f64);
+        assert_eq!(result.sections.len(), 1);
+        assert!((cal.calibration_factor - 1.0).abs() < 0.01);
+        assert!(cal.etas.last().unwrap().remaining_duration_s > 0.0);
+    }
+
+    #[test]
+    fn out_and_back_checkpoint_gets_a_dwell_per_visit() {
+        // Out to 0.030 and back, slightly offset so the two legs stay apart.
+        let locs: Vec<Location> = (0..=30)
+            .map(|i| (i as f64 * 0.001, 0.0))
+            .chain((0..30).rev().map(|i| (i as f64 * 0.001, 0.0002)))
+            .map(|(latitude, longitude)| Location {
+                longitude,
+                latitude,
+                altitude: 100.0,
+            })
+            .collect();
+        let trace = Trace::new(&locs).unwrap();
+        let mut back = make_waypoint(0.010, "A", "LifeBase");
+        back.longitude = 0.0002;
+        let mut arrival = make_waypoint(0.000, "Start", "Arrival");
+        arrival.longitude = 0.0002;
+        let waypoints = vec![
+            make_waypoint(0.000, "Start", "Start"),
+            make_waypoint(0.010, "A", "LifeBase"),
+            make_waypoint(0.030, "B", "TimeBarrier"),
+            back,
+            arrival,
+        ];
+        let passages = vec![
+            Passage::new("Start", T0),
+            Passage::new("A", T0 + 600),
+            Passage::new("A", T0 + 1200),
+            Passage::new("B", T0 + 3000),
+            Passage::new("A", T0 + 4800),
+            Passage::new("A", T0 + 5100),
+        ];
+
+        let result = recalibrate_from_passages(
+            &trace,
+            &waypoints,
+            BoundaryKind::Section,
+            &passages,
+            &AnalysisOptions::default(),
+        )
+        .unwrap();
+        let dwells: Vec<_> = result.sections.iter().map(|s| s.dwell_s).collect();
+        assert_eq!(dwells, [Some(600.0), None, Some(300.0)]);
+        assert_eq!(result.sections[2].duration_s, Some(1800.0));
+        assert_eq!(result.recalibration.actual_elapsed_s, 5100.0);
+    }
+}
@@ -0,0 +1,386 @@
+//! Crew rendezvous planning at crew-accessible aid stations.
+//!
+//! Arrival windows (earliest / likely / latest, from the section model or a
+//! recalibration) at the waypoints flagged [`Waypoint::crew`] become an
+//! itinerary: when the crew must be in place at each point, and whether the
+//! drive to the next one — from a caller-supplied matrix — gets them there
+//! before the runner.
+
+use crate::calibration::history::HistoryRecalibration;
+use crate::calibration::{self, BoundaryKind, RecalibratedEta, Recalibration};
+use crate::section::SectionStats;
+use crate::waypoint::Waypoint;
+
+/// Time the crew needs in place before the runner's earliest arrival (s).
+pub const DEFAULT_SETUP_S: f64 = 900.0;
+
+/// When the runner reaches one checkpoint, in seconds from the race start.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "wasm", derive(serde::Serialize))]
+#[cfg_attr(feature = "wasm", serde(rename_all = "camelCase"))]
+pub struct ArrivalWindow {
+    /// Checkpoint name.
+    pub name: String,
+    pub earliest_s: f64,
+    pub likely_s: f64,
+    pub latest_s: f64,
+    /// Planned stop (and sleep) after arriving (s).
+    pub stop_s: f64,
+}
+
+impl ArrivalWindow {
+    fn around(name: &str, from_s: f64, to_arrival_s: f64, spread: f64, stop_s: f64) -> Self {
+        ArrivalWindow {
+            name: name.to_string(),
+            earliest_s: from_s + to_arrival_s * (1.0 - spread),
+            likely_s: from_s + to_arrival_s,
+            latest_s: from_s + to_arrival_s * (1.0 + spread),
+            stop_s,
+        }
+    }
+}
+
+/// Windows at each section's end checkpoint, `spread` (a fraction, e.g.
+/// 0.25) either side of the predicted arrival.
+pub fn windows_from_sections(sections: &[SectionStats], spread: f64) -> Vec<ArrivalWindow> {
+    let mut departure_s = 0.0_f64;
+    sections
+        .iter()
+        .map(|s| {
+            // Each section starts when the previous one's stop ends.
+            departure_s += s.estimated_duration_s;
+            ArrivalWindow::around(
+                &s.end_location,
+                0.0,
+                s.predicted_arrival_s,
+                spread,
+                departure_s - s.predicted_arrival_s,
+            )
+        })
+        .collect()
+}
+
+/// Windows at the checkpoints still ahead of a live recalibration, `spread`
+/// either side of the remaining time to each. `waypoints` and `kind` must be
+/// the ones it was computed with.
+pub fn windows_from_recalibration(
+    recalibration: &Recalibration,
+    waypoints: &[Waypoint],
+    kind: BoundaryKind,
+    spread: f64,
+) -> Vec<ArrivalWindow> {
+    windows_ahead(
+        recalibration.etas.iter(),
+        recalibration.actual_elapsed_s,
+        waypoints,
+        &kind,
+        spread,
+    )
+}
+
+/// Windows at the checkpoints still ahead of a history recalibration, over
+/// its own ETA band.
+pub fn windows_from_history(
+    recalibration: &HistoryRecalibration,
+    waypoints: &[Waypoint],
+    kind: BoundaryKind,
+) -> Vec<ArrivalWindow> {
+    windows_ahead(
+        recalibration.etas.iter().map(|e| &e.eta),
+        recalibration.actual_elapsed_s,
+        waypoints,
+        &kind,
+        recalibration.eta_spread,
+    )
+}
+
+fn windows_ahead<'a>(
+    etas: impl Iterator<Item = &'a RecalibratedEta>,
+    elapsed_s: f64,
+    waypoints: &[Waypoint],
+    kind: &BoundaryKind,
+    spread: f64,
+) -> Vec<ArrivalWindow> {
+    etas.filter(|eta| eta.remaining_duration_s > 0.0)
+        .filter_map(|eta| {
+            let wpt = calibration::end_waypoint(waypoints, kind, eta.id)?;
+            Some(ArrivalWindow::around(
+                &wpt.name,
+                elapsed_s,
+                eta.cumulative_remaining_s - eta.stop_s,
+                spread,
+                eta.stop_s,
+            ))
+        })
+        .collect()
+}
+
+/// Drive times between crew points, supplied by the caller.
+#[derive(Debug, Clone, Default)]
+pub struct DriveTimes {
+    names: Vec<String>,
+    seconds: Vec<Vec<Option<f64>>>,
+}
+
+impl DriveTimes {
+    /// `seconds[i][j]` is the drive from `names[i]` to `names[j]`; `None`
+    /// where no road connects them.
+    pub fn new(names: Vec<String>, seconds: Vec<Vec<Option<f64>>>) -> Self {
+        Self { names, seconds }
+    }
+
+    /// Drive from `from` to `to` (s); `None` when either is unknown or no road connects them.
+    pub fn between(&self, from: &str, to: &str) -> Option<f64> {
+        let i = self.names.iter().position(|n| n == from)?;
+        let j = self.names.iter().position(|n| n == to)?;
+        self.seconds.get(i)?.get(j).copied().flatten()
+    }
+}
+
+/// One crew point on the itinerary. Times are epoch seconds.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "wasm", derive(serde::Serialize))]
+#[cfg_attr(feature = "wasm", serde(rename_all = "camelCase"))]
+pub struct Rendezvous {
+    pub name: String,
+    /// Crew in place: the setup time before the earliest arrival.
+    pub be_there_by: i64,
+    pub earliest_arrival: i64,
+    pub likely_arrival: i64,
+    pub latest_arrival: i64,
+    /// Runner leaves after the planned stop, arriving on time.
+    pub likely_departure: i64,
+    /// Runner leaves after the planned stop, arriving at the latest.
+    pub latest_departure: i64,
+}
+
+/// How reliably the crew reaches the next point before the runner.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "wasm", derive(serde::Serialize))]
+#[cfg_attr(feature = "wasm", serde(rename_all = "camelCase"))]
+pub enum TransferRisk {
+    /// In place even after waiting for the runner's latest departure.
+    Safe,
+    /// In place only if the runner leaves on time.
+    Tight,
+    /// Late even if the runner leaves on time, or no road.
+    Impossible,
+}
+
+/// Drive between two consecutive crew points.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "wasm", derive(serde::Serialize))]
+#[cfg_attr(feature = "wasm", serde(rename_all = "camelCase"))]
+pub struct Transfer {
+    pub from: String,
+    pub to: String,
+    /// `None` when the matrix has no road between them.
+    pub drive_s: Option<f64>,
+    /// Next `be_there_by` minus (likely departure + drive) (s); negative is late.
+    pub slack_s: Option<f64>,
+    pub risk: TransferRisk,
+}
+
+/// Crew points in route order and the transfers between them. Caller owns this value.
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "wasm", derive(serde::Serialize))]
+#[cfg_attr(feature = "wasm", serde(rename_all = "camelCase"))]
+pub struct CrewItinerary {
+    pub stops: Vec<Rendezvous>,
+    /// `transfers[i]` goes from `stops[i]` to `stops[i + 1]`.
+    pub transfers: Vec<Transfer>,
+}
+
+impl CrewItinerary {
+    /// No transfer is [`TransferRisk::Impossible`].
+    pub fn is_feasible(&self) -> bool {
+        self.transfers
+            .iter()
+            .all(|t| t.risk != TransferRisk::Impossible)
+    }
+}
+
+/// Plan the crew's day from `windows` at the waypoints flagged
+/// [`Waypoint::crew`]; windows elsewhere are skipped.
+///
+/// `start_time` (epoch s) anchors the windows. The crew must be in place
+/// `setup_s` before each earliest arrival (see [`DEFAULT_SETUP_S`]).
+pub fn plan(
+    waypoints: &[Waypoint],
+    windows: &[ArrivalWindow],
+    drive_times: &DriveTimes,
+    start_time: i64,
+    setup_s: f64,
+) -> CrewItinerary {
+    let at = |s: f64| start_time + s.round() as i64;
+    let stops: Vec<Rendezvous> = windows
+        .iter()
+        .filter(|w| waypoints.iter().any(|wpt| wpt.crew && wpt.name == w.name))
+        .map(|w| Rendezvous {
+            name: w.name.clone(),
+            be_there_by: at(w.earliest_s - setup_s),
+            earliest_arrival: at(w.earliest_s),
+            likely_arrival: at(w.likely_s),
+            latest_arrival: at(w.latest_s),
+            likely_departure: at(w.likely_s + w.stop_s),
+            latest_departure: at(w.latest_s + w.stop_s),
+        })
+        .collect();
+
+    let transfers = stops
+        .windows(2)
+        .map(|pair| {
+            let (from, to) = (&pair[0], &pair[1]);
+            let drive_s = drive_times.between(&from.name, &to.name);
+            let slack_s = drive_s.map(|d| (to.be_there_by - from.likely_departure) as f64 - d);
+            let risk = match drive_s {
+                Some(d) if (to.be_there_by - from.latest_departure) as f64 >= d => {
+                    TransferRisk::Safe
+                }
+                Some(_) if slack_s.is_some_and(|s| s >= 0.0) => TransferRisk::Tight,
+                _ => TransferRisk::Impossible,
+            };
+            Transfer {
+                from: from.name.clone(),
+                to: to.name.clone(),
+                drive_s,
+                slack_s,
+                risk,
+            }
+        })
+        .collect();
+
+    CrewItinerary { stops, transfers }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calibration::recalibrate_from_current;
+    use crate::location::Location;
+    use crate::pace_model::AnalysisOptions;
+    use crate::section;
+    use crate::trace::Trace;
+
+    const T0: i64 = 1_763_625_600;
+
+    fn flat_trace() -> Trace {
+        let locs: Vec<Location> = (0..81)
+            .map(|i| Location {
+                longitude: 0.0,
+                latitude: i as f64 * 0.001,
+                altitude: 100.0,
+            })
+            .collect();
+        Trace::new(&locs).unwrap()
+    }
+
+    fn make_waypoint(lat: f64, name: &str, wpt_type: &str, crew: bool) -> Waypoint {
+        Waypoint {
+            latitude: lat,
+            longitude: 0.0,
+            elevation: None,
+            name: name.to_string(),
+            description: None,
+            comment: None,
+            symbol: None,
+            wpt_type: Some(wpt_type.to_string()),
+            time: None,
+            stop_duration: None,
+            surface: None,
+            crew,
+        }
+    }
+
+    fn waypoints() -> Vec<Waypoint> {
+        vec![
+            make_waypoint(0.000, "Start", "Start", false),
+            make_waypoint(0.020, "A", "TimeBarrier", true),
+            make_waypoint(0.040, "Col", "TimeBarrier", false),
+            make_waypoint(0.060, "B", "LifeBase", true),
+            make_waypoint(0.080, "Finish", "Arrival", false),
+        ]
+    }
+
+    fn drive(a_to_b: Option<f64>) -> DriveTimes {
+        DriveTimes::new(
+            vec!["A".into(), "B".into(), "Finish".into()],
+            vec![
+                vec![Some(0.0), a_to_b, Some(1800.0)],
+                vec![a_to_b, Some(0.0), Some(600.0)],
+                vec![Some(1800.0), Some(600.0), Some(0.0)],
+            ],
+        )
+    }
+
+    #[test]
+    fn section_windows_bracket_the_prediction_and_carry_the_stop() {
+        let options = AnalysisOptions::default().life_base_stop(1800);
+        let sections =
+            section::compute_from_waypoints(&flat_trace(), &waypoints(), &options).unwrap();
+        let windows = windows_from_sections(&sections, 0.2);
+        assert_eq!(windows.len(), 4);
+        assert_eq!(windows[2].name, "B");
+        assert!((windows[2].stop_s - 1800.0).abs() < 1e-6);
+        assert_eq!(windows[0].stop_s, 0.0);
+        let w = &windows[2];
+        assert!((w.likely_s - sections[2].predicted_arrival_s).abs() < 1e-9);
+        assert!((w.earliest_s - 0.8 * w.likely_s).abs() < 1e-9);
+        assert!((w.latest_s - 1.2 * w.likely_s).abs() < 1e-9);
+    }
+
+    #[test]
+    fn itinerary_visits_crew_points_and_flags_transfers() {
+        let options = AnalysisOptions::default().life_base_stop(1800);
+        let sections =
+            section::compute_from_waypoints(&flat_trace(), &waypoints(), &options).unwrap();
+        let windows = windows_from_sections(&sections, 0.1);
+
+        let roomy = plan(&waypoints(), &windows, &drive(Some(300.0)), T0, 0.0);
+        let names: Vec<&str> = roomy.stops.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["A", "B"]);
+        assert_eq!(roomy.transfers.len(), 1);
+        assert_eq!(roomy.transfers[0].risk, TransferRisk::Safe);
+        assert!(roomy.is_feasible());
+        let b = &roomy.stops[1];
+        assert_eq!(b.likely_departure - b.likely_arrival, 1800);
+
+        // Makes it only when the runner leaves A on time.
+        let a = &roomy.stops[0];
+        let gap = (b.be_there_by - a.likely_departure) as f64;
+        let tight = plan(&waypoints(), &windows, &drive(Some(gap - 1.0)), T0, 0.0);
+        assert_eq!(tight.transfers[0].risk, TransferRisk::Tight);
+        assert!((tight.transfers[0].slack_s.unwrap() - 1.0).abs() < 1e-9);
+
+        let late = plan(&waypoints(), &windows, &drive(Some(gap + 60.0)), T0, 0.0);
+        assert_eq!(late.transfers[0].risk, TransferRisk::Impossible);
+        assert!(!late.is_feasible());
+
+        let no_road = plan(&waypoints(), &windows, &drive(None), T0, 0.0);
+        assert_eq!(no_road.transfers[0].risk, TransferRisk::Impossible);
+        assert_eq!(no_road.transfers[0].slack_s, None);
+    }
+
+    #[test]
+    fn recalibration_windows_cover_checkpoints_ahead() {
+        let trace = flat_trace();
+        let options = AnalysisOptions::default().life_base_stop(1800);
+        let rec = recalibrate_from_current(
+            &trace,
+            &waypoints(),
+            BoundaryKind::Section,
+            30,
+            2400.0,
+            &options,
+        )
+        .unwrap();
+        let windows = windows_from_recalibration(&rec, &waypoints(), BoundaryKind::Section, 0.1);
+        let names: Vec<&str> = windows.iter().map(|w| w.name.as_str()).collect();
+        assert_eq!(names, ["Col", "B", "Finish"]);
+        let b = &windows[1];
+        assert!((b.stop_s - 1800.0).abs() < 1e-6);
+        let eta = &rec.etas[2];
+        assert!((b.likely_s - (2400.0 + eta.cumulative_remaining_s - 1800.0)).abs() < 1e-9);
+        assert!(b.earliest_s > 2400.0);
+    }
+}
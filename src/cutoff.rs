@@ -0,0 +1,89 @@
+//! Absolute cutoff analysis.
+//!
+//! A checkpoint's `<time>` is a wall-clock barrier counted from the race start
+//! (the first boundary waypoint's `<time>`). Slack is the margin between that
+//! barrier and the predicted arrival, before any planned stop is taken there.
+
+/// Slack below this many minutes is [`CutoffRisk::AtRisk`].
+pub const AT_RISK_SLACK_MIN: f64 = 30.0;
+
+/// Slack below this many minutes is [`CutoffRisk::Tight`].
+pub const TIGHT_SLACK_MIN: f64 = 60.0;
+
+/// How close a predicted arrival is to a checkpoint's cutoff.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "wasm", derive(serde::Serialize))]
+#[cfg_attr(feature = "wasm", serde(rename_all = "camelCase"))]
+pub enum CutoffRisk {
+    /// At least [`TIGHT_SLACK_MIN`] of slack.
+    Safe,
+    /// Between [`AT_RISK_SLACK_MIN`] and [`TIGHT_SLACK_MIN`] of slack.
+    Tight,
+    /// Less than [`AT_RISK_SLACK_MIN`] of slack, but still ahead of the cutoff.
+    AtRisk,
+    /// Predicted arrival is after the cutoff.
+    Missed,
+}
+
+impl CutoffRisk {
+    pub fn from_slack_min(slack_min: f64) -> Self {
+        if slack_min < 0.0 {
+            CutoffRisk::Missed
+        } else if slack_min < AT_RISK_SLACK_MIN {
+            CutoffRisk::AtRisk
+        } else if slack_min < TIGHT_SLACK_MIN {
+            CutoffRisk::Tight
+        } else {
+            CutoffRisk::Safe
+        }
+    }
+}
+
+/// Slack (minutes) and risk for arriving `arrival_elapsed_s` after `race_start`
+/// at a checkpoint whose absolute cutoff is `cutoff`.
+///
+/// `None` when either timestamp is missing.
+pub(crate) fn assess(
+    race_start: Option<i64>,
+    cutoff: Option<i64>,
+    arrival_elapsed_s: f64,
+) -> Option<(f64, CutoffRisk)> {
+    let budget_s = (cutoff? - race_start?) as f64;
+    let slack_min = (budget_s - arrival_elapsed_s) / 60.0;
+    Some((slack_min, CutoffRisk::from_slack_min(slack_min)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn risk_levels_follow_thresholds() {
+        assert_eq!(CutoffRisk::from_slack_min(120.0), CutoffRisk::Safe);
+        assert_eq!(
+            CutoffRisk::from_slack_min(TIGHT_SLACK_MIN),
+            CutoffRisk::Safe
+        );
+        assert_eq!(CutoffRisk::from_slack_min(45.0), CutoffRisk::Tight);
+        assert_eq!(CutoffRisk::from_slack_min(10.0), CutoffRisk::AtRisk);
+        assert_eq!(CutoffRisk::from_slack_min(0.0), CutoffRisk::AtRisk);
+        assert_eq!(CutoffRisk::from_slack_min(-0.1), CutoffRisk::Missed);
+    }
+
+    #[test]
+    fn assess_counts_from_race_start() {
+        let (slack, risk) = assess(Some(1_000), Some(1_000 + 7_200), 3_600.0).unwrap();
+        assert!((slack - 60.0).abs() < 1e-9);
+        assert_eq!(risk, CutoffRisk::Safe);
+
+        let (slack, risk) = assess(Some(1_000), Some(1_000 + 3_600), 4_200.0).unwrap();
+        assert!((slack + 10.0).abs() < 1e-9);
+        assert_eq!(risk, CutoffRisk::Missed);
+    }
+
+    #[test]
+    fn assess_needs_both_timestamps() {
+        assert!(assess(None, Some(3_600), 0.0).is_none());
+        assert!(assess(Some(0), None, 0.0).is_none());
+    }
+}
@@ -15,6 +15,23 @@ pub struct Elevation {
     pub negative: f64,
 }
 
+/// How [`crate::Trace::with_elevation_fill`] resolves locations whose altitude
+/// is missing (see [`Location::has_altitude`]).
+#[derive(Debug, Clone, Copy, Default)]
+pub enum ElevationFill {
+    /// Linear interpolation in distance between the nearest known neighbours;
+    /// leading/trailing gaps copy the nearest known altitude. A trace with no
+    /// known altitude at all is flattened to 0 m.
+    #[default]
+    Interpolate,
+    /// Set every missing altitude to 0 m.
+    Zero,
+    /// Look each missing point up in a caller-supplied elevation model
+    /// (`None` when the DEM has no data there). Points the DEM cannot
+    /// resolve fall back to `Interpolate`.
+    Dem(fn(&Location) -> Option<f64>),
+}
+
 /// Cumulative elevation gain/loss computed on the denoised signal.
 #[derive(Debug)]
 pub struct GainLoss {
@@ -40,6 +57,78 @@ pub fn cumulative_horizontal_distances(locations: &[Location]) -> Vec<f64> {
     cum
 }
 
+/// Fill missing (`NaN`) altitudes in place according to `fill`.
+///
+/// Returns a mask aligned with `locations` that is `true` where the altitude
+/// was synthesised (interpolated or zeroed). DEM lookups count as measured
+/// data and are not flagged.
+pub fn fill_missing_altitudes(locations: &mut [Location], fill: ElevationFill) -> Vec<bool> {
+    let mut synthetic: Vec<bool> = locations.iter().map(|l| !l.has_altitude()).collect();
+    if !synthetic.contains(&true) {
+        return synthetic;
+    }
+
+    match fill {
+        ElevationFill::Zero => {
+            for (loc, _) in locations.iter_mut().zip(&synthetic).filter(|(_, &s)| s) {
+                loc.altitude = 0.0;
+            }
+        }
+        ElevationFill::Dem(lookup) => {
+            for (loc, s) in locations.iter_mut().zip(synthetic.iter_mut()) {
+                if !*s {
+                    continue;
+                }
+                if let Some(altitude) = lookup(loc).filter(|a| !a.is_nan()) {
+                    loc.altitude = altitude;
+                    *s = false;
+                }
+            }
+            interpolate_missing(locations);
+        }
+        ElevationFill::Interpolate => interpolate_missing(locations),
+    }
+    synthetic
+}
+
+/// Linear-in-distance interpolation of `NaN` altitudes between known neighbours.
+fn interpolate_missing(locations: &mut [Location]) {
+    let known: Vec<usize> = (0..locations.len())
+        .filter(|&i| locations[i].has_altitude())
+        .collect();
+    let (first, last) = match (known.first(), known.last()) {
+        (Some(&first), Some(&last)) => (first, last),
+        _ => {
+            for loc in locations.iter_mut() {
+                loc.altitude = 0.0;
+            }
+            return;
+        }
+    };
+
+    let cum_dist = cumulative_horizontal_distances(locations);
+    for pair in known.windows(2) {
+        let (lo, hi) = (pair[0], pair[1]);
+        let span = cum_dist[hi] - cum_dist[lo];
+        let (alt_lo, alt_hi) = (locations[lo].altitude, locations[hi].altitude);
+        for i in lo + 1..hi {
+            let t = if span > 0.0 {
+                (cum_dist[i] - cum_dist[lo]) / span
+            } else {
+                0.0
+            };
+            locations[i].altitude = alt_lo + t * (alt_hi - alt_lo);
+        }
+    }
+    let (alt_first, alt_last) = (locations[first].altitude, locations[last].altitude);
+    for loc in &mut locations[..first] {
+        loc.altitude = alt_first;
+    }
+    for loc in &mut locations[last + 1..] {
+        loc.altitude = alt_last;
+    }
+}
+
 /// Returns the median of a mutable slice (sorts in place).
 fn median(scratch: &mut [f64]) -> f64 {
     scratch.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
@@ -54,7 +143,8 @@ fn median(scratch: &mut [f64]) -> f64 {
 /// Distance-windowed median smoother.
 /// For each point, gathers all altitude values whose cumulative distance lies
 /// within ±`radius_km`, then returns the median.  The window is capped at
-/// `MAX_WINDOW_SAMPLES` to bound memory usage.
+/// `MAX_WINDOW_SAMPLES` to bound memory usage.  Missing (`NaN`) altitudes are
+/// left out of the window; a window with no known altitude yields `NaN`.
 pub fn median_smooth(locations: &[Location], cum_dist: &[f64], radius_km: f64) -> Vec<f64> {
     debug_assert_eq!(locations.len(), cum_dist.len());
     let n = locations.len();
@@ -87,13 +177,31 @@ pub fn median_smooth(locations: &[Location], cum_dist: &[f64], radius_km: f64) -
 
         let count = win_hi - win_lo + 1;
         scratch[..count].copy_from_slice(&alt_cache[win_lo..win_lo + count]);
-        out[i] = median(&mut scratch[..count]);
+        let known = partition_known(&mut scratch[..count]);
+        out[i] = if known == 0 {
+            f64::NAN
+        } else {
+            median(&mut scratch[..known])
+        };
     }
     out
 }
 
+/// Moves the known (non-`NaN`) values to the front of `values`, returning their count.
+fn partition_known(values: &mut [f64]) -> usize {
+    let mut known = 0;
+    for i in 0..values.len() {
+        if !values[i].is_nan() {
+            values.swap(known, i);
+            known += 1;
+        }
+    }
+    known
+}
+
 /// Hysteresis accumulator: only register gain/loss when the elevation change
 /// exceeds `threshold_m`, eliminating GPS jitter from the D+/D− totals.
+/// Missing (`NaN`) elevations carry the cumulative totals forward unchanged.
 pub fn accumulate(elevations: &[f64], threshold_m: f64) -> GainLoss {
     let n = elevations.len();
     let mut cum_gain = vec![0.0f64; n];
@@ -110,10 +218,19 @@ pub fn accumulate(elevations: &[f64], threshold_m: f64) -> GainLoss {
 
     let mut gain = 0.0f64;
     let mut loss = 0.0f64;
-    let mut ref_elev = elevations[0];
+    let mut ref_elev = elevations
+        .iter()
+        .copied()
+        .find(|e| !e.is_nan())
+        .unwrap_or(f64::NAN);
 
     for i in 1..n {
         let e = elevations[i];
+        if e.is_nan() {
+            cum_gain[i] = gain;
+            cum_loss[i] = loss;
+            continue;
+        }
         if e > ref_elev + threshold_m {
             gain += e - ref_elev;
             ref_elev = e;
@@ -240,6 +357,14 @@ mod tests {
         }
     }
 
+    #[test]
+    fn accumulate_carries_totals_across_missing_elevations() {
+        let elevs = vec![f64::NAN, 0.0, f64::NAN, 20.0, f64::NAN];
+        let gl = accumulate(&elevs, 3.0);
+        assert_eq!(gl.cum_gain, vec![0.0, 0.0, 0.0, 20.0, 20.0]);
+        assert!(gl.total_loss.abs() < 1e-9);
+    }
+
     #[test]
     fn accumulate_empty_input() {
         let gl = accumulate(&[], 3.0);
@@ -271,6 +396,71 @@ mod tests {
         assert_eq!(gl.total_gain, 0.0);
     }
 
+    #[test]
+    fn median_smooth_ignores_missing_altitudes() {
+        let locations = vec![
+            loc(0.0, 0.0, 100.0),
+            loc(0.0001, 0.0, f64::NAN),
+            loc(0.0002, 0.0, 110.0),
+        ];
+        let cum = cumulative_horizontal_distances(&locations);
+        let smoothed = median_smooth(&locations, &cum, 0.05);
+        assert!(smoothed.iter().all(|e| (e - 105.0).abs() < 1e-9));
+    }
+
+    // — fill_missing_altitudes ────────────────────────────────────────────────
+
+    #[test]
+    fn interpolate_fills_gaps_linearly_and_flags_them() {
+        let mut locations = vec![
+            loc(0.0, 0.0, f64::NAN),
+            loc(0.001, 0.0, 100.0),
+            loc(0.002, 0.0, f64::NAN),
+            loc(0.003, 0.0, 200.0),
+            loc(0.004, 0.0, f64::NAN),
+        ];
+        let synthetic = fill_missing_altitudes(&mut locations, ElevationFill::Interpolate);
+        assert_eq!(synthetic, vec![true, false, true, false, true]);
+        assert!((locations[0].altitude - 100.0).abs() < 1e-9);
+        assert!((locations[2].altitude - 150.0).abs() < 1e-6);
+        assert!((locations[4].altitude - 200.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn interpolate_without_any_known_altitude_flattens_to_zero() {
+        let mut locations = vec![loc(0.0, 0.0, f64::NAN), loc(0.001, 0.0, f64::NAN)];
+        let synthetic = fill_missing_altitudes(&mut locations, ElevationFill::Interpolate);
+        assert_eq!(synthetic, vec![true, true]);
+        assert!(locations.iter().all(|l| l.altitude == 0.0));
+    }
+
+    #[test]
+    fn zero_fill_only_touches_missing_points() {
+        let mut locations = vec![loc(0.0, 0.0, 100.0), loc(0.001, 0.0, f64::NAN)];
+        let synthetic = fill_missing_altitudes(&mut locations, ElevationFill::Zero);
+        assert_eq!(synthetic, vec![false, true]);
+        assert_eq!(locations[0].altitude, 100.0);
+        assert_eq!(locations[1].altitude, 0.0);
+    }
+
+    #[test]
+    fn dem_fill_counts_as_measured_and_falls_back_to_interpolation() {
+        fn dem(l: &Location) -> Option<f64> {
+            (l.latitude < 0.0015).then_some(42.0)
+        }
+        let mut locations = vec![
+            loc(0.0, 0.0, 10.0),
+            loc(0.001, 0.0, f64::NAN),
+            loc(0.002, 0.0, f64::NAN),
+            loc(0.003, 0.0, 62.0),
+        ];
+        let synthetic = fill_missing_altitudes(&mut locations, ElevationFill::Dem(dem));
+        assert_eq!(synthetic, vec![false, false, true, false]);
+        assert_eq!(locations[0].altitude, 10.0);
+        assert_eq!(locations[1].altitude, 42.0);
+        assert!((locations[2].altitude - 52.0).abs() < 1e-6);
+    }
+
     // — compute_slopes ────────────────────────────────────────────────────────
 
     #[test]
@@ -0,0 +1,180 @@
+//! Metabolic energy along a whole trace.
+//!
+//! Integrates the Minetti cost of transport ([`minetti::cmet`] for running,
+//! [`minetti::cw`] for walking) over every segment. Dividing the cost by its
+//! flat value gives the equivalent flat distance — for running, the same
+//! effort-weighted distance (`d_eff`) that drives the fatigue model.
+
+use std::str::FromStr;
+
+use crate::minetti;
+use crate::nutrition::J_PER_KCAL;
+use crate::trace::Trace;
+
+/// Which Minetti cost curve to integrate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "wasm", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "wasm", serde(rename_all = "camelCase"))]
+pub enum Gait {
+    #[default]
+    Running,
+    Walking,
+}
+
+impl Gait {
+    /// Metabolic cost at `slope` (rise/run) in J·kg⁻¹·m⁻¹.
+    pub fn cost(self, slope: f64) -> f64 {
+        match self {
+            Gait::Running => minetti::cmet(slope),
+            Gait::Walking => minetti::cw(slope),
+        }
+    }
+
+    /// Metabolic cost on the flat in J·kg⁻¹·m⁻¹.
+    pub fn flat_cost(self) -> f64 {
+        match self {
+            Gait::Running => minetti::CMET_FLAT,
+            Gait::Walking => minetti::CW_FLAT,
+        }
+    }
+}
+
+impl FromStr for Gait {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "running" | "run" => Ok(Gait::Running),
+            "walking" | "walk" | "hiking" => Ok(Gait::Walking),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Cumulative metabolic output at every trace index.
+#[derive(Debug, Clone)]
+pub struct EnergyCurve {
+    pub gait: Gait,
+    pub mass_kg: f64,
+    /// Energy spent from the start to each point (kJ). Same length as
+    /// `trace.locations()`, starting at 0.
+    pub cumulative_kj: Vec<f64>,
+    /// Equivalent flat distance from the start to each point (km).
+    pub cumulative_flat_km: Vec<f64>,
+}
+
+impl EnergyCurve {
+    /// Integrate `gait`'s cost curve over `trace` for a runner of `mass_kg`.
+    pub fn compute(trace: &Trace, mass_kg: f64, gait: Gait) -> Self {
+        let n = trace.locations.len();
+        let mut cumulative_kj = Vec::with_capacity(n);
+        let mut cumulative_flat_km = Vec::with_capacity(n);
+        let mut kj = 0.0_f64;
+        let mut flat_km = 0.0_f64;
+        cumulative_kj.push(kj);
+        cumulative_flat_km.push(flat_km);
+
+        let flat_cost = gait.flat_cost();
+        for j in 0..n - 1 {
+            let seg_dist_km = trace.cumulative_distances[j + 1] - trace.cumulative_distances[j];
+            let cost = gait.cost(trace.slopes[j] / 100.0);
+            kj += cost * seg_dist_km * mass_kg;
+            flat_km += seg_dist_km * cost / flat_cost;
+            cumulative_kj.push(kj);
+            cumulative_flat_km.push(flat_km);
+        }
+
+        Self {
+            gait,
+            mass_kg,
+            cumulative_kj,
+            cumulative_flat_km,
+        }
+    }
+
+    /// Energy over the whole trace (kJ).
+    pub fn total_kj(&self) -> f64 {
+        self.cumulative_kj.last().copied().unwrap_or(0.0)
+    }
+
+    /// Energy over the whole trace (kcal).
+    pub fn total_kcal(&self) -> f64 {
+        self.total_kj() * 1000.0 / J_PER_KCAL
+    }
+
+    /// Energy between two trace indices (kcal).
+    pub fn kcal_between(&self, start_index: usize, end_index: usize) -> f64 {
+        (self.cumulative_kj[end_index] - self.cumulative_kj[start_index]) * 1000.0 / J_PER_KCAL
+    }
+
+    /// Flat distance costing the same energy as the whole trace (km).
+    pub fn equivalent_flat_distance_km(&self) -> f64 {
+        self.cumulative_flat_km.last().copied().unwrap_or(0.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pace_model::{MinettiModel, WEATHER_NEUTRAL};
+    use crate::segment::{self, SegmentParams, SegmentState};
+    use crate::Location;
+
+    fn ramp_trace(n: usize, rise_per_point_m: f64) -> Trace {
+        let locs: Vec<Location> = (0..n)
+            .map(|i| Location {
+                longitude: 0.0,
+                latitude: i as f64 * 0.001,
+                altitude: 100.0 + i as f64 * rise_per_point_m,
+            })
+            .collect();
+        Trace::new(&locs).unwrap()
+    }
+
+    #[test]
+    fn flat_energy_matches_cost_times_distance_and_mass() {
+        let trace = ramp_trace(11, 0.0);
+        let curve = EnergyCurve::compute(&trace, 70.0, Gait::Running);
+        let km = trace.total_distance();
+        assert_eq!(curve.cumulative_kj.len(), trace.locations.len());
+        assert_eq!(curve.cumulative_kj[0], 0.0);
+        assert!((curve.total_kj() - minetti::CMET_FLAT * km * 70.0).abs() < 1e-6);
+        assert!((curve.equivalent_flat_distance_km() - km).abs() < 1e-9);
+        assert!((curve.kcal_between(0, 10) - curve.total_kcal()).abs() < 1e-9);
+
+        let walk = EnergyCurve::compute(&trace, 70.0, Gait::Walking);
+        assert!(walk.total_kj() < curve.total_kj());
+        assert!((walk.equivalent_flat_distance_km() - km).abs() < 1e-9);
+    }
+
+    #[test]
+    fn running_flat_equivalent_is_the_fatigue_effort_distance() {
+        let trace = ramp_trace(21, 8.0);
+        let curve = EnergyCurve::compute(&trace, 70.0, Gait::Running);
+        assert!(curve.equivalent_flat_distance_km() > trace.total_distance());
+
+        let params = SegmentParams {
+            base_pace_s_per_km: 500.0,
+            k_fatigue: 0.0,
+            clock_start: None,
+            weather: WEATHER_NEUTRAL,
+            forecast: None,
+            pace_model: &MinettiModel,
+            altitude_acclimatization_m: None,
+            darkness: false,
+            surfaces: None,
+            rested_until: None,
+            weather_scale: 1.0,
+        };
+        let mut state = SegmentState::new();
+        segment::compute(&trace, 0, 20, &params, &mut state);
+        assert!((state.d_eff_m / 1000.0 - curve.equivalent_flat_distance_km()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn parses_gait_names() {
+        assert_eq!("Walking".parse(), Ok(Gait::Walking));
+        assert_eq!("run".parse(), Ok(Gait::Running));
+        assert!("crawl".parse::<Gait>().is_err());
+    }
+}
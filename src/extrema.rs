@@ -5,6 +5,9 @@
 //! Counting across all scales gives a vote tally; points that exceed the
 //! `threshold` are extremum candidates.  Nearby candidates are clustered and
 //! kept only when their topographic prominence exceeds `MIN_PROMINENCE`.
+//!
+//! Missing altitudes are passed in as `NaN`: they never become extrema and
+//! are stepped over (not treated as cols) when measuring prominence.
 
 const AMPD_SCALE_MAX: usize = 21;
 const AMPD_THRESHOLD: usize = 15;
@@ -30,6 +33,9 @@ fn ampd_core(signal: &[f32], scale_max: usize, threshold: usize, find_peaks: boo
             break;
         }
         for i in scale..(n - scale) {
+            if signal[i].is_nan() {
+                continue;
+            }
             let is_ext = if find_peaks {
                 signal[i] > signal[i - scale] && signal[i] > signal[i + scale]
             } else {
@@ -100,6 +106,9 @@ fn extremum_prominence(signal: &[f32], idx: usize, find_peaks: bool) -> f32 {
     while i > 0 {
         i -= 1;
         let s = signal[i];
+        if s.is_nan() {
+            continue;
+        }
         if find_peaks {
             if s > v {
                 break;
@@ -122,6 +131,9 @@ fn extremum_prominence(signal: &[f32], idx: usize, find_peaks: bool) -> f32 {
     while j + 1 < signal.len() {
         j += 1;
         let s = signal[j];
+        if s.is_nan() {
+            continue;
+        }
         if find_peaks {
             if s > v {
                 break;
@@ -247,6 +259,17 @@ mod tests {
         assert_eq!(raw_peaks.len(), raw_valleys.len());
     }
 
+    #[test]
+    fn missing_samples_are_never_extrema_and_do_not_cut_prominence() {
+        let mut signal = vec![100.0f32; 60];
+        signal[10] = 180.0;
+        signal[12] = f32::NAN;
+        signal[30] = f32::NAN;
+        let raw_valleys = ampd_core(&signal, AMPD_SCALE_MAX, 1, false);
+        assert!(!raw_valleys.contains(&12) && !raw_valleys.contains(&30));
+        assert!((extremum_prominence(&signal, 10, true) - 80.0).abs() < 0.001);
+    }
+
     #[test]
     fn extremum_prominence_correct_for_dominant_peak() {
         let signal = vec![0.0f32, 50.0, 100.0, 60.0, 40.0, 55.0, 70.0, 30.0, 0.0];
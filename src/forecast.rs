@@ -0,0 +1,223 @@
+//! Time-varying weather forecasts.
+//!
+//! A [`WeatherForecast`] holds an hourly (or any-step) series per checkpoint.
+//! Between two checkpoints the conditions are interpolated linearly in time
+//! within each series, then linearly along distance from the start checkpoint's
+//! series to the end checkpoint's, so a segment is evaluated at the clock time
+//! the runner is predicted to reach it.
+
+use crate::pace_model::WeatherConditions;
+
+/// Forecast samples for one checkpoint, sorted by time.
+#[derive(Debug, Clone, Default)]
+pub struct ForecastSeries {
+    samples: Vec<(i64, WeatherConditions)>,
+}
+
+impl ForecastSeries {
+    /// Build from `(unix_time_s, conditions)` samples in any order.
+    pub fn new(mut samples: Vec<(i64, WeatherConditions)>) -> Self {
+        samples.sort_by_key(|&(t, _)| t);
+        Self { samples }
+    }
+
+    /// Build from consecutive hourly values starting at `start_s`.
+    pub fn hourly(start_s: i64, values: Vec<WeatherConditions>) -> Self {
+        Self {
+            samples: values
+                .into_iter()
+                .enumerate()
+                .map(|(i, c)| (start_s + i as i64 * 3600, c))
+                .collect(),
+        }
+    }
+
+    pub fn samples(&self) -> &[(i64, WeatherConditions)] {
+        &self.samples
+    }
+
+    /// Conditions at `unix_time_s`, linearly interpolated between samples and
+    /// held constant before the first and after the last. `None` when empty.
+    pub fn at(&self, unix_time_s: i64) -> Option<WeatherConditions> {
+        let first = self.samples.first()?;
+        let pos = self.samples.partition_point(|&(t, _)| t <= unix_time_s);
+        if pos == 0 {
+            return Some(first.1);
+        }
+        let (t0, c0) = self.samples[pos - 1];
+        let Some(&(t1, c1)) = self.samples.get(pos) else {
+            return Some(c0);
+        };
+        let frac = (unix_time_s - t0) as f64 / (t1 - t0) as f64;
+        Some(lerp(c0, c1, frac))
+    }
+}
+
+/// Name-keyed forecast series. Checkpoints without a series fall back to the
+/// static [`WeatherLookup`](crate::WeatherLookup).
+#[derive(Debug, Clone, Default)]
+pub struct WeatherForecast {
+    names: Vec<String>,
+    series: Vec<ForecastSeries>,
+}
+
+impl WeatherForecast {
+    pub fn empty() -> Self {
+        Self::default()
+    }
+
+    pub fn new(names: Vec<String>, series: Vec<ForecastSeries>) -> Self {
+        Self { names, series }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    pub fn find(&self, name: &str) -> Option<&ForecastSeries> {
+        self.names
+            .iter()
+            .zip(self.series.iter())
+            .find(|(n, _)| n.as_str() == name)
+            .map(|(_, s)| s)
+    }
+
+    /// Span between two checkpoints at cumulative distances `start_km..end_km`,
+    /// or `None` when neither checkpoint has a series.
+    pub fn span(
+        &self,
+        from: &str,
+        to: &str,
+        start_km: f64,
+        end_km: f64,
+    ) -> Option<WeatherSpan<'_>> {
+        let from = self.find(from).filter(|s| !s.samples.is_empty());
+        let to = self.find(to).filter(|s| !s.samples.is_empty());
+        if from.is_none() && to.is_none() {
+            return None;
+        }
+        Some(WeatherSpan {
+            from,
+            to,
+            start_km,
+            end_km,
+        })
+    }
+}
+
+/// Forecast between two checkpoints, evaluated per segment by `segment::compute`.
+#[derive(Debug, Clone, Copy)]
+pub struct WeatherSpan<'a> {
+    pub from: Option<&'a ForecastSeries>,
+    pub to: Option<&'a ForecastSeries>,
+    /// Cumulative distance of the start checkpoint (km).
+    pub start_km: f64,
+    /// Cumulative distance of the end checkpoint (km).
+    pub end_km: f64,
+}
+
+impl WeatherSpan<'_> {
+    /// Conditions at cumulative distance `dist_km` and time `unix_time_s`. When
+    /// only one checkpoint has a series it applies across the whole span.
+    pub fn conditions_at(&self, dist_km: f64, unix_time_s: i64) -> Option<WeatherConditions> {
+        let a = self.from.and_then(|s| s.at(unix_time_s));
+        let b = self.to.and_then(|s| s.at(unix_time_s));
+        match (a, b) {
+            (Some(a), Some(b)) => {
+                let len = self.end_km - self.start_km;
+                let frac = if len > 0.0 {
+                    ((dist_km - self.start_km) / len).clamp(0.0, 1.0)
+                } else {
+                    1.0
+                };
+                Some(lerp(a, b, frac))
+            }
+            (a, b) => a.or(b),
+        }
+    }
+}
+
+fn lerp(a: WeatherConditions, b: WeatherConditions, t: f64) -> WeatherConditions {
+    let mix = |x: f64, y: f64| x + (y - x) * t;
+    WeatherConditions {
+        temperature_c: mix(a.temperature_c, b.temperature_c),
+        humidity_pct: mix(a.humidity_pct, b.humidity_pct),
+        wind_kmh: mix(a.wind_kmh, b.wind_kmh),
+        precip_prob_pct: mix(a.precip_prob_pct, b.precip_prob_pct),
+        wind_dir_deg: match (a.wind_dir_deg, b.wind_dir_deg) {
+            // Along the shorter arc, so 350° → 10° passes through north.
+            (Some(x), Some(y)) => {
+                let delta = (y - x + 540.0).rem_euclid(360.0) - 180.0;
+                Some((x + delta * t).rem_euclid(360.0))
+            }
+            (x, y) if t < 0.5 => x.or(y),
+            (x, y) => y.or(x),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pace_model::WEATHER_NEUTRAL;
+
+    fn at_temp(temperature_c: f64) -> WeatherConditions {
+        WeatherConditions {
+            temperature_c,
+            ..WEATHER_NEUTRAL
+        }
+    }
+
+    #[test]
+    fn series_interpolates_in_time_and_clamps_at_ends() {
+        let series = ForecastSeries::hourly(1_000, vec![at_temp(10.0), at_temp(20.0)]);
+        assert!((series.at(0).unwrap().temperature_c - 10.0).abs() < 1e-9);
+        assert!((series.at(1_000 + 1800).unwrap().temperature_c - 15.0).abs() < 1e-9);
+        assert!((series.at(1_000 + 7200).unwrap().temperature_c - 20.0).abs() < 1e-9);
+        assert!(ForecastSeries::default().at(0).is_none());
+    }
+
+    #[test]
+    fn series_sorts_unordered_samples() {
+        let series = ForecastSeries::new(vec![(3600, at_temp(30.0)), (0, at_temp(10.0))]);
+        assert_eq!(series.samples()[0].0, 0);
+        assert!((series.at(900).unwrap().temperature_c - 15.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn span_interpolates_along_distance() {
+        let forecast = WeatherForecast::new(
+            vec!["A".into(), "B".into()],
+            vec![
+                ForecastSeries::hourly(0, vec![at_temp(0.0)]),
+                ForecastSeries::hourly(0, vec![at_temp(20.0)]),
+            ],
+        );
+        let span = forecast.span("A", "B", 10.0, 20.0).unwrap();
+        assert!((span.conditions_at(10.0, 0).unwrap().temperature_c - 0.0).abs() < 1e-9);
+        assert!((span.conditions_at(15.0, 0).unwrap().temperature_c - 10.0).abs() < 1e-9);
+        assert!((span.conditions_at(25.0, 0).unwrap().temperature_c - 20.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn wind_direction_interpolates_along_the_shorter_arc() {
+        let from = |deg| WeatherConditions {
+            wind_dir_deg: Some(deg),
+            ..WEATHER_NEUTRAL
+        };
+        let series = ForecastSeries::hourly(0, vec![from(350.0), from(10.0)]);
+        let mid = series.at(1800).unwrap().wind_dir_deg.unwrap();
+        assert!(mid.abs() < 1e-9 || (mid - 360.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn span_with_one_series_uses_it_throughout() {
+        let forecast = WeatherForecast::new(
+            vec!["B".into()],
+            vec![ForecastSeries::hourly(0, vec![at_temp(25.0)])],
+        );
+        let span = forecast.span("A", "B", 0.0, 5.0).unwrap();
+        assert!((span.conditions_at(0.0, 0).unwrap().temperature_c - 25.0).abs() < 1e-9);
+        assert!(forecast.span("A", "C", 0.0, 5.0).is_none());
+    }
+}
@@ -1,3 +1,4 @@
+use crate::surface::Surface;
 use crate::waypoint::Waypoint;
 use crate::Location;
 
@@ -56,14 +57,56 @@ fn parse_tag_content<'a>(
     bytes.get(value_start..value_end)
 }
 
+/// `<crew>`-style boolean flag: `true`, `yes` or `1`, any case.
+fn parse_flag(s: &str) -> bool {
+    matches!(s.trim().to_ascii_lowercase().as_str(), "true" | "yes" | "1")
+}
+
+/// Altitude of a `<trkpt>`, or `f64::NAN` when `<ele>` is absent or not a
+/// number.  The point is kept either way — `Trace::new` fills missing
+/// altitudes according to its [`ElevationFill`](crate::ElevationFill) policy.
+fn parse_elevation(bytes: &[u8], content: &[u8], content_start: usize, element_end: usize) -> f64 {
+    parse_tag_content(
+        bytes,
+        content,
+        content_start,
+        element_end,
+        b"<ele>",
+        b"</ele>",
+    )
+    .and_then(|s| std::str::from_utf8(s).ok())
+    .and_then(|s| s.parse::<f64>().ok())
+    .unwrap_or(f64::NAN)
+}
+
 // ── Public API ────────────────────────────────────────────────────────────────
 
 /// Parse `<trkpt>` elements from raw GPX bytes into a flat `Vec<Location>`.
 ///
-/// Handles both single-quoted and double-quoted attribute values.  Malformed
-/// or incomplete track-points are silently skipped, matching the behaviour of
-/// the reference Zig implementation.
+/// Handles both single-quoted and double-quoted attribute values.  Track-points
+/// without a valid `lat`/`lon` are silently skipped, matching the behaviour of
+/// the reference Zig implementation.  Track-points without `<ele>` are kept
+/// with a missing (`NaN`) altitude — see [`Location::has_altitude`].
 pub fn parse_trace_points(bytes: &[u8]) -> Vec<Location> {
+    parse_trkpts(bytes, None)
+}
+
+/// A recorded activity: track-points plus their `<time>` stamps.
+pub struct TimedTrack {
+    pub locations: Vec<Location>,
+    /// Unix epoch seconds per location; `None` where `<time>` is absent or invalid.
+    pub times: Vec<Option<i64>>,
+}
+
+/// Like [`parse_trace_points`], also reading each track-point's `<time>`.
+pub fn parse_timed_trace_points(bytes: &[u8]) -> TimedTrack {
+    let mut times = Vec::with_capacity(bytes.len() / 100);
+    let locations = parse_trkpts(bytes, Some(&mut times));
+    TimedTrack { locations, times }
+}
+
+/// Shared `<trkpt>` scanner; collects timestamps only when `times` is given.
+fn parse_trkpts(bytes: &[u8], mut times: Option<&mut Vec<Option<i64>>>) -> Vec<Location> {
     let mut locations = Vec::with_capacity(bytes.len() / 100);
     let mut pos = 0;
 
@@ -97,24 +140,21 @@ pub fn parse_trace_points(bytes: &[u8]) -> Vec<Location> {
             Some(value) => value,
             None => continue,
         };
-        let ele_bytes = match parse_tag_content(
-            bytes,
-            content,
-            content_start,
-            trkpt_end,
-            b"<ele>",
-            b"</ele>",
-        ) {
-            Some(slice) => slice,
-            None => continue,
-        };
-        let elevation = match std::str::from_utf8(ele_bytes)
-            .ok()
-            .and_then(|s| s.parse::<f64>().ok())
-        {
-            Some(value) => value,
-            None => continue,
-        };
+        let elevation = parse_elevation(bytes, content, content_start, trkpt_end);
+        if let Some(times) = times.as_deref_mut() {
+            times.push(
+                parse_tag_content(
+                    bytes,
+                    content,
+                    content_start,
+                    trkpt_end,
+                    b"<time>",
+                    b"</time>",
+                )
+                .and_then(|s| std::str::from_utf8(s).ok())
+                .and_then(|s| crate::time::parse_iso8601_to_epoch(s).ok()),
+            );
+        }
 
         locations.push(Location {
             longitude: lon,
@@ -189,24 +229,7 @@ pub(crate) fn parse_all(bytes: &[u8]) -> ParsedGpx {
                 Some(value) => value,
                 None => continue,
             };
-            let ele_bytes = match parse_tag_content(
-                bytes,
-                content,
-                content_start,
-                trkpt_end,
-                b"<ele>",
-                b"</ele>",
-            ) {
-                Some(slice) => slice,
-                None => continue,
-            };
-            let elevation = match std::str::from_utf8(ele_bytes)
-                .ok()
-                .and_then(|s| s.parse::<f64>().ok())
-            {
-                Some(value) => value,
-                None => continue,
-            };
+            let elevation = parse_elevation(bytes, content, content_start, trkpt_end);
 
             locations.push(Location {
                 longitude: lon,
@@ -320,6 +343,28 @@ pub(crate) fn parse_all(bytes: &[u8]) -> ParsedGpx {
             .and_then(|s| std::str::from_utf8(s).ok())
             .and_then(|s| s.parse::<u32>().ok());
 
+            let surface = parse_tag_content(
+                bytes,
+                content,
+                content_start,
+                wpt_end,
+                b"<surface>",
+                b"</surface>",
+            )
+            .and_then(|s| std::str::from_utf8(s).ok())
+            .and_then(|s| s.parse::<Surface>().ok());
+
+            let crew = parse_tag_content(
+                bytes,
+                content,
+                content_start,
+                wpt_end,
+                b"<crew>",
+                b"</crew>",
+            )
+            .and_then(|s| std::str::from_utf8(s).ok())
+            .is_some_and(parse_flag);
+
             waypoints.push(Waypoint {
                 latitude: lat,
                 longitude: lon,
@@ -331,6 +376,8 @@ pub(crate) fn parse_all(bytes: &[u8]) -> ParsedGpx {
                 wpt_type,
                 time,
                 stop_duration,
+                surface,
+                crew,
             });
             continue;
         }
@@ -577,6 +624,28 @@ pub fn parse_waypoints(bytes: &[u8]) -> Vec<Waypoint> {
         .and_then(|s| std::str::from_utf8(s).ok())
         .and_then(|s| s.parse::<u32>().ok());
 
+        let surface = parse_tag_content(
+            bytes,
+            content,
+            content_start,
+            wpt_end,
+            b"<surface>",
+            b"</surface>",
+        )
+        .and_then(|s| std::str::from_utf8(s).ok())
+        .and_then(|s| s.parse::<Surface>().ok());
+
+        let crew = parse_tag_content(
+            bytes,
+            content,
+            content_start,
+            wpt_end,
+            b"<crew>",
+            b"</crew>",
+        )
+        .and_then(|s| std::str::from_utf8(s).ok())
+        .is_some_and(parse_flag);
+
         waypoints.push(Waypoint {
             latitude: lat,
             longitude: lon,
@@ -588,6 +657,8 @@ pub fn parse_waypoints(bytes: &[u8]) -> Vec<Waypoint> {
             wpt_type,
             time,
             stop_duration,
+            surface,
+            crew,
         });
     }
 
@@ -644,6 +715,35 @@ mod tests {
         assert_eq!(locations[0].altitude, 200.0);
     }
 
+    #[test]
+    fn keeps_points_without_elevation() {
+        let gpx = br#"<gpx>
+  <trk><trkseg>
+    <trkpt lat="37.0" lon="-122.0"><ele>100</ele></trkpt>
+    <trkpt lat="37.1" lon="-122.1"></trkpt>
+    <trkpt lat="37.2" lon="-122.2"><ele>n/a</ele></trkpt>
+  </trkseg></trk>
+</gpx>"#;
+
+        let locations = parse_trace_points(gpx);
+        assert_eq!(locations.len(), 3);
+        assert!(locations[0].has_altitude());
+        assert!(!locations[1].has_altitude());
+        assert!(!locations[2].has_altitude());
+        assert_eq!(parse_all(gpx).locations.len(), 3);
+    }
+
+    #[test]
+    fn parse_timed_trace_points_reads_time_per_point() {
+        let gpx =
+            br#"<trkpt lat="45.0" lon="7.0"><ele>100</ele><time>2025-11-20T12:00:00Z</time></trkpt>
+<trkpt lat="45.1" lon="7.1"><ele>110</ele></trkpt>
+<trkpt lat="45.2" lon="7.2"><ele>120</ele><time>2025-11-20T12:01:00Z</time></trkpt>"#;
+        let track = parse_timed_trace_points(gpx);
+        assert_eq!(track.locations, parse_trace_points(gpx));
+        assert_eq!(track.times, vec![Some(1763640000), None, Some(1763640060)]);
+    }
+
     #[test]
     fn preserves_high_precision_coordinates() {
         let gpx =
@@ -715,6 +815,38 @@ mod tests {
         assert_eq!(waypoints[1].wpt_type.as_deref(), Some("LifeBase"));
     }
 
+    #[test]
+    fn parse_waypoints_reads_surface_extension() {
+        let gpx = br#"<gpx>
+  <wpt lat="45.0" lon="7.0">
+    <name>Moraine</name>
+    <extensions><surface>rocky</surface></extensions>
+  </wpt>
+  <wpt lat="45.1" lon="7.0"><name>Road</name><surface>1</surface></wpt>
+  <wpt lat="45.2" lon="7.0"><name>Bog</name><surface>swamp</surface></wpt>
+</gpx>"#;
+        let waypoints = parse_waypoints(gpx);
+        assert_eq!(waypoints[0].surface, Some(Surface::Rocky));
+        assert_eq!(waypoints[1].surface, Some(Surface::Road));
+        assert_eq!(waypoints[2].surface, None);
+    }
+
+    #[test]
+    fn parse_waypoints_reads_crew_extension() {
+        let gpx = br#"<gpx>
+  <wpt lat="45.0" lon="7.0">
+    <name>Aid</name>
+    <extensions><crew>true</crew></extensions>
+  </wpt>
+  <wpt lat="45.1" lon="7.0"><name>Hut</name><crew>no</crew></wpt>
+  <wpt lat="45.2" lon="7.0"><name>Col</name></wpt>
+</gpx>"#;
+        let waypoints = parse_waypoints(gpx);
+        assert!(waypoints[0].crew);
+        assert!(!waypoints[1].crew);
+        assert!(!waypoints[2].crew);
+    }
+
     #[test]
     fn parse_waypoints_skips_missing_lat_or_lon() {
         let gpx = br#"<gpx>
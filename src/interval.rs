@@ -5,11 +5,15 @@
 //! resolve trace indices, accumulate physiological metrics, compute derived stats.
 //! This module captures the invariant core.
 
+use crate::calibration::BoundaryKind;
+use crate::cutoff::{self, CutoffRisk};
 use crate::location::Location;
-use crate::pace_model::{AnalysisOptions, RECOVERY_LIFE_BASE};
-use crate::segment::{self, SegmentParams, SegmentState};
-use crate::trace::Trace;
-use crate::waypoint::Waypoint;
+use crate::nutrition::{self, SectionNutrition};
+use crate::pace_model::AnalysisOptions;
+use crate::route::ResolvedRoute;
+use crate::segment::{self, SegmentParams, SegmentSink, SegmentState};
+use crate::sleep::{self, SleepTracker};
+use crate::surface;
 
 /// Raw metrics computed for one interval (section or stage).
 /// The caller maps these into its own typed struct (SectionStats / StageStats).
@@ -36,54 +40,74 @@ pub(crate) struct IntervalMetrics {
     pub max_completion_time: Option<i64>,
     pub cutoff_ratio: Option<f64>,
     pub stop_duration: Option<u32>,
+    /// Stop actually applied at the end checkpoint (explicit or LifeBase default,
+    /// plus any sleep), seconds.
+    pub planned_stop_s: f64,
+    /// Sleep taken at the end checkpoint (seconds; included in `planned_stop_s`).
+    pub sleep_s: f64,
+    /// Fraction of `d_eff` recovered at the end checkpoint (LifeBase and/or sleep).
+    pub end_recovery: f64,
+    /// Predicted elapsed time from race start to arrival at the end checkpoint,
+    /// before its planned stop (seconds).
+    pub predicted_arrival_s: f64,
+    /// Minutes between `predicted_arrival_s` and the end checkpoint's absolute cutoff.
+    pub cutoff_slack_min: Option<f64>,
+    pub cutoff_risk: Option<CutoffRisk>,
+    /// Moving time with altitude ÷ moving time without it (1.0 when disabled).
+    pub altitude_factor: f64,
+    /// Moving seconds added by the altitude factor.
+    pub altitude_time_s: f64,
+    /// Moving seconds added by the darkness factor.
+    pub darkness_time_s: f64,
+    /// Moving time with surface classes ÷ moving time without (1.0 when unclassified).
+    pub surface_factor: f64,
+    /// Distance-weighted mean surface level over classified distance.
+    pub technicality: Option<u8>,
+    /// Energy, fluid and sodium needs (when `AnalysisOptions::nutrition` is set).
+    pub nutrition: Option<SectionNutrition>,
     /// Index of the stage this interval belongs to (only meaningful for sections).
     pub stage_idx: usize,
 }
 
-/// Compute interval metrics between consecutive boundary waypoints.
-///
-/// `boundary_filter` selects which waypoints count as interval boundaries.
-/// `track_stage_idx` enables stage-index tracking (used by sections to know
-/// which stage each section belongs to).
+/// Compute interval metrics between consecutive `kind` boundaries of `route`.
 pub(crate) fn compute_intervals(
-    trace: &Trace,
-    waypoints: &[Waypoint],
+    route: &ResolvedRoute<'_>,
     options: &AnalysisOptions,
-    boundary_filter: fn(&Waypoint) -> bool,
-    track_stage_idx: bool,
+    kind: BoundaryKind,
 ) -> Option<Vec<IntervalMetrics>> {
-    let base_pace_s_per_km = options.base_pace_s_per_km;
-    let k_fatigue = options.k_fatigue;
+    compute_intervals_traced(route, options, kind, None)
+}
+
+/// [`compute_intervals`], forwarding every trace segment to `on_segment`
+/// (see [`segment::compute_traced`]).
+pub(crate) fn compute_intervals_traced(
+    route: &ResolvedRoute<'_>,
+    options: &AnalysisOptions,
+    kind: BoundaryKind,
+    mut on_segment: Option<SegmentSink<'_>>,
+) -> Option<Vec<IntervalMetrics>> {
+    let trace = route.trace();
+    let waypoints = route.waypoints();
     let life_base_stop_s = options.life_base_stop_s;
-    let weather = &options.weather;
 
-    let boundary_wpts: Vec<&Waypoint> = waypoints.iter().filter(|w| boundary_filter(w)).collect();
-    if boundary_wpts.len() < 2 {
+    let surfaces = route.surfaces().overlaid(&options.surfaces);
+
+    if route.boundary_count(&kind) < 2 {
         return None;
     }
+    let spans = route.spans(&kind);
+    // Sections record which stage they belong to.
+    let track_stage_idx = matches!(kind, BoundaryKind::Section);
 
-    let mut results = Vec::with_capacity(boundary_wpts.len() - 1);
-    let mut search_start = 0usize;
-    let clock_start = boundary_wpts[0].time;
-    let mut state = SegmentState {
-        d_eff_m: 0.0,
-        elapsed_s: 0.0,
-    };
-    let mut current_stage_idx = 0usize;
-    let mut stage_active = false;
-
-    for i in 0..boundary_wpts.len() - 1 {
-        // Track stage index for section-level analysis.
-        if track_stage_idx && boundary_wpts[i].is_stage_boundary() {
-            if stage_active {
-                current_stage_idx += 1;
-            } else {
-                stage_active = true;
-            }
-        }
+    let mut results = Vec::with_capacity(spans.len());
+    let clock_start = route.boundaries(&kind).next().and_then(|w| w.time);
+    let mut state = SegmentState::new();
+    let mut sleep_tracker = SleepTracker::new(&options.sleep);
 
-        let start_wpt = boundary_wpts[i];
-        let end_wpt = boundary_wpts[i + 1];
+    for span in spans {
+        let (start_index, end_index) = (span.start_index, span.end_index);
+        let start_wpt = &waypoints[span.start_wpt];
+        let end_wpt = &waypoints[span.end_wpt];
 
         let start_target = Location {
             longitude: start_wpt.longitude,
@@ -96,20 +120,6 @@ pub(crate) fn compute_intervals(
             altitude: 0.0,
         };
 
-        let start_index = match trace.find_closest_point_from(&start_target, search_start) {
-            Some((_, idx, _)) => idx,
-            None => continue,
-        };
-        let end_index = match trace.find_closest_point_from(&end_target, start_index + 1) {
-            Some((_, idx, _)) => idx,
-            None => continue,
-        };
-        search_start = end_index;
-
-        if start_index >= end_index {
-            continue;
-        }
-
         let bearing = start_target.calculate_bearing_to(&end_target);
         let dist_km =
             trace.cumulative_distances[end_index] - trace.cumulative_distances[start_index];
@@ -118,18 +128,37 @@ pub(crate) fn compute_intervals(
         let elevation_loss_m = trace.cumulative_elevation_losses[end_index]
             - trace.cumulative_elevation_losses[start_index];
 
-        let interval_weather = weather.find(&end_wpt.name);
         let params = SegmentParams {
-            base_pace_s_per_km,
-            k_fatigue,
-            clock_start,
-            weather: interval_weather,
+            rested_until: sleep_tracker.rested_until,
+            ..SegmentParams::from_options(options, clock_start, Some(&surfaces)).interval(
+                options,
+                trace,
+                &start_wpt.name,
+                &end_wpt.name,
+                start_index,
+                end_index,
+            )
+        };
+        let metrics = match on_segment.as_mut() {
+            Some(sink) => segment::compute_traced(
+                trace,
+                start_index,
+                end_index,
+                &params,
+                &mut state,
+                &mut **sink,
+            ),
+            None => segment::compute(trace, start_index, end_index, &params, &mut state),
         };
-        let metrics = segment::compute(trace, start_index, end_index, &params, &mut state);
+        let predicted_arrival_s = state.race_s;
 
-        if end_wpt.wpt_type.as_deref() == Some("LifeBase") {
-            state.d_eff_m *= 1.0 - RECOVERY_LIFE_BASE;
-        }
+        let end_is_life_base = end_wpt.wpt_type.as_deref() == Some("LifeBase");
+        let sleep_s = sleep_tracker.at_checkpoint(
+            &end_wpt.name,
+            clock_start.map(|t0| t0 + predicted_arrival_s as i64),
+        );
+        let end_recovery = sleep::end_recovery(end_is_life_base, sleep_s);
+        state.d_eff_m *= 1.0 - end_recovery;
 
         let avg_slope = if dist_km > 0.0 {
             ((elevation_gain_m - elevation_loss_m) / (dist_km * 1000.0)) * 100.0
@@ -143,13 +172,40 @@ pub(crate) fn compute_intervals(
         };
         let stop_secs: f64 = if let Some(sd) = end_wpt.stop_duration {
             sd as f64
-        } else if end_wpt.wpt_type.as_deref() == Some("LifeBase") {
+        } else if end_is_life_base {
             life_base_stop_s as f64
         } else {
             0.0
-        };
+        } + sleep_s as f64;
         let estimated_duration_s = metrics.total_time + stop_secs;
-        let difficulty: u8 = if avg_pf < 1.1 {
+        let sea_level_time = metrics.total_time - metrics.altitude_time_s;
+        let altitude_factor = if sea_level_time > 0.0 {
+            metrics.total_time / sea_level_time
+        } else {
+            1.0
+        };
+        let surface_free_time = metrics.total_time - metrics.surface_time_s;
+        let surface_factor = if surface_free_time > 0.0 {
+            metrics.total_time / surface_free_time
+        } else {
+            1.0
+        };
+        let technicality = (metrics.classified_dist_km > 0.0)
+            .then(|| (metrics.technicality_dist_km / metrics.classified_dist_km).round() as u8);
+        let nutrition = options.nutrition.map(|profile| {
+            nutrition::estimate(
+                &profile,
+                metrics.metabolic_j_per_kg,
+                metrics.total_time,
+                metrics.heat_load_c_s,
+            )
+        });
+        state.race_s += stop_secs;
+        if sleep_s > 0 {
+            sleep_tracker.rested_until = sleep::rested_until(clock_start, state.race_s);
+        }
+        let cutoff = cutoff::assess(clock_start, end_wpt.time, predicted_arrival_s);
+        let grade_difficulty: u8 = if avg_pf < 1.1 {
             1
         } else if avg_pf < 1.4 {
             2
@@ -160,6 +216,7 @@ pub(crate) fn compute_intervals(
         } else {
             5
         };
+        let difficulty = surface::combined_difficulty(grade_difficulty, technicality);
         let max_completion_time = match (start_wpt.time, end_wpt.time) {
             (Some(t0), Some(t1)) => Some(t1 - t0),
             _ => None,
@@ -173,7 +230,7 @@ pub(crate) fn compute_intervals(
         });
 
         results.push(IntervalMetrics {
-            id: i,
+            id: span.id,
             start_index,
             end_index,
             point_count: end_index - start_index + 1,
@@ -195,7 +252,19 @@ pub(crate) fn compute_intervals(
             max_completion_time,
             cutoff_ratio,
             stop_duration: end_wpt.stop_duration,
-            stage_idx: current_stage_idx,
+            planned_stop_s: stop_secs,
+            sleep_s: sleep_s as f64,
+            end_recovery,
+            predicted_arrival_s,
+            cutoff_slack_min: cutoff.map(|(slack, _)| slack),
+            cutoff_risk: cutoff.map(|(_, risk)| risk),
+            altitude_factor,
+            altitude_time_s: metrics.altitude_time_s,
+            darkness_time_s: metrics.darkness_time_s,
+            surface_factor,
+            technicality,
+            nutrition,
+            stage_idx: if track_stage_idx { span.stage_idx } else { 0 },
         });
     }
 
@@ -207,6 +276,8 @@ mod tests {
     use super::*;
     use crate::location::Location;
     use crate::pace_model::AnalysisOptions;
+    use crate::trace::Trace;
+    use crate::waypoint::Waypoint;
 
     fn make_trace(locs: &[Location]) -> Trace {
         Trace::new(locs).unwrap()
@@ -240,6 +311,8 @@ mod tests {
             wpt_type: wpt_type.map(String::from),
             time,
             stop_duration,
+            surface: None,
+            crew: false,
         }
     }
 
@@ -248,11 +321,9 @@ mod tests {
         let trace = make_trace(&flat_locs(5));
         let wpts = vec![make_waypoint(0.0, "Start", Some("Start"), None, None)];
         assert!(compute_intervals(
-            &trace,
-            &wpts,
+            &ResolvedRoute::new(&trace, &wpts),
             &AnalysisOptions::default(),
-            Waypoint::is_section_boundary,
-            false
+            BoundaryKind::Section
         )
         .is_none());
     }
@@ -262,11 +333,9 @@ mod tests {
         let trace = make_trace(&flat_locs(5));
         let wpts: Vec<Waypoint> = vec![];
         assert!(compute_intervals(
-            &trace,
-            &wpts,
+            &ResolvedRoute::new(&trace, &wpts),
             &AnalysisOptions::default(),
-            Waypoint::is_section_boundary,
-            false
+            BoundaryKind::Section
         )
         .is_none());
     }
@@ -279,11 +348,9 @@ mod tests {
             make_waypoint(0.009, "End", Some("Arrival"), None, None),
         ];
         let intervals = compute_intervals(
-            &trace,
-            &wpts,
+            &ResolvedRoute::new(&trace, &wpts),
             &AnalysisOptions::default(),
-            Waypoint::is_section_boundary,
-            false,
+            BoundaryKind::Section,
         )
         .unwrap();
         assert_eq!(intervals.len(), 1);
@@ -302,11 +369,9 @@ mod tests {
             make_waypoint(0.009, "End", Some("TimeBarrier"), None, Some(600)),
         ];
         let intervals = compute_intervals(
-            &trace,
-            &wpts,
+            &ResolvedRoute::new(&trace, &wpts),
             &AnalysisOptions::default(),
-            Waypoint::is_section_boundary,
-            false,
+            BoundaryKind::Section,
         )
         .unwrap();
         assert_eq!(intervals[0].stop_duration, Some(600));
@@ -322,8 +387,12 @@ mod tests {
             make_waypoint(0.009, "End", Some("LifeBase"), None, None),
         ];
         let opts = AnalysisOptions::default().life_base_stop(1800);
-        let intervals =
-            compute_intervals(&trace, &wpts, &opts, Waypoint::is_section_boundary, false).unwrap();
+        let intervals = compute_intervals(
+            &ResolvedRoute::new(&trace, &wpts),
+            &opts,
+            BoundaryKind::Section,
+        )
+        .unwrap();
         // Duration includes the 1800s default life base stop
         assert!(intervals[0].estimated_duration_s > 1800.0);
     }
@@ -336,11 +405,9 @@ mod tests {
             make_waypoint(0.009, "End", Some("Arrival"), Some(5000), None),
         ];
         let intervals = compute_intervals(
-            &trace,
-            &wpts,
+            &ResolvedRoute::new(&trace, &wpts),
             &AnalysisOptions::default(),
-            Waypoint::is_section_boundary,
-            false,
+            BoundaryKind::Section,
         )
         .unwrap();
         assert_eq!(intervals[0].max_completion_time, Some(4000));
@@ -355,11 +422,9 @@ mod tests {
             make_waypoint(0.009, "End", Some("Arrival"), Some(1000), None),
         ];
         let intervals = compute_intervals(
-            &trace,
-            &wpts,
+            &ResolvedRoute::new(&trace, &wpts),
             &AnalysisOptions::default(),
-            Waypoint::is_section_boundary,
-            false,
+            BoundaryKind::Section,
         )
         .unwrap();
         assert_eq!(intervals[0].max_completion_time, Some(0));
@@ -376,11 +441,9 @@ mod tests {
             make_waypoint(0.012, "End", Some("Arrival"), None, None),
         ];
         let intervals = compute_intervals(
-            &trace,
-            &wpts,
+            &ResolvedRoute::new(&trace, &wpts),
             &AnalysisOptions::default().life_base_stop(0),
-            Waypoint::is_section_boundary,
-            true,
+            BoundaryKind::Section,
         )
         .unwrap();
         assert_eq!(intervals.len(), 3);
@@ -398,11 +461,9 @@ mod tests {
             make_waypoint(0.012, "End", Some("Arrival"), None, None),
         ];
         let intervals = compute_intervals(
-            &trace,
-            &wpts,
+            &ResolvedRoute::new(&trace, &wpts),
             &AnalysisOptions::default().life_base_stop(0),
-            Waypoint::is_stage_boundary,
-            false,
+            BoundaryKind::Stage,
         )
         .unwrap();
         assert_eq!(intervals.len(), 2);
@@ -419,11 +480,9 @@ mod tests {
             make_waypoint(0.009, "End", Some("Arrival"), None, None),
         ];
         let intervals = compute_intervals(
-            &trace,
-            &wpts,
+            &ResolvedRoute::new(&trace, &wpts),
             &AnalysisOptions::default(),
-            Waypoint::is_section_boundary,
-            false,
+            BoundaryKind::Section,
         )
         .unwrap();
         assert_eq!(intervals[0].difficulty, 1);
@@ -445,11 +504,9 @@ mod tests {
             make_waypoint(0.009, "End", Some("Arrival"), None, None),
         ];
         let intervals = compute_intervals(
-            &trace,
-            &wpts,
+            &ResolvedRoute::new(&trace, &wpts),
             &AnalysisOptions::default(),
-            Waypoint::is_section_boundary,
-            false,
+            BoundaryKind::Section,
         )
         .unwrap();
         assert!(intervals[0].difficulty > 1);
@@ -464,14 +521,133 @@ mod tests {
             make_waypoint(0.009, "End", Some("Arrival"), None, None),
         ];
         let intervals = compute_intervals(
-            &trace,
-            &wpts,
+            &ResolvedRoute::new(&trace, &wpts),
             &AnalysisOptions::default(),
-            Waypoint::is_section_boundary,
-            false,
+            BoundaryKind::Section,
         )
         .unwrap();
         // Northbound trace → bearing should be ~0 degrees (north)
         assert!(intervals[0].bearing.abs() < 1.0 || (intervals[0].bearing - 360.0).abs() < 1.0);
     }
+
+    #[test]
+    fn forecast_is_evaluated_at_the_predicted_clock_time() {
+        use crate::forecast::{ForecastSeries, WeatherForecast};
+        use crate::pace_model::{WeatherConditions, WeatherLookup, WEATHER_NEUTRAL};
+
+        const T0: i64 = 1_763_625_600; // 08:00 UTC, away from the circadian window
+        let hot = WeatherConditions {
+            temperature_c: 32.0,
+            ..WEATHER_NEUTRAL
+        };
+        let trace = make_trace(&flat_locs(40));
+        let wpts = vec![
+            make_waypoint(0.0, "Start", Some("Start"), Some(T0), None),
+            make_waypoint(0.039, "End", Some("Arrival"), None, None),
+        ];
+        let run = |options: &AnalysisOptions| {
+            compute_intervals(
+                &ResolvedRoute::new(&trace, &wpts),
+                options,
+                BoundaryKind::Section,
+            )
+            .unwrap()[0]
+                .estimated_duration_s
+        };
+        let neutral = run(&AnalysisOptions::default());
+        let static_hot =
+            run(&AnalysisOptions::default()
+                .weather(WeatherLookup::new(vec!["End".into()], vec![hot])));
+
+        // Constant series behaves like the static lookup.
+        let constant = WeatherForecast::new(
+            vec!["End".into()],
+            vec![ForecastSeries::hourly(T0, vec![hot, hot])],
+        );
+        let constant_hot = run(&AnalysisOptions::default().forecast(constant));
+        assert!((constant_hot - static_hot).abs() < 1e-6);
+
+        // Heat that only arrives later in the day costs less than heat from the start.
+        let warming = WeatherForecast::new(
+            vec!["Start".into(), "End".into()],
+            vec![
+                ForecastSeries::hourly(T0, vec![WEATHER_NEUTRAL, hot]),
+                ForecastSeries::hourly(T0, vec![WEATHER_NEUTRAL, hot]),
+            ],
+        );
+        let warming_up = run(&AnalysisOptions::default().forecast(warming));
+        assert!(warming_up > neutral);
+        assert!(warming_up < static_hot);
+    }
+
+    #[test]
+    fn forecast_after_a_stop_is_read_at_the_departure_time() {
+        use crate::forecast::{ForecastSeries, WeatherForecast};
+        use crate::pace_model::{WeatherConditions, WeatherLookup, WEATHER_NEUTRAL};
+
+        const T0: i64 = 1_763_625_600; // 08:00 UTC, away from the circadian window
+        let hot = WeatherConditions {
+            temperature_c: 32.0,
+            ..WEATHER_NEUTRAL
+        };
+        let trace = make_trace(&flat_locs(40));
+        // ~15 min to the LifeBase, then a 3 h stop.
+        let wpts = vec![
+            make_waypoint(0.0, "Start", Some("Start"), Some(T0), None),
+            make_waypoint(0.019, "LB", Some("LifeBase"), None, Some(3 * 3600)),
+            make_waypoint(0.039, "End", Some("Arrival"), None, None),
+        ];
+        let run = |options: &AnalysisOptions| {
+            let intervals = compute_intervals(
+                &ResolvedRoute::new(&trace, &wpts),
+                options,
+                BoundaryKind::Section,
+            )
+            .unwrap();
+            intervals[1].estimated_duration_s
+        };
+        // Turns hot 2 h in: after the runner reaches LB, before they leave it.
+        let turning = ForecastSeries::new(vec![(T0 + 7200, WEATHER_NEUTRAL), (T0 + 7260, hot)]);
+        let forecast = WeatherForecast::new(
+            vec!["LB".into(), "End".into()],
+            vec![turning.clone(), turning],
+        );
+        let static_hot =
+            run(&AnalysisOptions::default()
+                .weather(WeatherLookup::new(vec!["End".into()], vec![hot])));
+        assert!((run(&AnalysisOptions::default().forecast(forecast)) - static_hot).abs() < 1e-6);
+    }
+
+    #[test]
+    fn forecast_needs_a_race_clock() {
+        use crate::forecast::{ForecastSeries, WeatherForecast};
+        use crate::pace_model::{WeatherConditions, WEATHER_NEUTRAL};
+
+        let trace = make_trace(&flat_locs(10));
+        let wpts = vec![
+            make_waypoint(0.0, "Start", Some("Start"), None, None),
+            make_waypoint(0.009, "End", Some("Arrival"), None, None),
+        ];
+        let hot = WeatherConditions {
+            temperature_c: 32.0,
+            ..WEATHER_NEUTRAL
+        };
+        let forecast = WeatherForecast::new(
+            vec!["End".into()],
+            vec![ForecastSeries::hourly(0, vec![hot])],
+        );
+        let with = compute_intervals(
+            &ResolvedRoute::new(&trace, &wpts),
+            &AnalysisOptions::default().forecast(forecast),
+            BoundaryKind::Section,
+        )
+        .unwrap();
+        let without = compute_intervals(
+            &ResolvedRoute::new(&trace, &wpts),
+            &AnalysisOptions::default(),
+            BoundaryKind::Section,
+        )
+        .unwrap();
+        assert!((with[0].estimated_duration_s - without[0].estimated_duration_s).abs() < 1e-9);
+    }
 }
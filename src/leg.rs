@@ -5,6 +5,7 @@
 //! legs do not use the full physiological pace model.
 
 use crate::location::Location;
+use crate::route::ResolvedRoute;
 use crate::trace::Trace;
 use crate::waypoint::Waypoint;
 
@@ -38,26 +39,19 @@ pub struct LegStats {
 /// Returns an empty slice when fewer than 2 waypoints are provided.
 /// Uses Naismith's rule for duration (simple hiking estimate, no fatigue model).
 pub fn compute_from_waypoints(trace: &Trace, waypoints: &[Waypoint]) -> Vec<LegStats> {
-    if waypoints.len() < 2 {
-        return Vec::new();
-    }
+    compute_from_route(&ResolvedRoute::new(trace, waypoints))
+}
 
-    let mut legs = Vec::new();
-    let mut search_start = 0usize;
-    let mut current_section_idx = 0usize;
-    let mut section_active = false;
+/// [`compute_from_waypoints`] on a route already resolved.
+pub fn compute_from_route(route: &ResolvedRoute<'_>) -> Vec<LegStats> {
+    let trace = route.trace();
+    let waypoints = route.waypoints();
+    let mut legs = Vec::with_capacity(route.legs().len());
 
-    for i in 0..waypoints.len() - 1 {
-        if waypoints[i].is_section_boundary() {
-            if section_active {
-                current_section_idx += 1;
-            } else {
-                section_active = true;
-            }
-        }
-
-        let start_wpt = &waypoints[i];
-        let end_wpt = &waypoints[i + 1];
+    for span in route.legs() {
+        let (start_index, end_index) = (span.start_index, span.end_index);
+        let start_wpt = &waypoints[span.start_wpt];
+        let end_wpt = &waypoints[span.end_wpt];
 
         let start_target = Location {
             longitude: start_wpt.longitude,
@@ -70,20 +64,6 @@ pub fn compute_from_waypoints(trace: &Trace, waypoints: &[Waypoint]) -> Vec<LegS
             altitude: 0.0,
         };
 
-        let start_index = match trace.find_closest_point_from(&start_target, search_start) {
-            Some((_, idx, _)) => idx,
-            None => continue,
-        };
-        let end_index = match trace.find_closest_point_from(&end_target, start_index + 1) {
-            Some((_, idx, _)) => idx,
-            None => continue,
-        };
-        search_start = end_index;
-
-        if start_index >= end_index {
-            continue;
-        }
-
         let bearing = start_target.calculate_bearing_to(&end_target);
         let dist_km =
             trace.cumulative_distances[end_index] - trace.cumulative_distances[start_index];
@@ -130,8 +110,8 @@ pub fn compute_from_waypoints(trace: &Trace, waypoints: &[Waypoint]) -> Vec<LegS
         };
 
         legs.push(LegStats {
-            leg_id: i,
-            section_idx: current_section_idx,
+            leg_id: span.id,
+            section_idx: span.section_idx,
             start_index,
             end_index,
             point_count: end_index - start_index + 1,
@@ -181,6 +161,8 @@ mod tests {
             wpt_type: wpt_type.map(String::from),
             time: None,
             stop_duration: None,
+            surface: None,
+            crew: false,
         }
     }
 
@@ -1,43 +1,84 @@
 mod area;
 pub mod calibration;
 mod climbs;
+pub mod crew;
+pub mod cutoff;
 mod elevation;
+pub mod energy;
 mod error;
 mod extrema;
+pub mod forecast;
 pub mod gpx;
 #[cfg(test)]
 mod helper;
 mod interval;
 pub mod leg;
+pub mod lint;
 mod location;
 pub mod minetti;
+pub mod monte_carlo;
+pub mod nutrition;
 pub mod pace_model;
+pub mod pace_plan;
+pub mod race;
+pub mod route;
+pub mod schedule;
 pub mod section;
 pub mod segment;
 mod simplify;
+pub mod sleep;
+pub mod solar;
+pub mod splits;
 pub mod stage;
+pub mod surface;
+pub mod terrain;
 pub mod time;
+pub mod timeline;
 mod trace;
 #[cfg(feature = "wasm")]
 mod wasm;
 pub mod waypoint;
+pub mod wind;
 
 pub use area::Area;
+pub use calibration::fit::{PaceFit, SlopeBandModel};
+pub use calibration::history::{BandedEta, GradeMultipliers, HistoryRecalibration};
+pub use calibration::passages::{ActualSection, Passage, PassageRecalibration};
 pub use calibration::{BoundaryKind, RecalibratedEta, Recalibration};
 pub use climbs::ClimbStats;
-pub use elevation::{Elevation, GainLoss};
+pub use crew::{ArrivalWindow, CrewItinerary, DriveTimes, Rendezvous, Transfer, TransferRisk};
+pub use cutoff::CutoffRisk;
+pub use elevation::{Elevation, ElevationFill, GainLoss};
+pub use energy::{EnergyCurve, Gait};
 pub use error::TraceError;
-pub use gpx::{parse_metadata, parse_trace_points, parse_waypoints, GpxMetadata};
+pub use forecast::{ForecastSeries, WeatherForecast};
+pub use gpx::{
+    parse_metadata, parse_timed_trace_points, parse_trace_points, parse_waypoints, GpxMetadata,
+    TimedTrack,
+};
 pub use leg::LegStats;
+pub use lint::{Diagnostic, LintCode, LintReport, Severity};
 pub use location::Location;
-pub use pace_model::{AnalysisOptions, WeatherConditions, WeatherLookup};
+pub use monte_carlo::{FinishDistribution, MonteCarloOptions};
+pub use nutrition::{NutritionProfile, SectionNutrition};
+pub use pace_model::{AnalysisOptions, MinettiModel, PaceModel, WeatherConditions, WeatherLookup};
+pub use pace_plan::{PacePlan, PlanTarget};
+pub use race::{CheckpointGaps, CheckpointTime, Leaderboard, Race, Standing};
+pub use route::{Pin, ResolvedRoute, RouteSpan, SnapReport, WaypointSnap};
+pub use schedule::{ArrivalSchedule, PredictedPosition};
 pub use section::SectionStats;
+pub use sleep::{SleepBlock, SleepPlan};
+pub use splits::SplitStats;
 pub use stage::StageStats;
+pub use surface::{Surface, SurfaceMap};
+pub use terrain::{TerrainComparison, TerrainCost};
 pub use time::parse_iso8601_to_epoch;
+pub use timeline::Timeline;
 pub use trace::Trace;
 #[cfg(feature = "wasm")]
 pub use wasm::build_trace as build_wasm_trace;
 pub use waypoint::Waypoint;
+pub use wind::WindComponents;
 
 pub fn build_trace(locations: &[Location]) -> Result<Trace, TraceError> {
     Trace::new(locations)
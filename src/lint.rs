@@ -0,0 +1,317 @@
+//! Route validation before a course is published.
+//!
+//! [`lint`] checks a trace and its waypoints for the mistakes that make the
+//! analyses quietly wrong: missing Start or Arrival, unknown types, cutoffs
+//! out of order, stops on the wrong waypoints, duplicate names, waypoints
+//! off the track, and sections nobody can finish within their cutoff.
+
+use crate::calibration::BoundaryKind;
+use crate::pace_model::AnalysisOptions;
+use crate::route::ResolvedRoute;
+use crate::section;
+
+/// Waypoints further than this from the trace are flagged (km).
+pub const FAR_FROM_TRACK_KM: f64 = 0.25;
+
+/// The `<type>` values the analyses know about.
+pub const KNOWN_TYPES: [&str; 4] = ["Start", "TimeBarrier", "LifeBase", "Arrival"];
+
+/// How much a finding matters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "wasm", derive(serde::Serialize))]
+#[cfg_attr(feature = "wasm", serde(rename_all = "camelCase"))]
+pub enum Severity {
+    /// Suspicious, but the analyses still run as the author probably meant.
+    Warning,
+    /// The analyses will be wrong or incomplete.
+    Error,
+}
+
+/// What a finding is about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "wasm", derive(serde::Serialize))]
+#[cfg_attr(feature = "wasm", serde(rename_all = "camelCase"))]
+pub enum LintCode {
+    /// No waypoint of type `Start`.
+    MissingStart,
+    /// No waypoint of type `Arrival`.
+    MissingArrival,
+    /// A `<type>` outside [`KNOWN_TYPES`]; still treated as a section boundary.
+    UnknownType,
+    /// A boundary's `<time>` is not after the previous timed boundary's.
+    TimeNotIncreasing,
+    /// `<stopDuration>` on a waypoint that is not a LifeBase; it is applied anyway.
+    StopOnNonLifeBase,
+    /// Another waypoint has the same name; [`WeatherLookup::find`](crate::WeatherLookup::find)
+    /// and name-keyed options only see the first.
+    DuplicateName,
+    /// More than [`FAR_FROM_TRACK_KM`] from the trace point it snapped to.
+    FarFromTrack,
+    /// Could not be placed ahead of the previous waypoint; legs through it are dropped.
+    Unplaced,
+    /// The section's predicted duration under default options exceeds its cutoff.
+    InfeasibleCutoff,
+}
+
+/// One finding.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "wasm", derive(serde::Serialize))]
+#[cfg_attr(feature = "wasm", serde(rename_all = "camelCase"))]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub code: LintCode,
+    /// Index into the route's waypoints, when the finding is about one.
+    pub waypoint: Option<usize>,
+    /// [`SectionStats::section_id`](crate::SectionStats::section_id), for
+    /// [`LintCode::InfeasibleCutoff`].
+    pub section_id: Option<usize>,
+    /// Human-readable description.
+    pub message: String,
+}
+
+/// Every finding for a route, errors first. Caller owns this value.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "wasm", derive(serde::Serialize))]
+#[cfg_attr(feature = "wasm", serde(rename_all = "camelCase"))]
+pub struct LintReport {
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl LintReport {
+    pub fn has_errors(&self) -> bool {
+        self.diagnostics
+            .iter()
+            .any(|d| d.severity == Severity::Error)
+    }
+
+    pub fn is_clean(&self) -> bool {
+        self.diagnostics.is_empty()
+    }
+}
+
+/// Check `route` for publishing. Cutoff feasibility uses
+/// [`AnalysisOptions::default`]: a section flagged here is out of reach for
+/// the reference runner, not necessarily for every runner.
+pub fn lint(route: &ResolvedRoute<'_>) -> LintReport {
+    let waypoints = route.waypoints();
+    let mut diagnostics = Vec::new();
+    let mut push = |severity, code, waypoint, message: String| {
+        diagnostics.push(Diagnostic {
+            severity,
+            code,
+            waypoint,
+            section_id: None,
+            message,
+        });
+    };
+
+    for (wpt_type, code) in [
+        ("Start", LintCode::MissingStart),
+        ("Arrival", LintCode::MissingArrival),
+    ] {
+        if !waypoints
+            .iter()
+            .any(|w| w.wpt_type.as_deref() == Some(wpt_type))
+        {
+            push(
+                Severity::Error,
+                code,
+                None,
+                format!("no waypoint has type \"{wpt_type}\""),
+            );
+        }
+    }
+
+    let mut previous_time: Option<(i64, &str)> = None;
+    for (i, wpt) in waypoints.iter().enumerate() {
+        if let Some(t) = wpt.wpt_type.as_deref().filter(|t| !KNOWN_TYPES.contains(t)) {
+            push(
+                Severity::Warning,
+                LintCode::UnknownType,
+                Some(i),
+                format!("\"{}\" has unknown type \"{t}\"", wpt.name),
+            );
+        }
+        if let (true, Some(time)) = (wpt.is_section_boundary(), wpt.time) {
+            if let Some((_, name)) = previous_time.filter(|&(before, _)| time <= before) {
+                push(
+                    Severity::Error,
+                    LintCode::TimeNotIncreasing,
+                    Some(i),
+                    format!("\"{}\" closes no later than \"{name}\"", wpt.name),
+                );
+            }
+            previous_time = Some((time, &wpt.name));
+        }
+        if wpt.stop_duration.is_some() && wpt.wpt_type.as_deref() != Some("LifeBase") {
+            push(
+                Severity::Warning,
+                LintCode::StopOnNonLifeBase,
+                Some(i),
+                format!("\"{}\" has a stopDuration but is not a LifeBase", wpt.name),
+            );
+        }
+        if let Some(first) = waypoints[..i].iter().position(|w| w.name == wpt.name) {
+            push(
+                Severity::Warning,
+                LintCode::DuplicateName,
+                Some(i),
+                format!("\"{}\" repeats the name of waypoint {first}", wpt.name),
+            );
+        }
+    }
+
+    for snap in route.diagnostics().waypoints {
+        match snap.snap_distance_km {
+            None => push(
+                Severity::Error,
+                LintCode::Unplaced,
+                Some(snap.waypoint),
+                format!(
+                    "\"{}\" cannot be placed after the previous waypoint",
+                    snap.name
+                ),
+            ),
+            Some(km) if km > FAR_FROM_TRACK_KM => push(
+                Severity::Warning,
+                LintCode::FarFromTrack,
+                Some(snap.waypoint),
+                format!("\"{}\" is {km:.2} km from the track", snap.name),
+            ),
+            Some(_) => {}
+        }
+    }
+
+    let sections =
+        section::compute_from_route(route, &AnalysisOptions::default()).unwrap_or_default();
+    let spans = route.spans(&BoundaryKind::Section);
+    for s in sections {
+        let Some(ratio) = s.cutoff_ratio.filter(|&r| r > 1.0) else {
+            continue;
+        };
+        diagnostics.push(Diagnostic {
+            severity: Severity::Error,
+            code: LintCode::InfeasibleCutoff,
+            waypoint: spans
+                .iter()
+                .find(|span| span.id == s.section_id)
+                .map(|span| span.end_wpt),
+            section_id: Some(s.section_id),
+            message: format!(
+                "{} → {} needs {:.0}% of its cutoff",
+                s.start_location,
+                s.end_location,
+                ratio * 100.0
+            ),
+        });
+    }
+
+    diagnostics.sort_by_key(|d| std::cmp::Reverse(d.severity));
+    LintReport { diagnostics }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::location::Location;
+    use crate::trace::Trace;
+    use crate::waypoint::Waypoint;
+
+    fn make_trace(n: usize) -> Trace {
+        let locs: Vec<Location> = (0..n)
+            .map(|i| Location {
+                longitude: 0.0,
+                latitude: i as f64 * 0.001,
+                altitude: 100.0,
+            })
+            .collect();
+        Trace::new(&locs).unwrap()
+    }
+
+    fn make_waypoint(lat: f64, name: &str, wpt_type: Option<&str>, time: Option<i64>) -> Waypoint {
+        Waypoint {
+            latitude: lat,
+            longitude: 0.0,
+            elevation: None,
+            name: name.to_string(),
+            description: None,
+            comment: None,
+            symbol: None,
+            wpt_type: wpt_type.map(str::to_string),
+            time,
+            stop_duration: None,
+            surface: None,
+            crew: false,
+        }
+    }
+
+    fn codes(report: &LintReport) -> Vec<(Severity, LintCode, Option<usize>)> {
+        report
+            .diagnostics
+            .iter()
+            .map(|d| (d.severity, d.code, d.waypoint))
+            .collect()
+    }
+
+    #[test]
+    fn a_consistent_route_is_clean() {
+        let trace = make_trace(50);
+        let mut life_base = make_waypoint(0.020, "LB", Some("LifeBase"), Some(7200));
+        life_base.stop_duration = Some(1800);
+        let waypoints = vec![
+            make_waypoint(0.000, "Start", Some("Start"), Some(0)),
+            make_waypoint(0.010, "TB", Some("TimeBarrier"), Some(3600)),
+            life_base,
+            make_waypoint(0.049, "Finish", Some("Arrival"), Some(14_400)),
+        ];
+        let report = lint(&ResolvedRoute::new(&trace, &waypoints));
+        assert!(report.is_clean(), "{:?}", report.diagnostics);
+    }
+
+    #[test]
+    fn flags_each_mistake_errors_first() {
+        let trace = make_trace(50);
+        let mut spring = make_waypoint(0.005, "Spring", None, None);
+        spring.stop_duration = Some(300);
+        spring.longitude = 0.01; // ~1.1 km east of the track
+        let waypoints = vec![
+            make_waypoint(0.000, "Start", Some("Start"), Some(0)),
+            spring,
+            make_waypoint(0.010, "TB", Some("Timebarrier"), Some(7200)),
+            // Closes before TB, and ~3 km in 60 s is out of reach.
+            make_waypoint(0.030, "TB", Some("TimeBarrier"), Some(3600)),
+            make_waypoint(0.040, "Col", Some("LifeBase"), Some(3660)),
+        ];
+        let report = lint(&ResolvedRoute::new(&trace, &waypoints));
+        let found = codes(&report);
+
+        use LintCode::*;
+        use Severity::*;
+        assert!(found.contains(&(Error, MissingArrival, None)));
+        assert!(!found.iter().any(|&(_, code, _)| code == MissingStart));
+        assert!(found.contains(&(Warning, StopOnNonLifeBase, Some(1))));
+        assert!(found.contains(&(Warning, FarFromTrack, Some(1))));
+        assert!(found.contains(&(Warning, UnknownType, Some(2))));
+        assert!(found.contains(&(Error, TimeNotIncreasing, Some(3))));
+        assert!(found.contains(&(Warning, DuplicateName, Some(3))));
+        assert!(found.contains(&(Error, InfeasibleCutoff, Some(4))));
+        assert!(report.has_errors());
+        let first_warning = found.iter().position(|f| f.0 == Warning).unwrap();
+        assert!(found[first_warning..].iter().all(|f| f.0 == Warning));
+    }
+
+    #[test]
+    fn unplaced_waypoints_are_errors() {
+        let trace = make_trace(20);
+        let waypoints = vec![
+            make_waypoint(0.000, "Start", Some("Start"), None),
+            make_waypoint(0.019, "Finish", Some("Arrival"), None),
+            make_waypoint(0.010, "Back", None, None),
+        ];
+        let report = lint(&ResolvedRoute::new(&trace, &waypoints));
+        assert_eq!(
+            codes(&report),
+            vec![(Severity::Error, LintCode::Unplaced, Some(2))]
+        );
+    }
+}
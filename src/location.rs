@@ -11,6 +11,12 @@ pub struct Location {
 }
 
 impl Location {
+    /// `false` when the altitude is unknown (stored as `NaN`), e.g. a GPX
+    /// `<trkpt>` without `<ele>`.
+    pub fn has_altitude(&self) -> bool {
+        !self.altitude.is_nan()
+    }
+
     pub fn calculate_distance_to(&self, to: &Location) -> f64 {
         let earth_radius_kilometer = 6371.0;
 
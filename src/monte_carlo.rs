@@ -0,0 +1,566 @@
+//! Monte Carlo finish-time distribution.
+//!
+//! Samples base pace, fatigue, weather and stop durations from user-given
+//! [`Distribution`]s and replays the section model once per run. Reports
+//! per-checkpoint arrival percentiles and the probability of making each
+//! cutoff. A seeded RNG keeps results reproducible.
+
+use crate::calibration::BoundaryKind;
+use crate::interval::compute_intervals;
+use crate::pace_model::AnalysisOptions;
+use crate::route::ResolvedRoute;
+use crate::segment::{self, SegmentParams, SegmentState};
+use crate::sleep;
+use crate::trace::Trace;
+use crate::waypoint::Waypoint;
+
+/// Default number of simulated races.
+pub const DEFAULT_RUNS: usize = 1000;
+
+/// A random variable. Draws below zero are clamped to zero.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Distribution {
+    Fixed(f64),
+    Uniform { min: f64, max: f64 },
+    Normal { mean: f64, std_dev: f64 },
+    Triangular { min: f64, mode: f64, max: f64 },
+}
+
+impl Distribution {
+    fn sample(&self, rng: &mut Rng) -> f64 {
+        let x = match *self {
+            Distribution::Fixed(v) => v,
+            Distribution::Uniform { min, max } => min + (max - min) * rng.next_f64(),
+            Distribution::Normal { mean, std_dev } => mean + std_dev * rng.next_gaussian(),
+            Distribution::Triangular { min, mode, max } => {
+                let u = rng.next_f64();
+                let span = max - min;
+                if span <= 0.0 {
+                    min
+                } else if u < (mode - min) / span {
+                    min + (u * span * (mode - min)).sqrt()
+                } else {
+                    max - ((1.0 - u) * span * (max - mode)).sqrt()
+                }
+            }
+        };
+        x.max(0.0)
+    }
+}
+
+/// What to sample and how often. Unset pace/fatigue keep the
+/// [`AnalysisOptions`] value; multipliers default to a fixed 1.0.
+#[derive(Debug, Clone)]
+pub struct MonteCarloOptions {
+    pub runs: usize,
+    pub seed: u64,
+    /// Flat pace (s/km), drawn once per run.
+    pub base_pace_s_per_km: Option<Distribution>,
+    /// Fatigue coefficient, drawn once per run.
+    pub k_fatigue: Option<Distribution>,
+    /// Multiplier on the forecast weather factor, drawn per section.
+    pub weather_factor: Distribution,
+    /// Multiplier on each planned stop, drawn per stop. A sleep taken there
+    /// is added unscaled.
+    pub stop_factor: Distribution,
+}
+
+impl Default for MonteCarloOptions {
+    fn default() -> Self {
+        MonteCarloOptions {
+            runs: DEFAULT_RUNS,
+            seed: 0,
+            base_pace_s_per_km: None,
+            k_fatigue: None,
+            weather_factor: Distribution::Fixed(1.0),
+            stop_factor: Distribution::Fixed(1.0),
+        }
+    }
+}
+
+impl MonteCarloOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of simulated races.
+    pub fn runs(mut self, runs: usize) -> Self {
+        self.runs = runs;
+        self
+    }
+
+    /// RNG seed — same seed, same result.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Distribution of the flat pace (s/km).
+    pub fn base_pace(mut self, d: Distribution) -> Self {
+        self.base_pace_s_per_km = Some(d);
+        self
+    }
+
+    /// Distribution of the fatigue coefficient.
+    pub fn fatigue(mut self, d: Distribution) -> Self {
+        self.k_fatigue = Some(d);
+        self
+    }
+
+    /// Distribution of the weather-factor multiplier.
+    pub fn weather_factor(mut self, d: Distribution) -> Self {
+        self.weather_factor = d;
+        self
+    }
+
+    /// Distribution of the stop-duration multiplier.
+    pub fn stop_factor(mut self, d: Distribution) -> Self {
+        self.stop_factor = d;
+        self
+    }
+}
+
+/// Simulated arrival at one section end checkpoint.
+#[cfg_attr(feature = "wasm", derive(serde::Serialize))]
+#[cfg_attr(feature = "wasm", serde(rename_all = "camelCase"))]
+pub struct CheckpointDistribution {
+    pub section_id: usize,
+    pub end_index: usize,
+    pub end_location: String,
+    /// Elapsed time from race start on arrival (before the stop here), seconds.
+    pub arrival_p10_s: f64,
+    pub arrival_p50_s: f64,
+    pub arrival_p90_s: f64,
+    /// Checkpoint cutoff relative to race start (end_time − start time), or `None`.
+    pub cutoff_s: Option<i64>,
+    /// Share of runs arriving at or before `cutoff_s`.
+    pub p_beat_cutoff: Option<f64>,
+    /// The section's allowed completion time, as in `SectionStats`.
+    pub max_completion_time: Option<i64>,
+    /// Share of runs whose section duration (moving + stop, as in
+    /// `estimated_duration_s`) fits within `max_completion_time`.
+    pub p_within_max_completion: Option<f64>,
+}
+
+/// Result of a Monte Carlo simulation. Caller owns this value.
+#[cfg_attr(feature = "wasm", derive(serde::Serialize))]
+#[cfg_attr(feature = "wasm", serde(rename_all = "camelCase"))]
+pub struct FinishDistribution {
+    pub runs: usize,
+    /// One entry per section, in route order; the last is the finish.
+    pub checkpoints: Vec<CheckpointDistribution>,
+}
+
+/// Simulate the race `mc.runs` times at section granularity.
+///
+/// Sleep is planned once, from the deterministic run: a
+/// [`SleepAt::NotBefore`](crate::sleep::SleepAt::NotBefore) block stays at the
+/// checkpoint chosen there in every sample, however early or late it is
+/// reached, and keeps its planned length.
+///
+/// Returns `None` when fewer than 2 section boundaries exist or `mc.runs == 0`.
+pub fn simulate(
+    trace: &Trace,
+    waypoints: &[Waypoint],
+    options: &AnalysisOptions,
+    mc: &MonteCarloOptions,
+) -> Option<FinishDistribution> {
+    if mc.runs == 0 {
+        return None;
+    }
+    let route = ResolvedRoute::new(trace, waypoints);
+    let intervals = compute_intervals(&route, options, BoundaryKind::Section)?;
+    let clock_start = intervals.first().and_then(|m| m.start_time);
+    let surfaces = route.surfaces().overlaid(&options.surfaces);
+
+    let mut rng = Rng::new(mc.seed);
+    let mut arrivals = vec![Vec::with_capacity(mc.runs); intervals.len()];
+    let mut durations = vec![Vec::with_capacity(mc.runs); intervals.len()];
+
+    for _ in 0..mc.runs {
+        let base_pace_s_per_km = mc
+            .base_pace_s_per_km
+            .map_or(options.base_pace_s_per_km, |d| d.sample(&mut rng));
+        let k_fatigue = mc
+            .k_fatigue
+            .map_or(options.k_fatigue, |d| d.sample(&mut rng));
+        let mut state = SegmentState::new();
+        let mut elapsed_s = 0.0_f64;
+        let mut rested_until = None;
+
+        for (i, m) in intervals.iter().enumerate() {
+            let params = SegmentParams {
+                base_pace_s_per_km,
+                k_fatigue,
+                rested_until,
+                weather_scale: mc.weather_factor.sample(&mut rng),
+                ..SegmentParams::from_options(options, clock_start, Some(&surfaces)).interval(
+                    options,
+                    trace,
+                    &m.start_location,
+                    &m.end_location,
+                    m.start_index,
+                    m.end_index,
+                )
+            };
+            let metrics = segment::compute(trace, m.start_index, m.end_index, &params, &mut state);
+            state.d_eff_m *= 1.0 - m.end_recovery;
+            // Sleep keeps its planned length, as the recovery assumes.
+            let break_s = m.planned_stop_s - m.sleep_s;
+            let stop_s = if break_s > 0.0 {
+                break_s * mc.stop_factor.sample(&mut rng)
+            } else {
+                0.0
+            } + m.sleep_s;
+            state.race_s += stop_s;
+            if m.sleep_s > 0.0 {
+                rested_until = sleep::rested_until(clock_start, state.race_s);
+            }
+
+            elapsed_s += metrics.total_time;
+            arrivals[i].push(elapsed_s);
+            durations[i].push(metrics.total_time + stop_s);
+            elapsed_s += stop_s;
+        }
+    }
+
+    let runs = mc.runs as f64;
+    let checkpoints = intervals
+        .iter()
+        .zip(arrivals.iter_mut().zip(&durations))
+        .map(|(m, (arrival, duration))| {
+            arrival.sort_by(f64::total_cmp);
+            let cutoff_s = match (clock_start, m.end_time) {
+                (Some(t0), Some(t1)) if t1 > t0 => Some(t1 - t0),
+                _ => None,
+            };
+            CheckpointDistribution {
+                section_id: m.id,
+                end_index: m.end_index,
+                end_location: m.end_location.clone(),
+                arrival_p10_s: percentile(arrival, 10.0),
+                arrival_p50_s: percentile(arrival, 50.0),
+                arrival_p90_s: percentile(arrival, 90.0),
+                cutoff_s,
+                p_beat_cutoff: cutoff_s
+                    .map(|c| arrival.iter().filter(|&&a| a <= c as f64).count() as f64 / runs),
+                max_completion_time: m.max_completion_time,
+                p_within_max_completion: m
+                    .max_completion_time
+                    .filter(|&t| t > 0)
+                    .map(|t| duration.iter().filter(|&&d| d <= t as f64).count() as f64 / runs),
+            }
+        })
+        .collect();
+
+    Some(FinishDistribution {
+        runs: mc.runs,
+        checkpoints,
+    })
+}
+
+/// Nearest-rank percentile of an ascending, non-empty slice.
+fn percentile(sorted: &[f64], p: f64) -> f64 {
+    let rank = (p / 100.0 * (sorted.len() - 1) as f64).round() as usize;
+    sorted[rank.min(sorted.len() - 1)]
+}
+
+/// SplitMix64 — small, fast and good enough for sampling; not cryptographic.
+struct Rng {
+    state: u64,
+    spare_gaussian: Option<f64>,
+}
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        Rng {
+            state: seed,
+            spare_gaussian: None,
+        }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in `[0, 1)`.
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Standard normal (Box–Muller, caching the second value).
+    fn next_gaussian(&mut self) -> f64 {
+        if let Some(z) = self.spare_gaussian.take() {
+            return z;
+        }
+        let u1 = 1.0 - self.next_f64(); // (0, 1], keeps ln finite
+        let u2 = self.next_f64();
+        let r = (-2.0 * u1.ln()).sqrt();
+        let theta = 2.0 * std::f64::consts::PI * u2;
+        self.spare_gaussian = Some(r * theta.sin());
+        r * theta.cos()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::location::Location;
+    use crate::section;
+    use crate::sleep::{SleepBlock, SleepPlan};
+
+    fn make_trace(n: usize) -> Trace {
+        let locs: Vec<Location> = (0..n)
+            .map(|i| Location {
+                longitude: 0.0,
+                latitude: i as f64 * 0.001,
+                altitude: 100.0 + (i as f64 * 0.3).sin() * 20.0,
+            })
+            .collect();
+        Trace::new(&locs).unwrap()
+    }
+
+    fn make_waypoint(lat: f64, name: &str, wpt_type: &str, time: Option<i64>) -> Waypoint {
+        Waypoint {
+            latitude: lat,
+            longitude: 0.0,
+            elevation: None,
+            name: name.to_string(),
+            description: None,
+            comment: None,
+            symbol: None,
+            wpt_type: Some(wpt_type.to_string()),
+            time,
+            stop_duration: None,
+            surface: None,
+            crew: false,
+        }
+    }
+
+    const T0: i64 = 1_763_640_000; // 12:00 UTC
+
+    fn waypoints() -> Vec<Waypoint> {
+        vec![
+            make_waypoint(0.000, "Start", "Start", Some(T0)),
+            make_waypoint(0.050, "TB1", "TimeBarrier", Some(T0 + 3600)),
+            make_waypoint(0.100, "LB1", "LifeBase", Some(T0 + 9000)),
+            make_waypoint(0.199, "Finish", "Arrival", Some(T0 + 14400)),
+        ]
+    }
+
+    #[test]
+    fn fixed_inputs_reproduce_the_deterministic_model() {
+        let trace = make_trace(200);
+        let wpts = waypoints();
+        let options = AnalysisOptions::default();
+        let mc = MonteCarloOptions::new().runs(10);
+        let dist = simulate(&trace, &wpts, &options, &mc).unwrap();
+        let sections = section::compute_from_waypoints(&trace, &wpts, &options).unwrap();
+
+        assert_eq!(dist.checkpoints.len(), sections.len());
+        let mut elapsed = 0.0;
+        for (cp, s) in dist.checkpoints.iter().zip(&sections) {
+            let stop = s.stop_duration.map_or(
+                if s.end_location == "LB1" {
+                    options.life_base_stop_s as f64
+                } else {
+                    0.0
+                },
+                |sd| sd as f64,
+            );
+            elapsed += s.estimated_duration_s - stop;
+            assert!((cp.arrival_p50_s - elapsed).abs() < 1e-6);
+            assert_eq!(cp.arrival_p10_s, cp.arrival_p90_s);
+            assert_eq!(cp.max_completion_time, s.max_completion_time);
+            let within = s.cutoff_ratio.unwrap() <= 1.0;
+            assert_eq!(
+                cp.p_within_max_completion,
+                Some(if within { 1.0 } else { 0.0 })
+            );
+            elapsed += stop;
+        }
+    }
+
+    /// Doubles the time on every classified surface.
+    #[derive(Debug)]
+    struct Muddy;
+
+    impl crate::pace_model::PaceModel for Muddy {
+        fn surface_factor(&self, _surface: crate::surface::Surface) -> f64 {
+            2.0
+        }
+    }
+
+    #[test]
+    fn fixed_inputs_keep_a_custom_models_surface_factor() {
+        let trace = make_trace(200);
+        let mut wpts = waypoints();
+        wpts[1].surface = Some(crate::surface::Surface::Technical);
+        let options = AnalysisOptions::default().pace_model(Muddy);
+        let dist = simulate(&trace, &wpts, &options, &MonteCarloOptions::new().runs(3)).unwrap();
+        let sections = section::compute_from_waypoints(&trace, &wpts, &options).unwrap();
+
+        assert!(sections[1].surface_factor > 1.9);
+        let lb1 = &dist.checkpoints[1];
+        let moving_s = sections[0].estimated_duration_s + sections[1].estimated_duration_s
+            - options.life_base_stop_s as f64;
+        assert!((lb1.arrival_p50_s - moving_s).abs() < 1e-6);
+    }
+
+    #[test]
+    fn stop_factor_leaves_sleep_unscaled() {
+        let trace = make_trace(200);
+        let wpts = waypoints();
+        let options = AnalysisOptions::default()
+            .life_base_stop(1800)
+            .sleep(SleepPlan::new().block(SleepBlock::at_checkpoint("LB1", 3 * 3600)));
+        let run = |factor: f64| {
+            let mc = MonteCarloOptions::new()
+                .runs(3)
+                .stop_factor(Distribution::Fixed(factor));
+            simulate(&trace, &wpts, &options, &mc).unwrap()
+        };
+        let (planned, doubled) = (run(1.0), run(2.0));
+        let finish = |d: &FinishDistribution| d.checkpoints[2].arrival_p50_s;
+        assert!((finish(&doubled) - finish(&planned) - 1800.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn same_seed_same_result() {
+        let trace = make_trace(200);
+        let wpts = waypoints();
+        let mc = MonteCarloOptions::new()
+            .runs(200)
+            .seed(42)
+            .base_pace(Distribution::Normal {
+                mean: 500.0,
+                std_dev: 40.0,
+            })
+            .stop_factor(Distribution::Uniform { min: 0.5, max: 1.5 });
+        let a = simulate(&trace, &wpts, &AnalysisOptions::default(), &mc).unwrap();
+        let b = simulate(&trace, &wpts, &AnalysisOptions::default(), &mc).unwrap();
+        for (x, y) in a.checkpoints.iter().zip(&b.checkpoints) {
+            assert_eq!(x.arrival_p50_s, y.arrival_p50_s);
+            assert_eq!(x.p_beat_cutoff, y.p_beat_cutoff);
+        }
+        let c = simulate(
+            &trace,
+            &wpts,
+            &AnalysisOptions::default(),
+            &mc.clone().seed(7),
+        )
+        .unwrap();
+        assert_ne!(
+            a.checkpoints[2].arrival_p50_s,
+            c.checkpoints[2].arrival_p50_s
+        );
+    }
+
+    #[test]
+    fn percentiles_are_ordered_and_probabilities_bounded() {
+        let trace = make_trace(200);
+        let mc = MonteCarloOptions::new()
+            .runs(500)
+            .seed(1)
+            .base_pace(Distribution::Triangular {
+                min: 380.0,
+                mode: 480.0,
+                max: 700.0,
+            })
+            .fatigue(Distribution::Uniform {
+                min: 0.001,
+                max: 0.004,
+            })
+            .weather_factor(Distribution::Normal {
+                mean: 1.0,
+                std_dev: 0.05,
+            });
+        let dist = simulate(&trace, &waypoints(), &AnalysisOptions::default(), &mc).unwrap();
+        assert_eq!(dist.runs, 500);
+        let mut prev_p50 = 0.0;
+        for cp in &dist.checkpoints {
+            assert!(cp.arrival_p10_s < cp.arrival_p50_s);
+            assert!(cp.arrival_p50_s < cp.arrival_p90_s);
+            assert!(cp.arrival_p50_s > prev_p50);
+            prev_p50 = cp.arrival_p50_s;
+            let p = cp.p_beat_cutoff.unwrap();
+            assert!((0.0..=1.0).contains(&p));
+        }
+    }
+
+    #[test]
+    fn slower_pace_lowers_cutoff_probability() {
+        let trace = make_trace(200);
+        let wpts = waypoints();
+        let options = AnalysisOptions::default();
+        let spread = |mean| {
+            MonteCarloOptions::new()
+                .runs(300)
+                .seed(3)
+                .base_pace(Distribution::Normal {
+                    mean,
+                    std_dev: 60.0,
+                })
+        };
+        let fast = simulate(&trace, &wpts, &options, &spread(400.0)).unwrap();
+        let slow = simulate(&trace, &wpts, &options, &spread(700.0)).unwrap();
+        let last = fast.checkpoints.len() - 1;
+        assert!(
+            fast.checkpoints[last].p_beat_cutoff.unwrap()
+                > slow.checkpoints[last].p_beat_cutoff.unwrap()
+        );
+    }
+
+    #[test]
+    fn missing_cutoffs_and_zero_runs() {
+        let trace = make_trace(200);
+        let mut wpts = waypoints();
+        for w in &mut wpts {
+            w.time = None;
+        }
+        let dist = simulate(
+            &trace,
+            &wpts,
+            &AnalysisOptions::default(),
+            &MonteCarloOptions::new().runs(5),
+        )
+        .unwrap();
+        assert!(dist.checkpoints.iter().all(|c| c.p_beat_cutoff.is_none()));
+        assert!(simulate(
+            &trace,
+            &wpts,
+            &AnalysisOptions::default(),
+            &MonteCarloOptions::new().runs(0)
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn distributions_sample_within_support() {
+        let mut rng = Rng::new(9);
+        let tri = Distribution::Triangular {
+            min: 1.0,
+            mode: 2.0,
+            max: 4.0,
+        };
+        let uni = Distribution::Uniform { min: 3.0, max: 5.0 };
+        let mut normal_sum = 0.0;
+        for _ in 0..2000 {
+            let t = tri.sample(&mut rng);
+            assert!((1.0..=4.0).contains(&t));
+            let u = uni.sample(&mut rng);
+            assert!((3.0..5.0).contains(&u));
+            normal_sum += Distribution::Normal {
+                mean: 10.0,
+                std_dev: 1.0,
+            }
+            .sample(&mut rng);
+        }
+        assert!((normal_sum / 2000.0 - 10.0).abs() < 0.1);
+        assert_eq!(Distribution::Fixed(-3.0).sample(&mut rng), 0.0);
+    }
+}
@@ -0,0 +1,166 @@
+//! Energy, fluid and sodium needs between aid stations.
+//!
+//! Energy is the Minetti metabolic cost ([`minetti::cmet`](crate::minetti::cmet))
+//! integrated over the section and scaled by body mass. Fluid follows the
+//! moving time and the apparent temperature behind
+//! [`thermal_factor`](crate::pace_model::thermal_factor); sodium follows fluid.
+//! Every section boundary is treated as an aid station where the runner
+//! refills, so needs are checked against what the runner can carry.
+
+use crate::pace_model::WEATHER_T_OPT;
+
+pub const J_PER_KCAL: f64 = 4184.0;
+/// Sustainable food intake while moving (kcal/h); food to carry is capped at this rate.
+pub const MAX_INTAKE_KCAL_PER_H: f64 = 250.0;
+/// Fluid need at or below [`WEATHER_T_OPT`] apparent temperature (L/h).
+pub const FLUID_BASE_L_PER_H: f64 = 0.4;
+/// Extra fluid per °C of apparent temperature above [`WEATHER_T_OPT`] (L/h).
+pub const FLUID_HEAT_L_PER_H_PER_C: f64 = 0.04;
+/// Upper bound on fluid intake, roughly what the gut absorbs (L/h).
+pub const FLUID_MAX_L_PER_H: f64 = 1.2;
+/// Sodium to replace per litre of fluid (mg).
+pub const SODIUM_MG_PER_L: f64 = 600.0;
+
+pub const DEFAULT_MASS_KG: f64 = 70.0;
+pub const DEFAULT_FLUID_CAPACITY_L: f64 = 1.5;
+pub const DEFAULT_FOOD_CAPACITY_KCAL: f64 = 800.0;
+
+/// Runner mass and carrying capacity.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "wasm", derive(serde::Deserialize))]
+#[cfg_attr(feature = "wasm", serde(rename_all = "camelCase", default))]
+pub struct NutritionProfile {
+    /// Body mass including pack (kg).
+    pub mass_kg: f64,
+    /// Fluid the runner can carry between aid stations (L).
+    pub fluid_capacity_l: f64,
+    /// Food the runner can carry between aid stations (kcal).
+    pub food_capacity_kcal: f64,
+}
+
+impl Default for NutritionProfile {
+    fn default() -> Self {
+        Self {
+            mass_kg: DEFAULT_MASS_KG,
+            fluid_capacity_l: DEFAULT_FLUID_CAPACITY_L,
+            food_capacity_kcal: DEFAULT_FOOD_CAPACITY_KCAL,
+        }
+    }
+}
+
+impl NutritionProfile {
+    /// Default carrying capacity for a runner of `mass_kg`.
+    pub fn new(mass_kg: f64) -> Self {
+        Self {
+            mass_kg,
+            ..Self::default()
+        }
+    }
+
+    pub fn fluid_capacity(mut self, litres: f64) -> Self {
+        self.fluid_capacity_l = litres;
+        self
+    }
+
+    pub fn food_capacity(mut self, kcal: f64) -> Self {
+        self.food_capacity_kcal = kcal;
+        self
+    }
+}
+
+/// Needs for one section, moving time only.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "wasm", derive(serde::Serialize))]
+#[cfg_attr(feature = "wasm", serde(rename_all = "camelCase"))]
+pub struct SectionNutrition {
+    /// Energy expenditure (kcal).
+    pub energy_kcal: f64,
+    /// Food to carry: the expenditure, capped at [`MAX_INTAKE_KCAL_PER_H`] (kcal).
+    pub food_kcal: f64,
+    /// Fluid to carry (L).
+    pub fluid_l: f64,
+    /// Sodium to take (mg).
+    pub sodium_mg: f64,
+    /// `fluid_l` exceeds [`NutritionProfile::fluid_capacity_l`].
+    pub fluid_over_capacity: bool,
+    /// `food_kcal` exceeds [`NutritionProfile::food_capacity_kcal`].
+    pub food_over_capacity: bool,
+}
+
+impl SectionNutrition {
+    /// Either need exceeds what the runner carries.
+    pub fn over_capacity(&self) -> bool {
+        self.fluid_over_capacity || self.food_over_capacity
+    }
+}
+
+/// Fluid need (L/h) at an apparent temperature.
+pub fn fluid_rate_l_per_h(apparent_temp_c: f64) -> f64 {
+    (FLUID_BASE_L_PER_H + FLUID_HEAT_L_PER_H_PER_C * (apparent_temp_c - WEATHER_T_OPT).max(0.0))
+        .min(FLUID_MAX_L_PER_H)
+}
+
+/// Needs over `moving_s` seconds of running.
+///
+/// `metabolic_j_per_kg` is the summed `cmet × distance` and `heat_load_c_s`
+/// the summed `seconds × °C` of apparent temperature above [`WEATHER_T_OPT`]
+/// (see [`SegmentMetrics`](crate::segment::SegmentMetrics)).
+pub fn estimate(
+    profile: &NutritionProfile,
+    metabolic_j_per_kg: f64,
+    moving_s: f64,
+    heat_load_c_s: f64,
+) -> SectionNutrition {
+    let hours = moving_s.max(0.0) / 3600.0;
+    let energy_kcal = metabolic_j_per_kg * profile.mass_kg / J_PER_KCAL;
+    let food_kcal = energy_kcal.min(MAX_INTAKE_KCAL_PER_H * hours);
+    let fluid_l = (FLUID_BASE_L_PER_H * hours + FLUID_HEAT_L_PER_H_PER_C * heat_load_c_s / 3600.0)
+        .min(FLUID_MAX_L_PER_H * hours);
+    SectionNutrition {
+        energy_kcal,
+        food_kcal,
+        fluid_l,
+        sodium_mg: fluid_l * SODIUM_MG_PER_L,
+        fluid_over_capacity: fluid_l > profile.fluid_capacity_l,
+        food_over_capacity: food_kcal > profile.food_capacity_kcal,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::minetti::CMET_FLAT;
+
+    #[test]
+    fn flat_10k_costs_about_one_kcal_per_kg_per_km() {
+        let n = estimate(
+            &NutritionProfile::new(70.0),
+            CMET_FLAT * 10_000.0,
+            3600.0,
+            0.0,
+        );
+        assert!((n.energy_kcal - 602.3).abs() < 1.0);
+        assert!((n.food_kcal - MAX_INTAKE_KCAL_PER_H).abs() < 1e-9);
+        assert!((n.fluid_l - FLUID_BASE_L_PER_H).abs() < 1e-9);
+        assert!((n.sodium_mg - FLUID_BASE_L_PER_H * SODIUM_MG_PER_L).abs() < 1e-9);
+        assert!(!n.over_capacity());
+    }
+
+    #[test]
+    fn heat_raises_fluid_up_to_the_cap() {
+        assert!(fluid_rate_l_per_h(30.0) > fluid_rate_l_per_h(20.0));
+        assert_eq!(fluid_rate_l_per_h(0.0), FLUID_BASE_L_PER_H);
+        assert_eq!(fluid_rate_l_per_h(60.0), FLUID_MAX_L_PER_H);
+
+        // Four hours at 30 °C apparent.
+        let hot = estimate(
+            &NutritionProfile::default(),
+            0.0,
+            4.0 * 3600.0,
+            18.0 * 4.0 * 3600.0,
+        );
+        assert!((hot.fluid_l - 4.0 * fluid_rate_l_per_h(30.0)).abs() < 1e-9);
+        assert!(hot.fluid_over_capacity);
+        assert!(!hot.food_over_capacity);
+    }
+}
@@ -1,4 +1,13 @@
+use std::fmt;
+use std::sync::Arc;
+
+use crate::forecast::WeatherForecast;
 use crate::minetti;
+use crate::nutrition::NutritionProfile;
+use crate::sleep::SleepPlan;
+use crate::solar;
+use crate::surface::{Surface, SurfaceMap};
+use crate::wind;
 
 // ── Fatigue ───────────────────────────────────────────────────────────────────
 
@@ -21,12 +30,14 @@ pub fn fatigue_factor(d_eff_km: f64, k: f64) -> f64 {
 
 // ── Circadian ─────────────────────────────────────────────────────────────────
 
-/// Circadian pace multiplier (≥ 1.0) based on UTC time-of-day.
+/// Circadian pace multiplier (≥ 1.0) based on the time-of-day of `solar_time_s`.
 ///
-/// Smooth half-cosine bump centred at 3:30 UTC (peak sleep-deprivation), ±2 h window, up to +15%.
-pub fn circadian_factor(unix_time_s: i64) -> f64 {
-    let hours_utc = ((unix_time_s as f64) / 3600.0).rem_euclid(24.0);
-    let diff = hours_utc - 3.5;
+/// Smooth half-cosine bump centred at 3:30 (peak sleep-deprivation), ±2 h window, up to +15%.
+/// `segment::compute` passes an epoch shifted to the runner's local solar time
+/// (see [`solar::local_solar_offset_s`]), so the bump follows the sun, not UTC.
+pub fn circadian_factor(solar_time_s: i64) -> f64 {
+    let hours = ((solar_time_s as f64) / 3600.0).rem_euclid(24.0);
+    let diff = hours - 3.5;
     if diff.abs() < 2.0 {
         let normalized = diff / 2.0;
         1.0 + 0.15 * 0.5 * (1.0 + (std::f64::consts::PI * normalized).cos())
@@ -35,6 +46,41 @@ pub fn circadian_factor(unix_time_s: i64) -> f64 {
     }
 }
 
+// ── Darkness ──────────────────────────────────────────────────────────────────
+
+/// Pace penalty once the sun is below civil twilight (headtorch on technical ground).
+pub const DARKNESS_PENALTY: f64 = 0.10;
+
+/// Darkness pace multiplier (≥ 1.0) for a sun `sun_elevation_deg` above the horizon.
+///
+/// Neutral in daylight, ramps linearly through civil twilight from sunset
+/// ([`solar::SUNRISE_ELEVATION_DEG`]) to full `DARKNESS_PENALTY` at
+/// [`solar::CIVIL_TWILIGHT_ELEVATION_DEG`].
+pub fn darkness_factor(sun_elevation_deg: f64) -> f64 {
+    let depth = (solar::SUNRISE_ELEVATION_DEG - sun_elevation_deg)
+        / (solar::SUNRISE_ELEVATION_DEG - solar::CIVIL_TWILIGHT_ELEVATION_DEG);
+    1.0 + DARKNESS_PENALTY * depth.clamp(0.0, 1.0)
+}
+
+// ── Altitude ──────────────────────────────────────────────────────────────────
+
+/// Altitude below which there is no measurable hypoxia penalty (m).
+pub const ALTITUDE_THRESHOLD_M: f64 = 1500.0;
+/// Linear pace penalty per km above the threshold.
+pub const ALTITUDE_PER_KM: f64 = 0.04;
+/// Quadratic pace penalty per km² above the threshold — the drop steepens with height.
+pub const ALTITUDE_PER_KM2: f64 = 0.02;
+
+/// Hypoxia pace multiplier (≥ 1.0) at `altitude_m`.
+///
+/// The penalty starts above `ALTITUDE_THRESHOLD_M`, or above `acclimatization_m`
+/// (the altitude the runner is acclimatized to) when that is higher:
+/// 1.105 at 3000 m and 1.225 at 4000 m for a sea-level runner.
+pub fn altitude_factor(altitude_m: f64, acclimatization_m: f64) -> f64 {
+    let excess_km = (altitude_m - ALTITUDE_THRESHOLD_M.max(acclimatization_m)).max(0.0) / 1000.0;
+    1.0 + ALTITUDE_PER_KM * excess_km + ALTITUDE_PER_KM2 * excess_km * excess_km
+}
+
 // ── Composite pace factor ─────────────────────────────────────────────────────
 
 pub struct PaceFactors {
@@ -44,6 +90,7 @@ pub struct PaceFactors {
     pub combined: f64,
 }
 
+/// [`PaceModel::compute_factors`] with the built-in [`MinettiModel`].
 pub fn compute_factors(
     slope_frac: f64,
     d_eff_km: f64,
@@ -52,17 +99,118 @@ pub fn compute_factors(
     now_s: f64,
     weather_factor: f64,
 ) -> PaceFactors {
-    let terrain = minetti::pace_factor(slope_frac);
-    let fatigue = fatigue_factor(d_eff_km, k_fatigue);
-    let circadian = clock_start
-        .map(|t0| circadian_factor(t0 + now_s as i64))
-        .unwrap_or(1.0);
-    PaceFactors {
-        terrain,
-        combined: terrain * fatigue * circadian * weather_factor,
+    MinettiModel.compute_factors(
+        slope_frac,
+        d_eff_km,
+        k_fatigue,
+        clock_start,
+        now_s,
+        weather_factor,
+    )
+}
+
+// ── Pluggable model ───────────────────────────────────────────────────────────
+
+/// Per-segment pace multipliers applied by `segment::compute`.
+///
+/// Every method defaults to the built-in behaviour (Minetti terrain cost,
+/// exponential fatigue, 3:30 local-solar-time circadian bump, thermal/wind/precip weather),
+/// so an implementation only overrides the components it replaces — e.g. a
+/// hiking model swaps `terrain_factor` for Tobler's function.
+pub trait PaceModel: fmt::Debug + Send + Sync {
+    /// Terrain cost relative to flat for `slope_frac` (rise/run, not %).
+    /// Also weights the effort distance (d_eff) that drives fatigue.
+    fn terrain_factor(&self, slope_frac: f64) -> f64 {
+        minetti::pace_factor(slope_frac)
+    }
+
+    /// Fatigue multiplier after `d_eff_km` of effort-weighted distance.
+    fn fatigue_factor(&self, d_eff_km: f64, k_fatigue: f64) -> f64 {
+        fatigue_factor(d_eff_km, k_fatigue)
+    }
+
+    /// Time-of-day multiplier at `solar_time_s` (an epoch shifted to local solar time).
+    fn circadian_factor(&self, solar_time_s: i64) -> f64 {
+        circadian_factor(solar_time_s)
+    }
+
+    /// Darkness multiplier for a sun `sun_elevation_deg` above the horizon.
+    /// Only evaluated when darkness is enabled in [`AnalysisOptions::darkness`];
+    /// applied on top of `compute_factors`.
+    fn darkness_factor(&self, sun_elevation_deg: f64) -> f64 {
+        darkness_factor(sun_elevation_deg)
+    }
+
+    /// Hypoxia multiplier at `altitude_m` for a runner acclimatized to
+    /// `acclimatization_m`. Only evaluated when altitude is enabled in
+    /// [`AnalysisOptions::altitude`]; applied on top of `compute_factors`.
+    fn altitude_factor(&self, altitude_m: f64, acclimatization_m: f64) -> f64 {
+        altitude_factor(altitude_m, acclimatization_m)
+    }
+
+    /// Surface multiplier for a classified segment; unclassified segments
+    /// are not evaluated. Applied on top of `compute_factors`.
+    fn surface_factor(&self, surface: Surface) -> f64 {
+        surface.pace_factor()
+    }
+
+    /// Multiplier for one forecast.
+    fn weather_factor(&self, conditions: WeatherConditions) -> f64 {
+        weather_factor(conditions)
+    }
+
+    /// Multiplier for one forecast on a segment heading `bearing_deg`. With a
+    /// wind direction, the still-air `weather_factor` is combined with
+    /// [`wind::directional_wind_factor`]; without one, this is `weather_factor`.
+    fn weather_factor_along(&self, conditions: WeatherConditions, bearing_deg: f64) -> f64 {
+        match conditions.wind_dir_deg {
+            Some(from_deg) => {
+                let still = WeatherConditions {
+                    wind_kmh: 0.0,
+                    ..conditions
+                };
+                self.weather_factor(still)
+                    * wind::directional_wind_factor(wind::components(
+                        conditions.wind_kmh,
+                        from_deg,
+                        bearing_deg,
+                    ))
+            }
+            None => self.weather_factor(conditions),
+        }
+    }
+
+    /// Combine the components for one segment. `weather_factor` is passed in
+    /// pre-evaluated by `segment::compute` (once per interval, or per segment
+    /// for forecasts and directional wind). `clock_start` is in local solar
+    /// time, so `clock_start + now_s` feeds `circadian_factor` directly.
+    fn compute_factors(
+        &self,
+        slope_frac: f64,
+        d_eff_km: f64,
+        k_fatigue: f64,
+        clock_start: Option<i64>,
+        now_s: f64,
+        weather_factor: f64,
+    ) -> PaceFactors {
+        let terrain = self.terrain_factor(slope_frac);
+        let fatigue = self.fatigue_factor(d_eff_km, k_fatigue);
+        let circadian = clock_start
+            .map(|t0| self.circadian_factor(t0 + now_s as i64))
+            .unwrap_or(1.0);
+        PaceFactors {
+            terrain,
+            combined: terrain * fatigue * circadian * weather_factor,
+        }
     }
 }
 
+/// The built-in model: Minetti + exponential fatigue + circadian + weather.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MinettiModel;
+
+impl PaceModel for MinettiModel {}
+
 // ── Weather ───────────────────────────────────────────────────────────────────
 
 pub const WEATHER_T_OPT: f64 = 12.0;
@@ -81,6 +229,9 @@ pub struct WeatherConditions {
     pub humidity_pct: f64,
     pub wind_kmh: f64,
     pub precip_prob_pct: f64,
+    /// Direction the wind blows from (degrees clockwise from north). `None`
+    /// applies the speed-only [`wind_factor`] regardless of route bearing.
+    pub wind_dir_deg: Option<f64>,
 }
 
 /// Neutral forecast (cool, dry, calm). `weather_factor(WEATHER_NEUTRAL) == 1.0`.
@@ -89,6 +240,7 @@ pub const WEATHER_NEUTRAL: WeatherConditions = WeatherConditions {
     humidity_pct: 50.0,
     wind_kmh: 0.0,
     precip_prob_pct: 0.0,
+    wind_dir_deg: None,
 };
 
 /// Apparent (feels-like) temperature. Humidity amplifies heat stress above 20 °C only.
@@ -176,6 +328,22 @@ pub struct AnalysisOptions {
     pub life_base_stop_s: u32,
     /// Per-checkpoint weather forecast.
     pub weather: WeatherLookup,
+    /// Time-varying per-checkpoint forecast; takes precedence over `weather`
+    /// on intervals where either end checkpoint has a series.
+    pub forecast: WeatherForecast,
+    /// Pace model evaluated for every segment (default: [`MinettiModel`]).
+    pub pace_model: Arc<dyn PaceModel>,
+    /// Altitude the runner is acclimatized to (m). `None` disables the altitude factor.
+    pub altitude_acclimatization_m: Option<f64>,
+    /// Apply the darkness factor from the sun elevation at each segment.
+    pub darkness: bool,
+    /// Surface ranges over trace indices, overlaid on waypoint `<surface>` tags.
+    pub surfaces: SurfaceMap,
+    /// Planned sleep blocks (multi-day races).
+    pub sleep: SleepPlan,
+    /// Runner mass and carrying capacity; `None` skips the per-section
+    /// energy, fluid and sodium estimates.
+    pub nutrition: Option<NutritionProfile>,
 }
 
 impl Default for AnalysisOptions {
@@ -185,6 +353,13 @@ impl Default for AnalysisOptions {
             k_fatigue: K_FATIGUE,
             life_base_stop_s: DEFAULT_LIFE_BASE_STOP_S,
             weather: WeatherLookup::empty(),
+            forecast: WeatherForecast::empty(),
+            pace_model: Arc::new(MinettiModel),
+            altitude_acclimatization_m: None,
+            darkness: false,
+            surfaces: SurfaceMap::new(),
+            sleep: SleepPlan::new(),
+            nutrition: None,
         }
     }
 }
@@ -218,6 +393,50 @@ impl AnalysisOptions {
         self.weather = lookup;
         self
     }
+
+    /// Attach a time-varying forecast, evaluated at each segment's predicted clock time.
+    pub fn forecast(mut self, forecast: WeatherForecast) -> Self {
+        self.forecast = forecast;
+        self
+    }
+
+    /// Enable the altitude (hypoxia) factor for a runner acclimatized to
+    /// `acclimatization_m` (use `0.0` for a sea-level runner).
+    pub fn altitude(mut self, acclimatization_m: f64) -> Self {
+        self.altitude_acclimatization_m = Some(acclimatization_m);
+        self
+    }
+
+    /// Enable or disable the darkness factor (needs a race start time).
+    pub fn darkness(mut self, enabled: bool) -> Self {
+        self.darkness = enabled;
+        self
+    }
+
+    /// Classify trace index ranges by surface; these take precedence over
+    /// waypoint `<surface>` tags.
+    pub fn surfaces(mut self, surfaces: SurfaceMap) -> Self {
+        self.surfaces = surfaces;
+        self
+    }
+
+    /// Plan sleep blocks at checkpoints or clock times.
+    pub fn sleep(mut self, plan: SleepPlan) -> Self {
+        self.sleep = plan;
+        self
+    }
+
+    /// Estimate energy, fluid and sodium needs per section for this runner.
+    pub fn nutrition(mut self, profile: NutritionProfile) -> Self {
+        self.nutrition = Some(profile);
+        self
+    }
+
+    /// Replace the built-in Minetti pace model.
+    pub fn pace_model(mut self, model: impl PaceModel + 'static) -> Self {
+        self.pace_model = Arc::new(model);
+        self
+    }
 }
 
 #[cfg(test)]
@@ -297,6 +516,63 @@ mod tests {
         assert!((f.combined - expected).abs() < 1e-12);
     }
 
+    #[derive(Debug)]
+    struct FlatTerrain;
+
+    impl PaceModel for FlatTerrain {
+        fn terrain_factor(&self, _slope_frac: f64) -> f64 {
+            1.0
+        }
+    }
+
+    #[test]
+    fn custom_model_overrides_only_what_it_implements() {
+        let f = FlatTerrain.compute_factors(0.2, 50.0, K_FATIGUE, None, 0.0, 1.0);
+        assert!((f.terrain - 1.0).abs() < 1e-12);
+        assert!((f.combined - fatigue_factor(50.0, K_FATIGUE)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn minetti_model_matches_free_function() {
+        let free = compute_factors(0.1, 20.0, K_FATIGUE, Some(3 * 3600), 1800.0, 1.1);
+        let model = MinettiModel.compute_factors(0.1, 20.0, K_FATIGUE, Some(3 * 3600), 1800.0, 1.1);
+        assert!((free.combined - model.combined).abs() < 1e-12);
+        assert!((free.terrain - model.terrain).abs() < 1e-12);
+    }
+
+    #[test]
+    fn darkness_ramps_through_civil_twilight() {
+        assert!((darkness_factor(30.0) - 1.0).abs() < 1e-12);
+        assert!((darkness_factor(solar::SUNRISE_ELEVATION_DEG) - 1.0).abs() < 1e-12);
+        let dusk = darkness_factor(-3.0);
+        assert!(dusk > 1.0 && dusk < 1.0 + DARKNESS_PENALTY);
+        assert!((darkness_factor(-6.0) - (1.0 + DARKNESS_PENALTY)).abs() < 1e-12);
+        assert!((darkness_factor(-40.0) - (1.0 + DARKNESS_PENALTY)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn altitude_neutral_below_threshold() {
+        assert!((altitude_factor(ALTITUDE_THRESHOLD_M, 0.0) - 1.0).abs() < 1e-12);
+        assert!((altitude_factor(500.0, 0.0) - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn altitude_penalty_steepens_with_height() {
+        let at_3000 = altitude_factor(3000.0, 0.0);
+        let at_4000 = altitude_factor(4000.0, 0.0);
+        assert!((at_3000 - 1.105).abs() < 1e-9);
+        assert!((at_4000 - 1.225).abs() < 1e-9);
+        let step_low = altitude_factor(2500.0, 0.0) - altitude_factor(2000.0, 0.0);
+        let step_high = altitude_factor(3500.0, 0.0) - altitude_factor(3000.0, 0.0);
+        assert!(step_high > step_low);
+    }
+
+    #[test]
+    fn acclimatization_raises_the_threshold() {
+        assert!((altitude_factor(2500.0, 2500.0) - 1.0).abs() < 1e-12);
+        assert!(altitude_factor(3500.0, 2500.0) < altitude_factor(3500.0, 0.0));
+    }
+
     #[test]
     fn weather_neutral_returns_one() {
         assert!((weather_factor(WEATHER_NEUTRAL) - 1.0).abs() < 1e-9);
@@ -338,6 +614,7 @@ mod tests {
             humidity_pct: 85.0,
             wind_kmh: 35.0,
             precip_prob_pct: 80.0,
+            wind_dir_deg: None,
         };
         let lookup = WeatherLookup::new(vec!["Chamonix".into()], vec![hot]);
         assert!(lookup.factor_for("Chamonix") > 1.0);
@@ -360,6 +637,7 @@ mod tests {
             humidity_pct: 80.0,
             wind_kmh: 20.0,
             precip_prob_pct: 50.0,
+            wind_dir_deg: None,
         };
         let lookup = WeatherLookup::new(vec!["CP1".into()], vec![hot]);
         let opts = AnalysisOptions::new()
@@ -371,5 +649,19 @@ mod tests {
         assert!((opts.k_fatigue - 0.003).abs() < 1e-9);
         assert_eq!(opts.life_base_stop_s, 1200);
         assert!(opts.weather.factor_for("CP1") > 1.0);
+
+        assert!(opts.altitude_acclimatization_m.is_none());
+        assert_eq!(
+            AnalysisOptions::new()
+                .altitude(2000.0)
+                .altitude_acclimatization_m,
+            Some(2000.0)
+        );
+
+        assert!(!opts.darkness);
+        assert!(AnalysisOptions::new().darkness(true).darkness);
+
+        let flat = AnalysisOptions::new().pace_model(FlatTerrain);
+        assert!((flat.pace_model.terrain_factor(0.3) - 1.0).abs() < 1e-12);
     }
 }
@@ -0,0 +1,342 @@
+//! Pace plan: the base pace that hits a target time, split per section and per km.
+//!
+//! The forward model (pace → time) is monotonic in `base_pace_s_per_km`, so the
+//! inverse is found by bisection over [`interval::compute_intervals`](crate::interval)
+//! — fatigue, circadian, weather and planned LifeBase stops all apply.
+
+use crate::calibration::BoundaryKind;
+use crate::interval::{compute_intervals, compute_intervals_traced, IntervalMetrics};
+use crate::pace_model::AnalysisOptions;
+use crate::route::ResolvedRoute;
+use crate::trace::Trace;
+use crate::waypoint::Waypoint;
+
+/// Bisection bracket for the solved pace (s/km): 2:00/km … 60:00/km.
+pub const PLAN_PACE_MIN_S_PER_KM: f64 = 120.0;
+pub const PLAN_PACE_MAX_S_PER_KM: f64 = 3600.0;
+/// Stop bisecting once the bracket is narrower than this (s/km).
+const PLAN_PACE_TOLERANCE: f64 = 0.01;
+
+/// What the plan must hit, as elapsed seconds from the start.
+pub enum PlanTarget {
+    /// Arrival at the last section boundary.
+    Finish { elapsed_s: f64 },
+    /// Arrival (before any stop) at the named section boundary.
+    Checkpoint { name: String, elapsed_s: f64 },
+}
+
+/// Planned split for one section.
+#[cfg_attr(feature = "wasm", derive(serde::Serialize))]
+#[cfg_attr(feature = "wasm", serde(rename_all = "camelCase"))]
+pub struct SectionPlan {
+    pub section_id: usize,
+    pub start_location: String,
+    pub end_location: String,
+    pub distance_km: f64,
+    pub moving_s: f64,
+    /// Planned stop at the end checkpoint (explicit or LifeBase default).
+    pub stop_s: f64,
+    /// Average moving pace over the section (s/km).
+    pub pace_s_per_km: f64,
+    /// Elapsed time on arrival at the end checkpoint (s).
+    pub arrival_s: f64,
+    /// Elapsed time on leaving the end checkpoint (s).
+    pub departure_s: f64,
+}
+
+/// Planned split for one kilometre (the last one may be shorter).
+#[cfg_attr(feature = "wasm", derive(serde::Serialize))]
+#[cfg_attr(feature = "wasm", serde(rename_all = "camelCase"))]
+pub struct KmPlan {
+    /// 1-based kilometre number.
+    pub km: usize,
+    pub distance_km: f64,
+    pub moving_s: f64,
+    /// Moving pace over this kilometre (s/km).
+    pub pace_s_per_km: f64,
+    /// Elapsed time at the end of this kilometre, stops included (s).
+    pub elapsed_s: f64,
+}
+
+/// Solved plan. Caller owns this value.
+#[cfg_attr(feature = "wasm", derive(serde::Serialize))]
+#[cfg_attr(feature = "wasm", serde(rename_all = "camelCase"))]
+pub struct PacePlan {
+    /// Flat pace that meets the target under the current model.
+    pub base_pace_s_per_km: f64,
+    /// Predicted elapsed time at the target at that pace (s).
+    pub target_elapsed_s: f64,
+    pub sections: Vec<SectionPlan>,
+    pub kms: Vec<KmPlan>,
+}
+
+/// Solve for the base pace that reaches `target`, then lay out the plan.
+///
+/// Everything except `base_pace_s_per_km` is taken from `options`. Returns
+/// `None` when fewer than 2 section boundaries exist, the target checkpoint
+/// is not on the route, or the target cannot be met within
+/// [`PLAN_PACE_MIN_S_PER_KM`]..=[`PLAN_PACE_MAX_S_PER_KM`] (e.g. the planned
+/// stops alone exceed it).
+pub fn solve(
+    trace: &Trace,
+    waypoints: &[Waypoint],
+    options: &AnalysisOptions,
+    target: &PlanTarget,
+) -> Option<PacePlan> {
+    let (name, target_s) = match target {
+        PlanTarget::Finish { elapsed_s } => (None, *elapsed_s),
+        PlanTarget::Checkpoint { name, elapsed_s } => (Some(name.as_str()), *elapsed_s),
+    };
+    let route = ResolvedRoute::new(trace, waypoints);
+    let arrival_at = |pace: f64| -> Option<f64> {
+        let opts = options.clone().base_pace(pace);
+        let intervals = compute_intervals(&route, &opts, BoundaryKind::Section)?;
+        let last = match name {
+            Some(n) => intervals.iter().position(|m| m.end_location == n)?,
+            None => intervals.len().checked_sub(1)?,
+        };
+        let through: f64 = intervals[..=last]
+            .iter()
+            .map(|m| m.estimated_duration_s)
+            .sum();
+        Some(through - intervals[last].planned_stop_s)
+    };
+
+    let (mut lo, mut hi) = (PLAN_PACE_MIN_S_PER_KM, PLAN_PACE_MAX_S_PER_KM);
+    if arrival_at(lo)? > target_s || arrival_at(hi)? < target_s {
+        return None;
+    }
+    while hi - lo > PLAN_PACE_TOLERANCE {
+        let mid = (lo + hi) / 2.0;
+        if arrival_at(mid)? < target_s {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+    let base_pace_s_per_km = (lo + hi) / 2.0;
+
+    let plan_options = options.clone().base_pace(base_pace_s_per_km);
+    // Moving time of every trace segment, for the per-km breakdown.
+    let mut segment_s = vec![0.0_f64; trace.locations.len()];
+    let intervals = compute_intervals_traced(
+        &route,
+        &plan_options,
+        BoundaryKind::Section,
+        Some(&mut |sample, _| segment_s[sample.index] = sample.time_s),
+    )?;
+
+    let mut elapsed = 0.0_f64;
+    let sections = intervals
+        .iter()
+        .map(|m| {
+            let moving_s = m.estimated_duration_s - m.planned_stop_s;
+            let arrival_s = elapsed + moving_s;
+            elapsed = arrival_s + m.planned_stop_s;
+            SectionPlan {
+                section_id: m.id,
+                start_location: m.start_location.clone(),
+                end_location: m.end_location.clone(),
+                distance_km: m.total_distance_km,
+                moving_s,
+                stop_s: m.planned_stop_s,
+                pace_s_per_km: if m.total_distance_km > 0.0 {
+                    moving_s / m.total_distance_km
+                } else {
+                    0.0
+                },
+                arrival_s,
+                departure_s: elapsed,
+            }
+        })
+        .collect();
+
+    Some(PacePlan {
+        base_pace_s_per_km,
+        target_elapsed_s: arrival_at(base_pace_s_per_km)?,
+        sections,
+        kms: km_plan(trace, &intervals, &segment_s),
+    })
+}
+
+/// Bucket the solved intervals' per-segment moving time (`segment_s`) per km.
+fn km_plan(trace: &Trace, intervals: &[IntervalMetrics], segment_s: &[f64]) -> Vec<KmPlan> {
+    let mut kms: Vec<KmPlan> = Vec::new();
+    let mut bucket_km = 0.0_f64;
+    let mut bucket_s = 0.0_f64;
+    let mut elapsed = 0.0_f64;
+
+    for m in intervals {
+        for (j, &time_s) in (m.start_index..).zip(&segment_s[m.start_index..m.end_index]) {
+            let mut seg_km = trace.cumulative_distances[j + 1] - trace.cumulative_distances[j];
+            let mut seg_s = time_s;
+            // Split the segment across km boundaries proportionally to distance.
+            while seg_km > 0.0 && bucket_km + seg_km >= 1.0 {
+                let take_km = 1.0 - bucket_km;
+                let take_s = seg_s * take_km / seg_km;
+                bucket_s += take_s;
+                elapsed += take_s;
+                kms.push(KmPlan {
+                    km: kms.len() + 1,
+                    distance_km: 1.0,
+                    moving_s: bucket_s,
+                    pace_s_per_km: bucket_s,
+                    elapsed_s: elapsed,
+                });
+                seg_km -= take_km;
+                seg_s -= take_s;
+                bucket_km = 0.0;
+                bucket_s = 0.0;
+            }
+            bucket_km += seg_km;
+            bucket_s += seg_s;
+            elapsed += seg_s;
+        }
+        elapsed += m.planned_stop_s;
+    }
+    if bucket_km > 1e-9 {
+        kms.push(KmPlan {
+            km: kms.len() + 1,
+            distance_km: bucket_km,
+            moving_s: bucket_s,
+            pace_s_per_km: bucket_s / bucket_km,
+            elapsed_s: elapsed,
+        });
+    }
+    kms
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::location::Location;
+    use crate::section;
+
+    fn make_trace(n: usize) -> Trace {
+        let locs: Vec<Location> = (0..n)
+            .map(|i| Location {
+                longitude: 0.0,
+                latitude: i as f64 * 0.001,
+                altitude: 100.0 + (i as f64 * 0.2).sin() * 30.0,
+            })
+            .collect();
+        Trace::new(&locs).unwrap()
+    }
+
+    fn make_waypoint(lat: f64, name: &str, wpt_type: &str) -> Waypoint {
+        Waypoint {
+            latitude: lat,
+            longitude: 0.0,
+            elevation: None,
+            name: name.to_string(),
+            description: None,
+            comment: None,
+            symbol: None,
+            wpt_type: Some(wpt_type.to_string()),
+            time: None,
+            stop_duration: None,
+            surface: None,
+            crew: false,
+        }
+    }
+
+    fn waypoints() -> Vec<Waypoint> {
+        vec![
+            make_waypoint(0.000, "Start", "Start"),
+            make_waypoint(0.040, "TB1", "TimeBarrier"),
+            make_waypoint(0.080, "LB1", "LifeBase"),
+            make_waypoint(0.149, "Finish", "Arrival"),
+        ]
+    }
+
+    #[test]
+    fn finish_target_round_trips_through_forward_model() {
+        let trace = make_trace(150);
+        let wpts = waypoints();
+        let options = AnalysisOptions::default().life_base_stop(1800);
+        let target = 3.0 * 3600.0;
+        let plan = solve(
+            &trace,
+            &wpts,
+            &options,
+            &PlanTarget::Finish { elapsed_s: target },
+        )
+        .unwrap();
+
+        assert!((plan.target_elapsed_s - target).abs() < 1.0);
+        let forward = section::compute_from_waypoints(
+            &trace,
+            &wpts,
+            &options.clone().base_pace(plan.base_pace_s_per_km),
+        )
+        .unwrap();
+        let total: f64 = forward.iter().map(|s| s.estimated_duration_s).sum();
+        assert!((total - target).abs() < 1.0);
+        assert_eq!(plan.sections.len(), forward.len());
+        // LifeBase stop is part of the plan.
+        assert_eq!(plan.sections[1].stop_s, 1800.0);
+        assert!((plan.sections.last().unwrap().arrival_s - target).abs() < 1.0);
+    }
+
+    #[test]
+    fn checkpoint_target_is_met_on_arrival() {
+        let trace = make_trace(150);
+        let plan = solve(
+            &trace,
+            &waypoints(),
+            &AnalysisOptions::default(),
+            &PlanTarget::Checkpoint {
+                name: "LB1".to_string(),
+                elapsed_s: 5400.0,
+            },
+        )
+        .unwrap();
+        assert!((plan.sections[1].arrival_s - 5400.0).abs() < 1.0);
+        assert_eq!(plan.sections[1].end_location, "LB1");
+    }
+
+    #[test]
+    fn km_plan_covers_the_route_and_matches_sections() {
+        let trace = make_trace(150);
+        let plan = solve(
+            &trace,
+            &waypoints(),
+            &AnalysisOptions::default(),
+            &PlanTarget::Finish { elapsed_s: 12000.0 },
+        )
+        .unwrap();
+        let km_total: f64 = plan.kms.iter().map(|k| k.distance_km).sum();
+        assert!((km_total - trace.total_distance()).abs() < 1e-6);
+        let km_moving: f64 = plan.kms.iter().map(|k| k.moving_s).sum();
+        let section_moving: f64 = plan.sections.iter().map(|s| s.moving_s).sum();
+        assert!((km_moving - section_moving).abs() < 1e-6);
+        assert!((plan.kms.last().unwrap().elapsed_s - 12000.0).abs() < 1.0);
+        assert_eq!(plan.kms[0].km, 1);
+        assert!(plan.kms.windows(2).all(|w| w[0].elapsed_s < w[1].elapsed_s));
+    }
+
+    #[test]
+    fn infeasible_or_unknown_targets_yield_none() {
+        let trace = make_trace(150);
+        let wpts = waypoints();
+        let options = AnalysisOptions::default().life_base_stop(7200);
+        // Stops alone exceed the target.
+        assert!(solve(
+            &trace,
+            &wpts,
+            &options,
+            &PlanTarget::Finish { elapsed_s: 3600.0 }
+        )
+        .is_none());
+        assert!(solve(
+            &trace,
+            &wpts,
+            &options,
+            &PlanTarget::Checkpoint {
+                name: "Nowhere".to_string(),
+                elapsed_s: 3600.0,
+            }
+        )
+        .is_none());
+    }
+}
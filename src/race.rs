@@ -0,0 +1,477 @@
+//! Many runners on one course.
+//!
+//! A [`Race`] resolves the boundary waypoints and surfaces once, then keeps
+//! each runner's last snapped fix and checkpoint times. A [`Leaderboard`]
+//! recalibrates only the runners with new fixes since the last one, and
+//! projects finishing order and gaps at every checkpoint.
+
+use std::collections::HashMap;
+
+use crate::calibration::history::MAX_SNAP_DISTANCE_KM;
+use crate::calibration::{self, BoundaryKind, Recalibration, ResolvedRange};
+use crate::location::Location;
+use crate::pace_model::AnalysisOptions;
+use crate::route::ResolvedRoute;
+use crate::surface::SurfaceMap;
+use crate::trace::Trace;
+use crate::waypoint::Waypoint;
+
+/// One runner's progress.
+struct Runner {
+    id: String,
+    /// Trace index of the last fix.
+    index: usize,
+    /// Epoch s of the last fix.
+    time: i64,
+    /// Epoch s at each range's end checkpoint, once passed.
+    arrivals: Vec<Option<i64>>,
+    /// Cleared by every new fix.
+    recalibration: Option<Recalibration>,
+}
+
+/// One runner on the leaderboard.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "wasm", derive(serde::Serialize))]
+#[cfg_attr(feature = "wasm", serde(rename_all = "camelCase"))]
+pub struct Standing {
+    pub runner: String,
+    /// Predicted finishing position, 1-based.
+    pub rank: usize,
+    /// Position by distance covered now, 1-based; ties go to the earlier fix.
+    pub on_course_rank: usize,
+    pub current_index: usize,
+    pub distance_km: f64,
+    /// Race start to the last fix (s).
+    pub elapsed_s: f64,
+    /// Race start to the finish (s), actual once finished.
+    pub finish_s: Option<f64>,
+    /// Behind the predicted winner at the finish (s).
+    pub gap_s: Option<f64>,
+    pub finished: bool,
+}
+
+/// One runner at one checkpoint.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "wasm", derive(serde::Serialize))]
+#[cfg_attr(feature = "wasm", serde(rename_all = "camelCase"))]
+pub struct CheckpointTime {
+    pub runner: String,
+    /// Race start to arrival (s).
+    pub elapsed_s: f64,
+    /// Recalibrated ETA rather than a passed checkpoint.
+    pub projected: bool,
+    /// Behind the first runner there (s).
+    pub gap_s: f64,
+}
+
+/// Arrival order at the end checkpoint of one interval.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "wasm", derive(serde::Serialize))]
+#[cfg_attr(feature = "wasm", serde(rename_all = "camelCase"))]
+pub struct CheckpointGaps {
+    pub id: usize,
+    pub name: String,
+    pub end_index: usize,
+    /// Earliest first. Runners who passed it without a time are left out.
+    pub times: Vec<CheckpointTime>,
+}
+
+/// Projected standings and checkpoint gaps. Caller owns this value.
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "wasm", derive(serde::Serialize))]
+#[cfg_attr(feature = "wasm", serde(rename_all = "camelCase"))]
+pub struct Leaderboard {
+    /// Predicted finishing order.
+    pub standings: Vec<Standing>,
+    /// One entry per interval, in route order.
+    pub checkpoints: Vec<CheckpointGaps>,
+}
+
+/// Runners sharing one trace, one boundary resolution and one set of options.
+pub struct Race<'a> {
+    trace: &'a Trace,
+    options: &'a AnalysisOptions,
+    ranges: Vec<ResolvedRange<'a>>,
+    surfaces: SurfaceMap,
+    start_time: i64,
+    runners: Vec<Runner>,
+    by_id: HashMap<String, usize>,
+}
+
+impl<'a> Race<'a> {
+    /// `start_time` (epoch s) falls back to the first boundary's `<time>`.
+    ///
+    /// Returns `None` when fewer than 2 boundaries exist or the start time is unknown.
+    pub fn new(
+        trace: &'a Trace,
+        waypoints: &'a [Waypoint],
+        kind: BoundaryKind,
+        options: &'a AnalysisOptions,
+        start_time: Option<i64>,
+    ) -> Option<Self> {
+        Self::on_route(
+            &ResolvedRoute::new(trace, waypoints),
+            kind,
+            options,
+            start_time,
+        )
+    }
+
+    /// [`Race::new`] on a route already resolved.
+    pub fn on_route(
+        route: &ResolvedRoute<'a>,
+        kind: BoundaryKind,
+        options: &'a AnalysisOptions,
+        start_time: Option<i64>,
+    ) -> Option<Self> {
+        let trace = route.trace();
+        let (ranges, first_time) = calibration::resolve_ranges(route, &kind)?;
+        Some(Race {
+            trace,
+            options,
+            start_time: start_time.or(first_time)?,
+            surfaces: route.surfaces().overlaid(&options.surfaces),
+            ranges,
+            runners: Vec::new(),
+            by_id: HashMap::new(),
+        })
+    }
+
+    /// Runners registered by [`Race::ingest`].
+    pub fn runner_count(&self) -> usize {
+        self.runners.len()
+    }
+
+    /// Record a GPS fix for `runner`, registering them on their first
+    /// accepted one.
+    ///
+    /// The fix snaps forward from the runner's last one. Checkpoints passed
+    /// since then get a time interpolated by distance between the two fixes.
+    ///
+    /// Returns the snapped trace index, or `None` when the fix is older than
+    /// the runner's last one or more than [`MAX_SNAP_DISTANCE_KM`] from the
+    /// trace.
+    pub fn ingest(&mut self, runner: &str, location: &Location, time: i64) -> Option<usize> {
+        let known = self.by_id.get(runner).copied();
+        let (from_index, from_time) = match known {
+            Some(slot) => (self.runners[slot].index, self.runners[slot].time),
+            None => (
+                self.ranges.first().map_or(0, |rs| rs.start_index),
+                self.start_time,
+            ),
+        };
+        if time < from_time {
+            return None;
+        }
+        let (_, index, dist_km) = self.trace.find_closest_point_from(location, from_index)?;
+        if dist_km > MAX_SNAP_DISTANCE_KM {
+            return None;
+        }
+
+        // Register the runner only once their first fix is accepted.
+        let slot = known.unwrap_or_else(|| {
+            self.runners.push(Runner {
+                id: runner.to_string(),
+                index: from_index,
+                time: from_time,
+                arrivals: vec![None; self.ranges.len()],
+                recalibration: None,
+            });
+            self.by_id
+                .insert(runner.to_string(), self.runners.len() - 1);
+            self.runners.len() - 1
+        });
+        let r = &mut self.runners[slot];
+        let cum = &self.trace.cumulative_distances;
+        let (from_km, to_km) = (cum[r.index], cum[index]);
+        for (k, rs) in self.ranges.iter().enumerate() {
+            if r.index < rs.end_index && rs.end_index <= index && r.arrivals[k].is_none() {
+                let frac = if to_km > from_km {
+                    (cum[rs.end_index] - from_km) / (to_km - from_km)
+                } else {
+                    1.0
+                };
+                r.arrivals[k] = Some(r.time + (frac * (time - r.time) as f64).round() as i64);
+            }
+        }
+        r.index = index;
+        r.time = time;
+        r.recalibration = None;
+        Some(index)
+    }
+
+    /// Recalibration of `runner` from their last fix; `None` for an unknown runner.
+    pub fn recalibration(&mut self, runner: &str) -> Option<&Recalibration> {
+        let slot = *self.by_id.get(runner)?;
+        self.refresh(slot);
+        self.runners[slot].recalibration.as_ref()
+    }
+
+    /// Project every runner to every checkpoint still ahead of them and rank
+    /// them by predicted finish. Runners without new fixes reuse their last
+    /// recalibration.
+    pub fn leaderboard(&mut self) -> Leaderboard {
+        for slot in 0..self.runners.len() {
+            self.refresh(slot);
+        }
+
+        // Race start to each checkpoint, per runner.
+        let times: Vec<Vec<Option<(f64, bool)>>> = self
+            .runners
+            .iter()
+            .map(|r| self.checkpoint_times(r))
+            .collect();
+
+        let checkpoints = self
+            .ranges
+            .iter()
+            .enumerate()
+            .map(|(k, rs)| {
+                let mut times: Vec<CheckpointTime> = self
+                    .runners
+                    .iter()
+                    .zip(&times)
+                    .filter_map(|(r, t)| {
+                        t[k].map(|(elapsed_s, projected)| CheckpointTime {
+                            runner: r.id.clone(),
+                            elapsed_s,
+                            projected,
+                            gap_s: 0.0,
+                        })
+                    })
+                    .collect();
+                times.sort_by(|a, b| a.elapsed_s.total_cmp(&b.elapsed_s));
+                let first = times.first().map_or(0.0, |t| t.elapsed_s);
+                for t in &mut times {
+                    t.gap_s = t.elapsed_s - first;
+                }
+                CheckpointGaps {
+                    id: rs.id,
+                    name: rs.end_wpt_name.to_string(),
+                    end_index: rs.end_index,
+                    times,
+                }
+            })
+            .collect();
+
+        let cum = &self.trace.cumulative_distances;
+        let mut standings: Vec<Standing> = self
+            .runners
+            .iter()
+            .zip(&times)
+            .map(|(r, t)| {
+                let finish = t.last().copied().flatten();
+                Standing {
+                    runner: r.id.clone(),
+                    rank: 0,
+                    on_course_rank: 0,
+                    current_index: r.index,
+                    distance_km: cum[r.index],
+                    elapsed_s: (r.time - self.start_time) as f64,
+                    finish_s: finish.map(|(s, _)| s),
+                    gap_s: None,
+                    finished: finish.is_some_and(|(_, projected)| !projected),
+                }
+            })
+            .collect();
+
+        standings.sort_by(|a, b| {
+            b.distance_km
+                .total_cmp(&a.distance_km)
+                .then(a.elapsed_s.total_cmp(&b.elapsed_s))
+        });
+        for (i, s) in standings.iter_mut().enumerate() {
+            s.on_course_rank = i + 1;
+        }
+        // Runners without a finish projection go last.
+        standings.sort_by(|a, b| match (a.finish_s, b.finish_s) {
+            (Some(x), Some(y)) => x.total_cmp(&y),
+            (a, b) => b.is_some().cmp(&a.is_some()),
+        });
+        let winner = standings.first().and_then(|s| s.finish_s);
+        for (i, s) in standings.iter_mut().enumerate() {
+            s.rank = i + 1;
+            s.gap_s = s.finish_s.zip(winner).map(|(f, w)| f - w);
+        }
+
+        Leaderboard {
+            standings,
+            checkpoints,
+        }
+    }
+
+    fn refresh(&mut self, slot: usize) {
+        let r = &self.runners[slot];
+        if r.recalibration.is_some() {
+            return;
+        }
+        let recalibration = calibration::recalibrate_resolved(
+            self.trace,
+            &self.ranges,
+            Some(self.start_time),
+            &self.surfaces,
+            r.index,
+            (r.time - self.start_time) as f64,
+            self.options,
+        );
+        self.runners[slot].recalibration = Some(recalibration);
+    }
+
+    /// `(race start to arrival, projected)` at each checkpoint: the recorded
+    /// time when passed, the arrival ETA (before the stop there) when ahead.
+    fn checkpoint_times(&self, r: &Runner) -> Vec<Option<(f64, bool)>> {
+        let elapsed_s = (r.time - self.start_time) as f64;
+        let etas = r.recalibration.as_ref().map_or(&[][..], |c| &c.etas);
+        r.arrivals
+            .iter()
+            .enumerate()
+            .map(|(k, arrival)| match (arrival, etas.get(k)) {
+                (Some(t), _) => Some(((t - self.start_time) as f64, false)),
+                (None, Some(eta)) if eta.remaining_duration_s > 0.0 => {
+                    Some((elapsed_s + eta.cumulative_remaining_s - eta.stop_s, true))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calibration::recalibrate_from_current;
+
+    const T0: i64 = 1_763_625_600;
+
+    fn flat_trace() -> Trace {
+        let locs: Vec<Location> = (0..61)
+            .map(|i| Location {
+                longitude: 0.0,
+                latitude: i as f64 * 0.001,
+                altitude: 100.0,
+            })
+            .collect();
+        Trace::new(&locs).unwrap()
+    }
+
+    fn make_waypoint(lat: f64, name: &str, wpt_type: &str) -> Waypoint {
+        Waypoint {
+            latitude: lat,
+            longitude: 0.0,
+            elevation: None,
+            name: name.to_string(),
+            description: None,
+            comment: None,
+            symbol: None,
+            wpt_type: Some(wpt_type.to_string()),
+            time: None,
+            stop_duration: None,
+            surface: None,
+            crew: false,
+        }
+    }
+
+    fn waypoints() -> Vec<Waypoint> {
+        let mut wpts = vec![
+            make_waypoint(0.000, "Start", "Start"),
+            make_waypoint(0.020, "TB1", "TimeBarrier"),
+            make_waypoint(0.040, "LB1", "LifeBase"),
+            make_waypoint(0.060, "Arrival", "Arrival"),
+        ];
+        wpts[0].time = Some(T0);
+        wpts
+    }
+
+    fn at(index: usize) -> Location {
+        Location {
+            longitude: 0.0,
+            latitude: index as f64 * 0.001,
+            altitude: 100.0,
+        }
+    }
+
+    #[test]
+    fn ranks_by_projected_finish_with_checkpoint_gaps() {
+        let trace = flat_trace();
+        let wpts = waypoints();
+        let options = AnalysisOptions::default().life_base_stop(0);
+        let mut race = Race::new(&trace, &wpts, BoundaryKind::Section, &options, None).unwrap();
+
+        // "slow" is further along now but takes twice as long per km.
+        assert_eq!(race.ingest("fast", &at(10), T0 + 600), Some(10));
+        assert_eq!(race.ingest("fast", &at(30), T0 + 1800), Some(30));
+        assert_eq!(race.ingest("slow", &at(34), T0 + 4080), Some(34));
+        assert_eq!(race.runner_count(), 2);
+
+        let board = race.leaderboard();
+        let order: Vec<&str> = board.standings.iter().map(|s| s.runner.as_str()).collect();
+        assert_eq!(order, ["fast", "slow"]);
+        assert_eq!(board.standings[0].gap_s, Some(0.0));
+        assert!(board.standings[1].gap_s.unwrap() > 0.0);
+        assert_eq!(board.standings[1].on_course_rank, 1);
+        assert!(!board.standings[0].finished);
+
+        // TB1 (index 20) interpolated halfway between the fixes at 10 and 30.
+        let tb1 = &board.checkpoints[0];
+        assert_eq!(tb1.name, "TB1");
+        assert_eq!(tb1.times[0].runner, "fast");
+        assert_eq!(tb1.times[0].elapsed_s, 1200.0);
+        assert!(!tb1.times[0].projected);
+        assert_eq!(tb1.times[1].elapsed_s, 2400.0);
+        assert_eq!(tb1.times[1].gap_s, 1200.0);
+        // LB1 is still ahead of both.
+        assert!(board.checkpoints[1].times.iter().all(|t| t.projected));
+    }
+
+    #[test]
+    fn projections_match_a_single_runner_recalibration() {
+        let trace = flat_trace();
+        let wpts = waypoints();
+        let options = AnalysisOptions::default().life_base_stop(1800);
+        let mut race = Race::new(&trace, &wpts, BoundaryKind::Section, &options, None).unwrap();
+        race.ingest("a", &at(25), T0 + 2400);
+
+        let single =
+            recalibrate_from_current(&trace, &wpts, BoundaryKind::Section, 25, 2400.0, &options)
+                .unwrap();
+        let shared = race.recalibration("a").unwrap();
+        assert_eq!(shared.calibration_factor, single.calibration_factor);
+        let finish = single.etas.last().unwrap().cumulative_remaining_s;
+        assert_eq!(shared.etas.last().unwrap().cumulative_remaining_s, finish);
+
+        let board = race.leaderboard();
+        assert!((board.standings[0].finish_s.unwrap() - (2400.0 + finish)).abs() < 1e-6);
+        assert!(race.recalibration("b").is_none());
+    }
+
+    #[test]
+    fn ignores_stale_and_off_course_fixes() {
+        let trace = flat_trace();
+        let wpts = waypoints();
+        let options = AnalysisOptions::default();
+        let mut race = Race::new(&trace, &wpts, BoundaryKind::Section, &options, None).unwrap();
+        race.ingest("a", &at(30), T0 + 1800);
+        assert_eq!(race.ingest("a", &at(40), T0 + 1200), None);
+        let far = Location {
+            longitude: 0.01,
+            ..at(40)
+        };
+        assert_eq!(race.ingest("a", &far, T0 + 2400), None);
+
+        // A rejected first fix registers nobody.
+        assert_eq!(race.ingest("b", &far, T0 + 2400), None);
+        assert_eq!(race.ingest("c", &at(10), T0 - 60), None);
+        assert_eq!(race.runner_count(), 1);
+        assert!(race.recalibration("b").is_none());
+        assert_eq!(race.leaderboard().standings.len(), 1);
+
+        // Finishing records the arrival and ends the projection.
+        assert_eq!(race.ingest("a", &at(60), T0 + 3600), Some(60));
+        let board = race.leaderboard();
+        assert!(board.standings[0].finished);
+        assert_eq!(board.standings[0].finish_s, Some(3600.0));
+
+        let untimed = vec![make_waypoint(0.0, "Start", "Start"), waypoints().remove(3)];
+        assert!(Race::new(&trace, &untimed, BoundaryKind::Section, &options, None).is_none());
+    }
+}
@@ -0,0 +1,517 @@
+//! Waypoints resolved onto a trace once, for every analysis that needs them.
+//!
+//! Legs, sections, stages and recalibration all partition the route between
+//! consecutive waypoints of some kind and snap each pair onto trace indices.
+//! A [`ResolvedRoute`] does that for all three partitions up front, so a
+//! caller recalibrating every few seconds — or analysing the same route at
+//! several granularities — pays for the snapping once.
+
+use std::borrow::Cow;
+
+use crate::calibration::{self, BoundaryKind};
+use crate::location::Location;
+use crate::surface::SurfaceMap;
+use crate::trace::Trace;
+use crate::waypoint::Waypoint;
+
+/// One interval between two consecutive waypoints of a partition.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RouteSpan {
+    /// Position of the start waypoint among the partition's waypoints;
+    /// intervals that failed to resolve leave gaps.
+    pub id: usize,
+    /// Index of the start waypoint in the route's waypoints.
+    pub start_wpt: usize,
+    /// Index of the end waypoint in the route's waypoints.
+    pub end_wpt: usize,
+    pub start_index: usize,
+    pub end_index: usize,
+    /// Section containing the start waypoint.
+    pub section_idx: usize,
+    /// Stage containing the start waypoint.
+    pub stage_idx: usize,
+}
+
+/// Beyond a waypoint's nearest approach, how much further (km) another
+/// stretch of the trace may pass and still count as a competing pass.
+pub const PASS_MARGIN_KM: f64 = 0.1;
+
+/// Where to place a waypoint on the trace instead of snapping it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Pin {
+    /// Trace index, clamped to the last point.
+    Index(usize),
+    /// Cumulative distance from the start (km).
+    DistanceKm(f64),
+}
+
+impl Pin {
+    fn index(self, trace: &Trace) -> usize {
+        match self {
+            Pin::Index(idx) => idx.min(trace.locations.len() - 1),
+            Pin::DistanceKm(km) => trace.index_at_distance(km),
+        }
+    }
+}
+
+/// How one waypoint landed on the trace.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "wasm", derive(serde::Serialize))]
+#[cfg_attr(feature = "wasm", serde(rename_all = "camelCase"))]
+pub struct WaypointSnap {
+    /// Index into the route's waypoints.
+    pub waypoint: usize,
+    pub name: String,
+    /// Trace index; `None` when nothing lies ahead of the previous waypoint.
+    pub index: Option<usize>,
+    /// Waypoint to its trace point (km).
+    pub snap_distance_km: Option<f64>,
+    pub pinned: bool,
+    /// Separate stretches of trace within [`PASS_MARGIN_KM`] of the nearest approach.
+    pub passes: usize,
+    /// More than one pass: a loop or out-and-back may have snapped to the wrong one.
+    pub ambiguous: bool,
+    /// No pass lies at or after the previous waypoint's index, or the
+    /// waypoint did not resolve.
+    pub out_of_order: bool,
+}
+
+/// Snapping report for a route. Caller owns this value.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "wasm", derive(serde::Serialize))]
+#[cfg_attr(feature = "wasm", serde(rename_all = "camelCase"))]
+pub struct SnapReport {
+    /// One entry per waypoint, in order.
+    pub waypoints: Vec<WaypointSnap>,
+    /// Ids ([`RouteSpan::id`]) of intervals that did not resolve to a forward
+    /// stretch of trace, and are missing from the analyses.
+    pub dropped_legs: Vec<usize>,
+    pub dropped_sections: Vec<usize>,
+    pub dropped_stages: Vec<usize>,
+}
+
+/// One partition: its boundary waypoints and the spans between them.
+#[derive(Clone)]
+struct Partition {
+    /// Indices into the route's waypoints.
+    boundaries: Vec<usize>,
+    spans: Vec<RouteSpan>,
+}
+
+/// Legs, sections and stages of one trace and waypoint list, without the
+/// borrows — for owners of both that keep the resolution alongside them.
+#[derive(Clone)]
+pub(crate) struct RouteIndex {
+    /// Trace index each waypoint is pinned to, if any.
+    pins: Vec<Option<usize>>,
+    legs: Partition,
+    sections: Partition,
+    stages: Partition,
+    /// Ranges from waypoint `<surface>` tags, snapped like the partitions.
+    surfaces: SurfaceMap,
+}
+
+impl RouteIndex {
+    /// `pins` are `(waypoint index, pin)` pairs; out-of-range waypoints are ignored.
+    pub(crate) fn new(trace: &Trace, waypoints: &[Waypoint], pins: &[(usize, Pin)]) -> Self {
+        let mut pinned = vec![None; waypoints.len()];
+        for &(wpt, pin) in pins {
+            if let Some(slot) = pinned.get_mut(wpt) {
+                *slot = Some(pin.index(trace));
+            }
+        }
+        let section_of = membership(waypoints, Waypoint::is_section_boundary);
+        let stage_of = membership(waypoints, Waypoint::is_stage_boundary);
+        let partition = |filter: &dyn Fn(&Waypoint) -> bool| {
+            let boundaries: Vec<usize> = (0..waypoints.len())
+                .filter(|&i| filter(&waypoints[i]))
+                .collect();
+            let mut spans = resolve(trace, waypoints, &pinned, &boundaries);
+            for span in &mut spans {
+                span.section_idx = section_of[span.start_wpt];
+                span.stage_idx = stage_of[span.start_wpt];
+            }
+            Partition { boundaries, spans }
+        };
+        RouteIndex {
+            legs: partition(&|_| true),
+            sections: partition(&|w| calibration::is_boundary(w, &BoundaryKind::Section)),
+            stages: partition(&|w| calibration::is_boundary(w, &BoundaryKind::Stage)),
+            surfaces: SurfaceMap::from_pinned_waypoints(trace, waypoints, &pinned),
+            pins: pinned,
+        }
+    }
+}
+
+/// A trace and its waypoints, with legs, sections and stages resolved onto
+/// trace indices.
+pub struct ResolvedRoute<'a> {
+    trace: &'a Trace,
+    waypoints: &'a [Waypoint],
+    index: Cow<'a, RouteIndex>,
+}
+
+impl<'a> ResolvedRoute<'a> {
+    pub fn new(trace: &'a Trace, waypoints: &'a [Waypoint]) -> Self {
+        Self::with_pins(trace, waypoints, &[])
+    }
+
+    /// [`ResolvedRoute::new`], with the waypoints in `pins` — `(index into
+    /// waypoints, pin)` — placed on the trace instead of snapped. Later
+    /// waypoints snap forward from a pinned one as from a snapped one.
+    pub fn with_pins(trace: &'a Trace, waypoints: &'a [Waypoint], pins: &[(usize, Pin)]) -> Self {
+        ResolvedRoute {
+            trace,
+            waypoints,
+            index: Cow::Owned(RouteIndex::new(trace, waypoints, pins)),
+        }
+    }
+
+    /// A route over `index`, which must have been built from `trace` and `waypoints`.
+    #[cfg(feature = "wasm")]
+    pub(crate) fn with_index(
+        trace: &'a Trace,
+        waypoints: &'a [Waypoint],
+        index: &'a RouteIndex,
+    ) -> Self {
+        ResolvedRoute {
+            trace,
+            waypoints,
+            index: Cow::Borrowed(index),
+        }
+    }
+
+    pub fn trace(&self) -> &'a Trace {
+        self.trace
+    }
+
+    pub fn waypoints(&self) -> &'a [Waypoint] {
+        self.waypoints
+    }
+
+    /// Spans between every pair of consecutive waypoints.
+    pub fn legs(&self) -> &[RouteSpan] {
+        &self.index.legs.spans
+    }
+
+    /// Spans between consecutive `kind` boundaries.
+    pub fn spans(&self, kind: &BoundaryKind) -> &[RouteSpan] {
+        &self.partition(kind).spans
+    }
+
+    /// The `kind` boundary waypoints, in route order.
+    pub fn boundaries(&self, kind: &BoundaryKind) -> impl Iterator<Item = &'a Waypoint> + '_ {
+        let waypoints = self.waypoints;
+        self.partition(kind)
+            .boundaries
+            .iter()
+            .map(move |&i| &waypoints[i])
+    }
+
+    /// Surface ranges from the waypoints' `<surface>` tags, with pinned
+    /// waypoints kept at their pin.
+    pub fn surfaces(&self) -> &SurfaceMap {
+        &self.index.surfaces
+    }
+
+    /// Number of `kind` boundary waypoints, resolved or not.
+    pub fn boundary_count(&self, kind: &BoundaryKind) -> usize {
+        self.partition(kind).boundaries.len()
+    }
+
+    /// How each waypoint snapped between its neighbours (the legs
+    /// resolution), and which intervals were dropped.
+    pub fn diagnostics(&self) -> SnapReport {
+        let trace = self.trace;
+        let mut snapped: Vec<Option<usize>> = vec![None; self.waypoints.len()];
+        for span in self.legs() {
+            snapped[span.start_wpt].get_or_insert(span.start_index);
+            snapped[span.end_wpt] = Some(span.end_index);
+        }
+
+        let mut previous: Option<usize> = None;
+        let waypoints = self
+            .waypoints
+            .iter()
+            .enumerate()
+            .map(|(i, wpt)| {
+                let target = target(wpt);
+                let distances: Vec<f64> = trace
+                    .locations
+                    .iter()
+                    .map(|loc| target.calculate_distance_to(loc))
+                    .collect();
+                let nearest_km = distances.iter().copied().fold(f64::INFINITY, f64::min);
+                // Runs of points within reach of the nearest approach.
+                let reach_km = nearest_km + PASS_MARGIN_KM;
+                let passes = distances
+                    .iter()
+                    .zip(std::iter::once(&f64::INFINITY).chain(&distances))
+                    .filter(|&(&d, &before)| d <= reach_km && before > reach_km)
+                    .count();
+                let index = snapped[i];
+                // No pass left once the previous waypoint is reached.
+                let out_of_order = index.is_none()
+                    || previous.is_some_and(|p| distances[p..].iter().all(|&d| d > reach_km));
+                if index.is_some() {
+                    previous = index;
+                }
+                WaypointSnap {
+                    waypoint: i,
+                    name: wpt.name.clone(),
+                    index,
+                    snap_distance_km: index.map(|j| distances[j]),
+                    pinned: self.index.pins[i].is_some(),
+                    passes,
+                    ambiguous: passes > 1,
+                    out_of_order,
+                }
+            })
+            .collect();
+
+        let dropped = |partition: &Partition| {
+            let resolved: Vec<usize> = partition.spans.iter().map(|s| s.id).collect();
+            (0..partition.boundaries.len().saturating_sub(1))
+                .filter(|id| !resolved.contains(id))
+                .collect()
+        };
+        SnapReport {
+            waypoints,
+            dropped_legs: dropped(&self.index.legs),
+            dropped_sections: dropped(&self.index.sections),
+            dropped_stages: dropped(&self.index.stages),
+        }
+    }
+
+    fn partition(&self, kind: &BoundaryKind) -> &Partition {
+        match kind {
+            BoundaryKind::Section => &self.index.sections,
+            BoundaryKind::Stage => &self.index.stages,
+        }
+    }
+}
+
+/// For each waypoint, the 0-based index of the interval it starts in when
+/// `filter` marks the boundaries (0 before the first one).
+fn membership(waypoints: &[Waypoint], filter: fn(&Waypoint) -> bool) -> Vec<usize> {
+    let mut seen = 0usize;
+    waypoints
+        .iter()
+        .map(|w| {
+            seen += usize::from(filter(w));
+            seen.saturating_sub(1)
+        })
+        .collect()
+}
+
+fn target(w: &Waypoint) -> Location {
+    Location {
+        longitude: w.longitude,
+        latitude: w.latitude,
+        altitude: 0.0,
+    }
+}
+
+/// Snap consecutive pairs of `boundaries` onto the trace, each search
+/// continuing from the previous pair's end; `pinned` waypoints keep their
+/// index. Pairs that don't resolve to a forward interval are dropped.
+/// Membership is left at 0.
+fn resolve(
+    trace: &Trace,
+    waypoints: &[Waypoint],
+    pinned: &[Option<usize>],
+    boundaries: &[usize],
+) -> Vec<RouteSpan> {
+    let snap = |wpt: usize, from: usize| match pinned[wpt] {
+        Some(idx) => Some(idx),
+        None => trace
+            .find_closest_point_from(&target(&waypoints[wpt]), from)
+            .map(|(_, idx, _)| idx),
+    };
+    let mut spans = Vec::with_capacity(boundaries.len().saturating_sub(1));
+    let mut search_start = 0usize;
+    for (i, pair) in boundaries.windows(2).enumerate() {
+        let (start_wpt, end_wpt) = (pair[0], pair[1]);
+        let Some(start_index) = snap(start_wpt, search_start) else {
+            continue;
+        };
+        let Some(end_index) = snap(end_wpt, start_index + 1) else {
+            continue;
+        };
+        search_start = end_index;
+        if start_index >= end_index {
+            continue;
+        }
+        spans.push(RouteSpan {
+            id: i,
+            start_wpt,
+            end_wpt,
+            start_index,
+            end_index,
+            section_idx: 0,
+            stage_idx: 0,
+        });
+    }
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::surface::Surface;
+
+    fn make_trace(n: usize) -> Trace {
+        let locs: Vec<Location> = (0..n)
+            .map(|i| Location {
+                longitude: 0.0,
+                latitude: i as f64 * 0.001,
+                altitude: 100.0,
+            })
+            .collect();
+        Trace::new(&locs).unwrap()
+    }
+
+    fn make_waypoint(lat: f64, name: &str, wpt_type: Option<&str>) -> Waypoint {
+        Waypoint {
+            latitude: lat,
+            longitude: 0.0,
+            elevation: None,
+            name: name.to_string(),
+            description: None,
+            comment: None,
+            symbol: None,
+            wpt_type: wpt_type.map(str::to_string),
+            time: None,
+            stop_duration: None,
+            surface: None,
+            crew: false,
+        }
+    }
+
+    #[test]
+    fn resolves_every_partition_with_membership() {
+        let trace = make_trace(50);
+        let waypoints = vec![
+            make_waypoint(0.000, "Start", Some("Start")),
+            make_waypoint(0.005, "Spring", None),
+            make_waypoint(0.010, "TB1", Some("TimeBarrier")),
+            make_waypoint(0.020, "LB1", Some("LifeBase")),
+            make_waypoint(0.030, "View", None),
+            make_waypoint(0.040, "TB2", Some("TimeBarrier")),
+            make_waypoint(0.049, "Arrival", Some("Arrival")),
+        ];
+        let route = ResolvedRoute::new(&trace, &waypoints);
+
+        assert_eq!(route.legs().len(), 6);
+        let sections = route.spans(&BoundaryKind::Section);
+        let bounds: Vec<(usize, usize)> = sections
+            .iter()
+            .map(|s| (s.start_index, s.end_index))
+            .collect();
+        assert_eq!(bounds, [(0, 10), (10, 20), (20, 40), (40, 49)]);
+        let stage_of: Vec<usize> = sections.iter().map(|s| s.stage_idx).collect();
+        assert_eq!(stage_of, [0, 0, 1, 1]);
+        let section_of: Vec<usize> = route.legs().iter().map(|s| s.section_idx).collect();
+        assert_eq!(section_of, [0, 0, 1, 2, 2, 3]);
+
+        let stages = route.spans(&BoundaryKind::Stage);
+        assert_eq!(stages.len(), 2);
+        assert_eq!((stages[1].start_wpt, stages[1].end_wpt), (3, 6));
+        assert_eq!(route.boundary_count(&BoundaryKind::Stage), 3);
+        let names: Vec<&str> = route
+            .boundaries(&BoundaryKind::Stage)
+            .map(|w| w.name.as_str())
+            .collect();
+        assert_eq!(names, ["Start", "LB1", "Arrival"]);
+    }
+
+    #[test]
+    fn unresolvable_pairs_leave_gaps_in_ids() {
+        let trace = make_trace(20);
+        // "Back" sits before "Mid" on the trace, so Mid → Back cannot go forward.
+        let waypoints = vec![
+            make_waypoint(0.000, "Start", Some("Start")),
+            make_waypoint(0.019, "Mid", Some("TimeBarrier")),
+            make_waypoint(0.005, "Back", Some("Arrival")),
+        ];
+        let route = ResolvedRoute::new(&trace, &waypoints);
+        let ids: Vec<usize> = route
+            .spans(&BoundaryKind::Section)
+            .iter()
+            .map(|s| s.id)
+            .collect();
+        assert_eq!(ids, [0]);
+        assert_eq!(route.boundary_count(&BoundaryKind::Section), 3);
+
+        let report = route.diagnostics();
+        assert_eq!(report.dropped_sections, [1]);
+        assert_eq!(report.dropped_legs, [1]);
+        let back = &report.waypoints[2];
+        assert_eq!(back.index, None);
+        assert!(back.out_of_order);
+        assert!(!report.waypoints[1].out_of_order);
+        assert_eq!(report.waypoints[1].index, Some(19));
+    }
+
+    #[test]
+    fn flags_a_second_pass_and_honours_pins() {
+        // Out and back: north over 0..=20, then south over 20..=40.
+        let locs: Vec<Location> = (0..41)
+            .map(|i: i32| Location {
+                longitude: 0.0,
+                latitude: (20 - (20 - i).abs()) as f64 * 0.001,
+                altitude: 100.0,
+            })
+            .collect();
+        let trace = Trace::new(&locs).unwrap();
+        let waypoints = vec![
+            make_waypoint(0.000, "Start", Some("Start")),
+            make_waypoint(0.010, "Bridge", Some("TimeBarrier")),
+            make_waypoint(0.020, "Turn", None),
+            make_waypoint(0.000, "Finish", Some("Arrival")),
+        ];
+
+        let report = ResolvedRoute::new(&trace, &waypoints).diagnostics();
+        let bridge = &report.waypoints[1];
+        assert_eq!(bridge.index, Some(10));
+        assert_eq!(bridge.passes, 2);
+        assert!(bridge.ambiguous);
+        assert!(!bridge.pinned);
+        assert!(bridge.snap_distance_km.unwrap() < 1e-9);
+        assert_eq!(report.waypoints[2].passes, 1);
+        assert_eq!(report.waypoints[3].index, Some(40));
+        assert!(report.waypoints.iter().all(|w| !w.out_of_order));
+
+        // Pinned to the way back, Bridge now comes after Turn.
+        let back_km = trace.cumulative_distances[30];
+        for pin in [Pin::Index(30), Pin::DistanceKm(back_km)] {
+            let route = ResolvedRoute::with_pins(&trace, &waypoints, &[(1, pin)]);
+            let sections = route.spans(&BoundaryKind::Section);
+            assert_eq!((sections[0].start_index, sections[0].end_index), (0, 30));
+            let report = route.diagnostics();
+            assert!(report.waypoints[1].pinned);
+            assert_eq!(report.waypoints[1].index, Some(30));
+            assert!(report.waypoints[2].out_of_order);
+        }
+    }
+
+    #[test]
+    fn pins_move_surface_ranges() {
+        let trace = make_trace(50);
+        let mut scree = make_waypoint(0.020, "Scree", None);
+        scree.surface = Some(Surface::Rocky);
+        let waypoints = vec![
+            make_waypoint(0.000, "Start", Some("Start")),
+            scree,
+            make_waypoint(0.049, "Arrival", Some("Arrival")),
+        ];
+
+        let snapped = ResolvedRoute::new(&trace, &waypoints);
+        assert_eq!(snapped.surfaces().surface_at(19), None);
+        assert_eq!(snapped.surfaces().surface_at(20), Some(Surface::Rocky));
+
+        let pinned = ResolvedRoute::with_pins(&trace, &waypoints, &[(1, Pin::Index(30))]);
+        assert_eq!(pinned.surfaces().surface_at(29), None);
+        assert_eq!(pinned.surfaces().surface_at(30), Some(Surface::Rocky));
+    }
+}
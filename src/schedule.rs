@@ -0,0 +1,289 @@
+//! Predicted clock time at every trace point.
+//!
+//! A forward simulation of the race from a start epoch: the same section walk
+//! as [`section::compute_from_waypoints`](crate::section::compute_from_waypoints)
+//! (weather, fatigue recovery, LifeBase stops and sleep), recorded per trace
+//! index. [`ArrivalSchedule::position_at`] and
+//! [`ArrivalSchedule::time_at_distance`] answer "where will I be at 2am?" and
+//! "when do I reach km 87?".
+
+use crate::calibration::BoundaryKind;
+use crate::interval::compute_intervals_traced;
+use crate::location::Location;
+use crate::pace_model::AnalysisOptions;
+use crate::route::ResolvedRoute;
+use crate::timeline;
+use crate::trace::Trace;
+use crate::waypoint::Waypoint;
+
+/// Predicted arrival and departure at each trace index.
+#[derive(Debug, Clone)]
+pub struct ArrivalSchedule {
+    /// Race start (epoch s).
+    pub start_time: i64,
+    /// Seconds from the start to arriving at each point, before any stop there.
+    pub arrival_s: Vec<f64>,
+    /// Seconds from the start to leaving each point; larger than `arrival_s`
+    /// only at checkpoints with a stop.
+    pub departure_s: Vec<f64>,
+    cumulative_distances: Vec<f64>,
+    locations: Vec<Location>,
+}
+
+/// Where the runner is predicted to be at a given time.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "wasm", derive(serde::Serialize))]
+#[cfg_attr(feature = "wasm", serde(rename_all = "camelCase"))]
+pub struct PredictedPosition {
+    /// Index of the last trace point reached.
+    pub index: usize,
+    /// Distance from the trace start (km).
+    pub distance_km: f64,
+    /// Interpolated between `index` and the next point.
+    pub location: Location,
+    /// Stopped at a checkpoint (`index`).
+    pub stopped: bool,
+}
+
+impl ArrivalSchedule {
+    /// Simulate the race from `start_time` (epoch s).
+    ///
+    /// With at least two section boundaries the walk follows the sections,
+    /// and `start_time` replaces the Start waypoint's `<time>` for the
+    /// circadian and forecast clock. Points before the first boundary share
+    /// the start time, points after the last one its arrival. Without
+    /// boundaries the whole trace is one stretch with neutral weather and no
+    /// stops, as in [`timeline::compute`].
+    pub fn compute(
+        trace: &Trace,
+        waypoints: &[Waypoint],
+        options: &AnalysisOptions,
+        start_time: i64,
+    ) -> Self {
+        let n = trace.locations.len();
+        let mut waypoints = waypoints.to_vec();
+        if let Some(start) = waypoints.iter_mut().find(|w| w.is_section_boundary()) {
+            start.time = Some(start_time);
+        }
+
+        let mut moving: Vec<Option<f64>> = vec![None; n];
+        let intervals = compute_intervals_traced(
+            &ResolvedRoute::new(trace, &waypoints),
+            options,
+            BoundaryKind::Section,
+            Some(&mut |sample, state| moving[sample.index + 1] = Some(state.elapsed_s)),
+        );
+
+        let mut stop_s = vec![0.0_f64; n];
+        let moving: Vec<f64> = match intervals {
+            Some(intervals) if !intervals.is_empty() => {
+                for m in &intervals {
+                    stop_s[m.end_index] += m.planned_stop_s;
+                }
+                // Forward-fill points the walk did not reach.
+                let mut last = 0.0_f64;
+                moving
+                    .into_iter()
+                    .map(|t| {
+                        last = t.unwrap_or(last);
+                        last
+                    })
+                    .collect()
+            }
+            _ => timeline::compute(trace, options, Some(start_time)).cumulative_time_s,
+        };
+
+        let mut arrival_s = Vec::with_capacity(n);
+        let mut departure_s = Vec::with_capacity(n);
+        let mut stopped = 0.0_f64;
+        for (t, stop) in moving.iter().zip(&stop_s) {
+            arrival_s.push(t + stopped);
+            stopped += stop;
+            departure_s.push(t + stopped);
+        }
+
+        Self {
+            start_time,
+            arrival_s,
+            departure_s,
+            cumulative_distances: trace.cumulative_distances.clone(),
+            locations: trace.locations.clone(),
+        }
+    }
+
+    /// Predicted arrival at trace index `index` (epoch s).
+    pub fn arrival_time(&self, index: usize) -> Option<i64> {
+        self.arrival_s
+            .get(index)
+            .map(|t| self.start_time + t.round() as i64)
+    }
+
+    /// Predicted finish (epoch s).
+    pub fn finish_time(&self) -> i64 {
+        self.start_time + self.arrival_s.last().copied().unwrap_or(0.0).round() as i64
+    }
+
+    /// Where the runner is at `time` (epoch s); `None` before the start or
+    /// after the finish.
+    pub fn position_at(&self, time: i64) -> Option<PredictedPosition> {
+        let t = (time - self.start_time) as f64;
+        let reached = self.arrival_s.partition_point(|&a| a <= t);
+        let index = reached.checked_sub(1)?;
+        let last = self.arrival_s.len() - 1;
+        if t < self.departure_s[index] || index == last {
+            if index == last && t > self.arrival_s[last] {
+                return None;
+            }
+            return Some(PredictedPosition {
+                index,
+                distance_km: self.cumulative_distances[index],
+                location: self.locations[index],
+                stopped: t < self.departure_s[index],
+            });
+        }
+
+        let span = self.arrival_s[index + 1] - self.departure_s[index];
+        let frac = if span > 0.0 {
+            (t - self.departure_s[index]) / span
+        } else {
+            0.0
+        };
+        let (a, b) = (&self.locations[index], &self.locations[index + 1]);
+        let lerp = |x: f64, y: f64| x + (y - x) * frac;
+        Some(PredictedPosition {
+            index,
+            distance_km: lerp(
+                self.cumulative_distances[index],
+                self.cumulative_distances[index + 1],
+            ),
+            location: Location {
+                longitude: lerp(a.longitude, b.longitude),
+                latitude: lerp(a.latitude, b.latitude),
+                altitude: lerp(a.altitude, b.altitude),
+            },
+            stopped: false,
+        })
+    }
+
+    /// Predicted arrival at `distance_km` from the trace start (epoch s);
+    /// `None` outside the trace.
+    pub fn time_at_distance(&self, distance_km: f64) -> Option<i64> {
+        let total = *self.cumulative_distances.last()?;
+        if !(0.0..=total).contains(&distance_km) {
+            return None;
+        }
+        let i = self
+            .cumulative_distances
+            .partition_point(|&d| d < distance_km);
+        if i == 0 {
+            return self.arrival_time(0);
+        }
+        let (d0, d1) = (
+            self.cumulative_distances[i - 1],
+            self.cumulative_distances[i],
+        );
+        let frac = if d1 > d0 {
+            (distance_km - d0) / (d1 - d0)
+        } else {
+            1.0
+        };
+        let t = self.departure_s[i - 1] + (self.arrival_s[i] - self.departure_s[i - 1]) * frac;
+        Some(self.start_time + t.round() as i64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::section;
+
+    const T0: i64 = 1_763_625_600;
+
+    fn make_trace(n: usize) -> Trace {
+        let locs: Vec<Location> = (0..n)
+            .map(|i| Location {
+                longitude: 0.0,
+                latitude: i as f64 * 0.001,
+                altitude: 100.0 + i as f64 * 2.0,
+            })
+            .collect();
+        Trace::new(&locs).unwrap()
+    }
+
+    fn make_waypoint(lat: f64, name: &str, wpt_type: &str) -> Waypoint {
+        Waypoint {
+            latitude: lat,
+            longitude: 0.0,
+            elevation: None,
+            name: name.to_string(),
+            description: None,
+            comment: None,
+            symbol: None,
+            wpt_type: Some(wpt_type.to_string()),
+            time: None,
+            stop_duration: None,
+            surface: None,
+            crew: false,
+        }
+    }
+
+    #[test]
+    fn arrivals_match_sections_including_life_base_stop() {
+        let trace = make_trace(41);
+        let waypoints = vec![
+            make_waypoint(0.0, "Start", "Start"),
+            make_waypoint(0.020, "Base", "LifeBase"),
+            make_waypoint(0.040, "Finish", "Arrival"),
+        ];
+        let options = AnalysisOptions::default().life_base_stop(1800);
+        let schedule = ArrivalSchedule::compute(&trace, &waypoints, &options, T0);
+
+        let mut timed = waypoints.clone();
+        timed[0].time = Some(T0);
+        let sections = section::compute_from_waypoints(&trace, &timed, &options).unwrap();
+        let base = sections[0].end_index;
+        assert!((schedule.arrival_s[base] - sections[0].predicted_arrival_s).abs() < 1e-6);
+        assert!((schedule.departure_s[base] - schedule.arrival_s[base] - 1800.0).abs() < 1e-6);
+        assert!((schedule.arrival_s[40] - sections[1].predicted_arrival_s).abs() < 1e-6);
+        assert!(schedule.arrival_s.windows(2).all(|w| w[1] > w[0]));
+    }
+
+    #[test]
+    fn lookups_invert_each_other() {
+        let trace = make_trace(41);
+        let waypoints = vec![
+            make_waypoint(0.0, "Start", "Start"),
+            make_waypoint(0.020, "Base", "LifeBase"),
+            make_waypoint(0.040, "Finish", "Arrival"),
+        ];
+        let options = AnalysisOptions::default().life_base_stop(1800);
+        let schedule = ArrivalSchedule::compute(&trace, &waypoints, &options, T0);
+
+        let km = 3.3;
+        let t = schedule.time_at_distance(km).unwrap();
+        let pos = schedule.position_at(t).unwrap();
+        assert!((pos.distance_km - km).abs() < 0.01);
+        assert!(!pos.stopped);
+
+        // Mid-stop at the LifeBase.
+        let at_base = schedule.arrival_time(20).unwrap() + 900;
+        let pos = schedule.position_at(at_base).unwrap();
+        assert!(pos.stopped);
+        assert_eq!(pos.index, 20);
+
+        assert!(schedule.position_at(T0 - 1).is_none());
+        assert!(schedule.position_at(schedule.finish_time() + 1).is_none());
+        assert!(schedule.time_at_distance(-1.0).is_none());
+        assert_eq!(schedule.time_at_distance(0.0), Some(T0));
+    }
+
+    #[test]
+    fn without_boundaries_follows_the_timeline() {
+        let trace = make_trace(11);
+        let options = AnalysisOptions::default();
+        let schedule = ArrivalSchedule::compute(&trace, &[], &options, T0);
+        let tl = timeline::compute(&trace, &options, Some(T0));
+        assert_eq!(schedule.arrival_s, tl.cumulative_time_s);
+        assert_eq!(schedule.arrival_s, schedule.departure_s);
+    }
+}
@@ -1,5 +1,9 @@
+use crate::calibration::BoundaryKind;
+use crate::cutoff::CutoffRisk;
 use crate::interval::{compute_intervals, IntervalMetrics};
+use crate::nutrition::SectionNutrition;
 use crate::pace_model::AnalysisOptions;
+use crate::route::ResolvedRoute;
 use crate::trace::Trace;
 use crate::waypoint::Waypoint;
 
@@ -23,7 +27,8 @@ pub struct SectionStats {
     pub start_time: Option<i64>,
     pub end_time: Option<i64>,
     pub bearing: f64,
-    /// 1–5 based on average Minetti pace factor (1→<1.1, 2→<1.4, 3→<1.8, 4→<2.5, 5→≥2.5).
+    /// 1–5 from the average Minetti pace factor (1→<1.1, 2→<1.4, 3→<1.8, 4→<2.5, 5→≥2.5),
+    /// raised by `technicality` (see [`surface::combined_difficulty`](crate::surface::combined_difficulty)).
     pub difficulty: u8,
     /// Moving time + planned stop at end checkpoint (seconds).
     pub estimated_duration_s: f64,
@@ -34,6 +39,33 @@ pub struct SectionStats {
     pub cutoff_ratio: Option<f64>,
     /// Planned stop at the end checkpoint (seconds), from `<stopDuration>` or `None`.
     pub stop_duration: Option<u32>,
+    /// Predicted time from race start to arrival at the end checkpoint, before its stop (seconds).
+    pub predicted_arrival_s: f64,
+    /// End checkpoint's absolute cutoff minus predicted arrival (minutes); `None` without
+    /// a start or end `<time>`. Negative means the cutoff is missed.
+    pub cutoff_slack_min: Option<f64>,
+    pub cutoff_risk: Option<CutoffRisk>,
+    /// Hypoxia slowdown over the section: moving time with the altitude factor ÷
+    /// without it. 1.0 unless [`AnalysisOptions::altitude`] is set.
+    pub altitude_factor: f64,
+    /// Moving seconds added by the altitude factor (included in `estimated_duration_s`).
+    pub altitude_time_s: f64,
+    /// Moving seconds added by running between dusk and dawn (included in
+    /// `estimated_duration_s`). 0 unless [`AnalysisOptions::darkness`] is set.
+    pub darkness_time_s: f64,
+    /// Surface slowdown over the section: moving time with surface classes ÷
+    /// without them. 1.0 when no surface is set; below 1.0 on road.
+    pub surface_factor: f64,
+    /// Technicality level 1 (road) – 5 (boulder field), distance-weighted over
+    /// the classified part of the section; `None` when nothing is classified.
+    /// Feeds `difficulty` together with the grade.
+    pub technicality: Option<u8>,
+    /// Planned sleep taken at the end checkpoint (seconds; included in
+    /// `estimated_duration_s`). See [`AnalysisOptions::sleep`].
+    pub sleep_s: f64,
+    /// Energy, fluid and sodium needs for the moving time, checked against
+    /// the runner's carrying capacity. `None` unless [`AnalysisOptions::nutrition`] is set.
+    pub nutrition: Option<SectionNutrition>,
 }
 
 impl From<IntervalMetrics> for SectionStats {
@@ -62,6 +94,16 @@ impl From<IntervalMetrics> for SectionStats {
             max_completion_time: m.max_completion_time,
             cutoff_ratio: m.cutoff_ratio,
             stop_duration: m.stop_duration,
+            predicted_arrival_s: m.predicted_arrival_s,
+            cutoff_slack_min: m.cutoff_slack_min,
+            cutoff_risk: m.cutoff_risk,
+            altitude_factor: m.altitude_factor,
+            altitude_time_s: m.altitude_time_s,
+            darkness_time_s: m.darkness_time_s,
+            surface_factor: m.surface_factor,
+            technicality: m.technicality,
+            sleep_s: m.sleep_s,
+            nutrition: m.nutrition,
         }
     }
 }
@@ -75,13 +117,15 @@ pub fn compute_from_waypoints(
     waypoints: &[Waypoint],
     options: &AnalysisOptions,
 ) -> Option<Vec<SectionStats>> {
-    let intervals = compute_intervals(
-        trace,
-        waypoints,
-        options,
-        Waypoint::is_section_boundary,
-        true,
-    )?;
+    compute_from_route(&ResolvedRoute::new(trace, waypoints), options)
+}
+
+/// [`compute_from_waypoints`] on a route already resolved.
+pub fn compute_from_route(
+    route: &ResolvedRoute<'_>,
+    options: &AnalysisOptions,
+) -> Option<Vec<SectionStats>> {
+    let intervals = compute_intervals(route, options, BoundaryKind::Section)?;
     Some(intervals.into_iter().map(SectionStats::from).collect())
 }
 
@@ -89,7 +133,10 @@ pub fn compute_from_waypoints(
 mod tests {
     use super::*;
     use crate::location::Location;
-    use crate::pace_model::AnalysisOptions;
+    use crate::nutrition::NutritionProfile;
+    use crate::pace_model::{AnalysisOptions, WeatherConditions, WeatherLookup, WEATHER_NEUTRAL};
+    use crate::sleep::{SleepBlock, SleepPlan};
+    use crate::surface::{Surface, SurfaceMap};
 
     fn make_trace(n: usize) -> Trace {
         let locs: Vec<Location> = (0..n)
@@ -114,6 +161,8 @@ mod tests {
             wpt_type: wpt_type.map(String::from),
             time,
             stop_duration: None,
+            surface: None,
+            crew: false,
         }
     }
 
@@ -196,6 +245,257 @@ mod tests {
         assert_eq!(sections[0].end_time, Some(1_007_200));
     }
 
+    #[test]
+    fn cutoff_slack_counts_from_race_start_and_excludes_end_stop() {
+        let trace = make_trace(12);
+        let mut waypoints = vec![
+            make_waypoint(0.0, "Start", Some("Start"), Some(1_000_000)),
+            make_waypoint(0.005, "LB1", Some("LifeBase"), None),
+            make_waypoint(0.011, "TB2", Some("TimeBarrier"), Some(1_000_000 + 86_400)),
+        ];
+        waypoints[1].stop_duration = Some(1800);
+        let sections =
+            compute_from_waypoints(&trace, &waypoints, &AnalysisOptions::default()).unwrap();
+
+        assert_eq!(sections[0].cutoff_slack_min, None);
+        assert_eq!(sections[0].cutoff_risk, None);
+        assert!(
+            (sections[0].predicted_arrival_s - (sections[0].estimated_duration_s - 1800.0)).abs()
+                < 1e-6
+        );
+
+        let arrival = sections[0].estimated_duration_s + sections[1].estimated_duration_s;
+        assert!((sections[1].predicted_arrival_s - arrival).abs() < 1e-6);
+        let slack = sections[1].cutoff_slack_min.unwrap();
+        assert!((slack - (86_400.0 - arrival) / 60.0).abs() < 1e-6);
+        assert_eq!(sections[1].cutoff_risk, Some(CutoffRisk::Safe));
+    }
+
+    #[test]
+    fn unreachable_cutoff_is_missed() {
+        let trace = make_trace(12);
+        let waypoints = vec![
+            make_waypoint(0.0, "Start", Some("Start"), Some(1_000_000)),
+            make_waypoint(0.011, "TB", Some("TimeBarrier"), Some(1_000_060)),
+        ];
+        let sections =
+            compute_from_waypoints(&trace, &waypoints, &AnalysisOptions::default()).unwrap();
+        assert!(sections[0].cutoff_slack_min.unwrap() < 0.0);
+        assert_eq!(sections[0].cutoff_risk, Some(CutoffRisk::Missed));
+    }
+
+    #[test]
+    fn altitude_factor_is_reported_per_section() {
+        // 1500 m → ~3500 m over 0.5 km, then a flat high section.
+        let locs: Vec<Location> = (0..12)
+            .map(|i| Location {
+                longitude: 0.0,
+                latitude: i as f64 * 0.001,
+                altitude: if i < 6 { 1500.0 } else { 3500.0 },
+            })
+            .collect();
+        let trace = Trace::new(&locs).unwrap();
+        let waypoints = vec![
+            make_waypoint(0.0, "Start", Some("Start"), None),
+            make_waypoint(0.005, "Col", Some("TimeBarrier"), None),
+            make_waypoint(0.011, "Summit", Some("Arrival"), None),
+        ];
+        let off = compute_from_waypoints(&trace, &waypoints, &AnalysisOptions::default()).unwrap();
+        assert!(off
+            .iter()
+            .all(|s| s.altitude_factor == 1.0 && s.altitude_time_s == 0.0));
+
+        let on = compute_from_waypoints(
+            &trace,
+            &waypoints,
+            &AnalysisOptions::default().altitude(0.0),
+        )
+        .unwrap();
+        assert!((on[0].altitude_factor - 1.0).abs() < 1e-9);
+        assert!(on[1].altitude_factor > 1.1);
+        assert!(
+            (on[1].estimated_duration_s - off[1].estimated_duration_s - on[1].altitude_time_s)
+                .abs()
+                < 1e-6
+        );
+
+        let acclimatized = compute_from_waypoints(
+            &trace,
+            &waypoints,
+            &AnalysisOptions::default().altitude(3000.0),
+        )
+        .unwrap();
+        assert!(acclimatized[1].altitude_factor < on[1].altitude_factor);
+    }
+
+    #[test]
+    fn darkness_time_only_accrues_at_night() {
+        let trace = make_trace(12);
+        let run = |start: i64| {
+            let waypoints = vec![
+                make_waypoint(0.0, "Start", Some("Start"), Some(start)),
+                make_waypoint(0.011, "Finish", Some("Arrival"), None),
+            ];
+            compute_from_waypoints(
+                &trace,
+                &waypoints,
+                &AnalysisOptions::default().darkness(true),
+            )
+            .unwrap()
+        };
+        // Longitude 0 on 1970-01-01: midnight is full dark, noon is full day.
+        let night = run(0);
+        let day = run(12 * 3600);
+        assert!(night[0].darkness_time_s > 0.0);
+        assert_eq!(day[0].darkness_time_s, 0.0);
+
+        let waypoints = vec![
+            make_waypoint(0.0, "Start", Some("Start"), Some(0)),
+            make_waypoint(0.011, "Finish", Some("Arrival"), None),
+        ];
+        let off = compute_from_waypoints(&trace, &waypoints, &AnalysisOptions::default()).unwrap();
+        assert_eq!(off[0].darkness_time_s, 0.0);
+        assert!(
+            (night[0].estimated_duration_s
+                - off[0].estimated_duration_s
+                - night[0].darkness_time_s)
+                .abs()
+                < 1e-6
+        );
+    }
+
+    #[test]
+    fn surface_slows_section_and_raises_difficulty() {
+        let trace = make_trace(12);
+        let mut waypoints = vec![
+            make_waypoint(0.0, "Start", Some("Start"), None),
+            make_waypoint(0.005, "Col", Some("TimeBarrier"), None),
+            make_waypoint(0.011, "Finish", Some("Arrival"), None),
+        ];
+        let plain =
+            compute_from_waypoints(&trace, &waypoints, &AnalysisOptions::default()).unwrap();
+        assert!(plain
+            .iter()
+            .all(|s| s.surface_factor == 1.0 && s.technicality.is_none()));
+
+        // Boulder field from the Col onwards, via the waypoint tag.
+        waypoints[1].surface = Some(Surface::Boulder);
+        let tagged =
+            compute_from_waypoints(&trace, &waypoints, &AnalysisOptions::default()).unwrap();
+        assert_eq!(
+            tagged[0].estimated_duration_s,
+            plain[0].estimated_duration_s
+        );
+        assert!((tagged[1].surface_factor - Surface::Boulder.pace_factor()).abs() < 1e-9);
+        assert_eq!(tagged[1].technicality, Some(5));
+        assert_eq!(tagged[1].difficulty, 5);
+        assert!(tagged[1].pace_factor == plain[1].pace_factor);
+
+        // An explicit range overrides the tag.
+        let options = AnalysisOptions::default().surfaces(SurfaceMap::new().with_range(
+            0,
+            trace.locations.len() - 1,
+            Surface::Road,
+        ));
+        let road = compute_from_waypoints(&trace, &waypoints, &options).unwrap();
+        assert!(road[1].surface_factor < 1.0);
+        assert!(road[1].estimated_duration_s < plain[1].estimated_duration_s);
+        assert_eq!(road[1].technicality, Some(1));
+    }
+
+    #[test]
+    fn sleep_adds_stop_and_recovers_fatigue() {
+        let trace = make_trace(31);
+        let waypoints = vec![
+            make_waypoint(0.0, "Start", Some("Start"), Some(0)),
+            make_waypoint(0.015, "Base", Some("LifeBase"), None),
+            make_waypoint(0.030, "Finish", Some("Arrival"), None),
+        ];
+        let options = AnalysisOptions::default().fatigue(0.5).life_base_stop(0);
+        let awake = compute_from_waypoints(&trace, &waypoints, &options).unwrap();
+        let rested = compute_from_waypoints(
+            &trace,
+            &waypoints,
+            &options
+                .clone()
+                .sleep(SleepPlan::new().block(SleepBlock::at_checkpoint("Base", 5400))),
+        )
+        .unwrap();
+        assert_eq!(awake[0].sleep_s, 0.0);
+        assert_eq!(rested[0].sleep_s, 5400.0);
+        assert!(
+            (rested[0].estimated_duration_s - awake[0].estimated_duration_s - 5400.0).abs() < 1e-6
+        );
+        // Deeper d_eff recovery than the LifeBase alone → faster second section.
+        assert!(rested[1].estimated_duration_s < awake[1].estimated_duration_s);
+
+        // The same block by clock time lands on the first boundary reached after it.
+        let by_clock = compute_from_waypoints(
+            &trace,
+            &waypoints,
+            &options
+                .clone()
+                .sleep(SleepPlan::new().block(SleepBlock::not_before(1, 5400))),
+        )
+        .unwrap();
+        assert_eq!(by_clock[0].sleep_s, 5400.0);
+        assert_eq!(by_clock[1].sleep_s, 0.0);
+    }
+
+    #[test]
+    fn nutrition_follows_climb_and_heat() {
+        let trace = make_trace(31);
+        let waypoints = vec![
+            make_waypoint(0.0, "Start", Some("Start"), None),
+            make_waypoint(0.015, "CP1", Some("TimeBarrier"), None),
+            make_waypoint(0.030, "Finish", Some("Arrival"), None),
+        ];
+        assert!(
+            compute_from_waypoints(&trace, &waypoints, &AnalysisOptions::default()).unwrap()[0]
+                .nutrition
+                .is_none()
+        );
+
+        let options = AnalysisOptions::default().nutrition(NutritionProfile::new(60.0));
+        let mild = compute_from_waypoints(&trace, &waypoints, &options).unwrap();
+        let n = mild[0].nutrition.unwrap();
+        assert!(n.energy_kcal > 0.0 && n.fluid_l > 0.0 && n.sodium_mg > 0.0);
+        assert!(!n.over_capacity());
+
+        let heavier = compute_from_waypoints(
+            &trace,
+            &waypoints,
+            &AnalysisOptions::default().nutrition(NutritionProfile::new(90.0)),
+        )
+        .unwrap();
+        assert!((heavier[0].nutrition.unwrap().energy_kcal / n.energy_kcal - 1.5).abs() < 1e-9);
+
+        let hot = WeatherConditions {
+            temperature_c: 34.0,
+            humidity_pct: 80.0,
+            ..WEATHER_NEUTRAL
+        };
+        let heat = compute_from_waypoints(
+            &trace,
+            &waypoints,
+            &options
+                .clone()
+                .weather(WeatherLookup::new(vec!["CP1".into()], vec![hot])),
+        )
+        .unwrap();
+        assert!(heat[0].nutrition.unwrap().fluid_l > n.fluid_l);
+        assert_eq!(heat[1].nutrition, mild[1].nutrition);
+
+        // A tiny bottle cannot cover the section.
+        let small = compute_from_waypoints(
+            &trace,
+            &waypoints,
+            &AnalysisOptions::default().nutrition(NutritionProfile::new(60.0).fluid_capacity(0.01)),
+        )
+        .unwrap();
+        assert!(small[0].nutrition.unwrap().fluid_over_capacity);
+    }
+
     #[test]
     fn max_completion_time_null_without_timestamps() {
         let trace = make_trace(4);
@@ -248,6 +548,30 @@ mod tests {
         assert!(sections_lb[1].estimated_duration_s < sections_tb[1].estimated_duration_s);
     }
 
+    #[test]
+    fn custom_pace_model_is_honoured() {
+        #[derive(Debug)]
+        struct Doubled;
+        impl crate::pace_model::PaceModel for Doubled {
+            fn terrain_factor(&self, slope_frac: f64) -> f64 {
+                2.0 * crate::minetti::pace_factor(slope_frac)
+            }
+        }
+
+        let trace = make_trace(6);
+        let waypoints = vec![
+            make_waypoint(0.0, "Start", Some("Start"), None),
+            make_waypoint(0.005, "End", Some("Arrival"), None),
+        ];
+        let opts = AnalysisOptions::default().fatigue(0.0);
+        let base = compute_from_waypoints(&trace, &waypoints, &opts).unwrap();
+        let doubled =
+            compute_from_waypoints(&trace, &waypoints, &opts.pace_model(Doubled)).unwrap();
+        let ratio = doubled[0].estimated_duration_s / base[0].estimated_duration_s;
+        assert!((ratio - 2.0).abs() < 1e-9);
+        assert!((doubled[0].pace_factor - 2.0 * base[0].pace_factor).abs() < 1e-9);
+    }
+
     #[test]
     fn night_section_slower_than_day() {
         let locs: Vec<Location> = (0..5)
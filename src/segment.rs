@@ -1,4 +1,10 @@
-use crate::pace_model::{self, WeatherConditions};
+use crate::forecast::WeatherSpan;
+use crate::minetti;
+use crate::pace_model::{
+    apparent_temp_c, AnalysisOptions, PaceModel, WeatherConditions, WEATHER_NEUTRAL, WEATHER_T_OPT,
+};
+use crate::solar;
+use crate::surface::SurfaceMap;
 use crate::trace::Trace;
 
 /// Per-segment physiological metrics accumulated over a trace index range.
@@ -14,24 +20,139 @@ pub struct SegmentMetrics {
     pub total_time: f64,
     /// Sum of seg_dist_km × terrain_pace_factor. Used to derive the average pace factor.
     pub total_weighted_dist_km: f64,
+    /// Share of `total_time` caused by the altitude factor (seconds; 0 when disabled).
+    pub altitude_time_s: f64,
+    /// Share of `total_time` caused by the darkness factor (seconds; 0 when disabled).
+    pub darkness_time_s: f64,
+    /// Share of `total_time` caused by the surface factor (seconds; may be negative on road).
+    pub surface_time_s: f64,
+    /// Distance with a classified surface (km).
+    pub classified_dist_km: f64,
+    /// Sum of seg_dist_km × surface level over classified segments.
+    pub technicality_dist_km: f64,
+    /// Minetti metabolic cost summed over the range (J/kg).
+    pub metabolic_j_per_kg: f64,
+    /// Sum of seg_time × apparent temperature above `WEATHER_T_OPT` (°C·s).
+    pub heat_load_c_s: f64,
 }
 
 /// Configuration parameters for a segment computation (immutable across a call).
-pub struct SegmentParams {
+pub struct SegmentParams<'a> {
     pub base_pace_s_per_km: f64,
     pub k_fatigue: f64,
     pub clock_start: Option<i64>,
+    /// Static conditions, used where `forecast` has no value.
     pub weather: WeatherConditions,
+    /// Time-varying forecast for the enclosing interval. Evaluated per segment at
+    /// `clock_start + race_s`; ignored when `clock_start` is `None`.
+    pub forecast: Option<WeatherSpan<'a>>,
+    pub pace_model: &'a dyn PaceModel,
+    /// Acclimatization altitude (m) for the altitude factor; `None` disables it.
+    pub altitude_acclimatization_m: Option<f64>,
+    /// Apply the darkness factor from the sun elevation; needs `clock_start`.
+    pub darkness: bool,
+    /// Resolved surface ranges; unclassified segments are neutral.
+    pub surfaces: Option<&'a SurfaceMap>,
+    /// No circadian penalty before this epoch (set after a sleep block).
+    pub rested_until: Option<i64>,
+    /// Multiplies the weather factor on every segment (1.0 leaves it as the
+    /// model computes it); Monte Carlo draws it per run.
+    pub weather_scale: f64,
+}
+
+impl<'a> SegmentParams<'a> {
+    /// Every field from `options`, starting the clock at `clock_start`. The
+    /// static weather is neutral and there is no forecast until
+    /// [`SegmentParams::interval`] narrows them to one interval.
+    pub fn from_options(
+        options: &'a AnalysisOptions,
+        clock_start: Option<i64>,
+        surfaces: Option<&'a SurfaceMap>,
+    ) -> Self {
+        SegmentParams {
+            base_pace_s_per_km: options.base_pace_s_per_km,
+            k_fatigue: options.k_fatigue,
+            clock_start,
+            weather: WEATHER_NEUTRAL,
+            forecast: None,
+            pace_model: options.pace_model.as_ref(),
+            altitude_acclimatization_m: options.altitude_acclimatization_m,
+            darkness: options.darkness,
+            surfaces,
+            rested_until: None,
+            weather_scale: 1.0,
+        }
+    }
+
+    /// `self` with the weather and forecast `options` give the interval from
+    /// waypoint `start` to waypoint `end` over trace points `[start_index, end_index]`.
+    pub fn interval(
+        self,
+        options: &'a AnalysisOptions,
+        trace: &Trace,
+        start: &str,
+        end: &str,
+        start_index: usize,
+        end_index: usize,
+    ) -> Self {
+        SegmentParams {
+            weather: options.weather.find(end),
+            forecast: options.forecast.span(
+                start,
+                end,
+                trace.cumulative_distances[start_index],
+                trace.cumulative_distances[end_index],
+            ),
+            ..self
+        }
+    }
 }
 
 /// Mutable physiological state carried across consecutive segments.
+#[derive(Debug, Clone, Copy, Default)]
 pub struct SegmentState {
     /// Cumulative effort-weighted distance in metres.
     pub d_eff_m: f64,
     /// Cumulative moving-time clock in seconds.
     pub elapsed_s: f64,
+    /// Race clock in seconds: moving time plus the stops and sleep callers
+    /// add between ranges. Every time-of-day lookup (forecast, circadian,
+    /// darkness, sleep) reads `clock_start + race_s`.
+    pub race_s: f64,
 }
 
+impl SegmentState {
+    /// Fresh state at the start line.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// Pace multipliers applied to one trace segment, reported by [`compute_traced`].
+#[derive(Debug, Clone, Copy)]
+pub struct SegmentSample {
+    /// Index of the segment's first point.
+    pub index: usize,
+    pub terrain: f64,
+    pub fatigue: f64,
+    pub circadian: f64,
+    pub weather: f64,
+    /// Hypoxia; 1.0 unless altitude is enabled.
+    pub altitude: f64,
+    /// Headtorch penalty; 1.0 unless darkness is enabled.
+    pub darkness: f64,
+    /// Surface class; 1.0 on unclassified ground.
+    pub surface: f64,
+    /// Every multiplier applied to base pace: the product of the components
+    /// above, unless a custom `compute_factors` combines them differently.
+    pub combined: f64,
+    /// Predicted moving time over the segment (seconds).
+    pub time_s: f64,
+}
+
+/// Callback for [`compute_traced`].
+pub type SegmentSink<'s> = &'s mut dyn FnMut(&SegmentSample, &SegmentState);
+
 /// Accumulate metrics over trace points `[start_index, end_index)`.
 ///
 /// The `state` fields are mutated in place so the caller can carry fatigue
@@ -40,16 +161,54 @@ pub fn compute(
     trace: &Trace,
     start_index: usize,
     end_index: usize,
-    params: &SegmentParams,
+    params: &SegmentParams<'_>,
+    state: &mut SegmentState,
+) -> SegmentMetrics {
+    accumulate(trace, start_index, end_index, params, state, None)
+}
+
+/// [`compute`], calling `on_segment` after each segment with its factor
+/// breakdown; `state` already includes that segment when it is called.
+pub fn compute_traced(
+    trace: &Trace,
+    start_index: usize,
+    end_index: usize,
+    params: &SegmentParams<'_>,
     state: &mut SegmentState,
+    on_segment: SegmentSink<'_>,
+) -> SegmentMetrics {
+    accumulate(
+        trace,
+        start_index,
+        end_index,
+        params,
+        state,
+        Some(on_segment),
+    )
+}
+
+fn accumulate(
+    trace: &Trace,
+    start_index: usize,
+    end_index: usize,
+    params: &SegmentParams<'_>,
+    state: &mut SegmentState,
+    mut on_segment: Option<SegmentSink<'_>>,
 ) -> SegmentMetrics {
     let mut min_elevation = trace.locations[start_index].altitude;
     let mut max_elevation = trace.locations[start_index].altitude;
     let mut max_slope = 0.0_f64;
     let mut total_time = 0.0_f64;
     let mut total_weighted_dist_km = 0.0_f64;
+    let mut altitude_time_s = 0.0_f64;
+    let mut darkness_time_s = 0.0_f64;
+    let mut surface_time_s = 0.0_f64;
+    let mut classified_dist_km = 0.0_f64;
+    let mut technicality_dist_km = 0.0_f64;
+    let mut metabolic_j_per_kg = 0.0_f64;
+    let mut heat_load_c_s = 0.0_f64;
 
-    let weather_factor = pace_model::weather_factor(params.weather);
+    let weather_factor = params.pace_model.weather_factor(params.weather);
 
     for j in start_index..end_index {
         let ele = trace.locations[j].altitude;
@@ -60,20 +219,103 @@ pub fn compute(
         let slope_frac = trace.slopes[j] / 100.0;
         let seg_dist_km = trace.cumulative_distances[j + 1] - trace.cumulative_distances[j];
 
-        let factors = pace_model::compute_factors(
+        let forecast = match (&params.forecast, params.clock_start) {
+            (Some(span), Some(t0)) => {
+                let mid_km =
+                    (trace.cumulative_distances[j] + trace.cumulative_distances[j + 1]) / 2.0;
+                span.conditions_at(mid_km, t0 + state.race_s as i64)
+            }
+            _ => None,
+        };
+        let conditions = forecast.unwrap_or(params.weather);
+        let seg_weather_factor = params.weather_scale
+            * if conditions.wind_dir_deg.is_some() {
+                let bearing = trace.locations[j].calculate_bearing_to(&trace.locations[j + 1]);
+                params.pace_model.weather_factor_along(conditions, bearing)
+            } else if forecast.is_some() {
+                params.pace_model.weather_factor(conditions)
+            } else {
+                weather_factor
+            };
+
+        // The circadian bump follows local solar time at the runner's longitude.
+        let now = params.clock_start.map(|t0| t0 + state.race_s as i64);
+        // A recent sleep block suspends it.
+        let rested = now.zip(params.rested_until).is_some_and(|(now, r)| now < r);
+        let solar_clock_start = params
+            .clock_start
+            .zip(now)
+            .filter(|_| !rested)
+            .map(|(t0, now)| t0 + solar::local_solar_offset_s(trace.locations[j].longitude, now));
+        let factors = params.pace_model.compute_factors(
             slope_frac,
             state.d_eff_m / 1000.0,
             params.k_fatigue,
-            params.clock_start,
-            state.elapsed_s,
-            weather_factor,
+            solar_clock_start,
+            state.race_s,
+            seg_weather_factor,
         );
 
-        let seg_time = seg_dist_km * params.base_pace_s_per_km * factors.combined;
+        // Hypoxia is a separate multiplier so its cost can be reported on its own.
+        let altitude = params.altitude_acclimatization_m.map_or(1.0, |acclim_m| {
+            let mean_alt = (ele + trace.locations[j + 1].altitude) / 2.0;
+            params.pace_model.altitude_factor(mean_alt, acclim_m)
+        });
+
+        let darkness = match now {
+            Some(now) if params.darkness => params
+                .pace_model
+                .darkness_factor(solar::sun_elevation_deg(&trace.locations[j], now)),
+            _ => 1.0,
+        };
+
+        let surface_factor = match params.surfaces.and_then(|m| m.surface_at(j)) {
+            Some(surface) => {
+                classified_dist_km += seg_dist_km;
+                technicality_dist_km += seg_dist_km * surface.level() as f64;
+                params.pace_model.surface_factor(surface)
+            }
+            None => 1.0,
+        };
+
+        let seg_time = seg_dist_km
+            * params.base_pace_s_per_km
+            * factors.combined
+            * altitude
+            * darkness
+            * surface_factor;
+        // The breakdown costs extra model calls, so only a traced run pays for it.
+        let sample = on_segment.is_some().then(|| SegmentSample {
+            index: j,
+            terrain: factors.terrain,
+            fatigue: params
+                .pace_model
+                .fatigue_factor(state.d_eff_m / 1000.0, params.k_fatigue),
+            circadian: solar_clock_start.map_or(1.0, |t0| {
+                params.pace_model.circadian_factor(t0 + state.race_s as i64)
+            }),
+            weather: seg_weather_factor,
+            altitude,
+            darkness,
+            surface: surface_factor,
+            combined: factors.combined * altitude * darkness * surface_factor,
+            time_s: seg_time,
+        });
+        surface_time_s += seg_time * (1.0 - 1.0 / surface_factor);
+        altitude_time_s += seg_time * (1.0 - 1.0 / altitude);
+        darkness_time_s += seg_time * (1.0 - 1.0 / darkness);
         total_time += seg_time;
         state.elapsed_s += seg_time;
+        state.race_s += seg_time;
         total_weighted_dist_km += seg_dist_km * factors.terrain;
+        metabolic_j_per_kg += minetti::cmet(slope_frac) * seg_dist_km * 1000.0;
+        heat_load_c_s += seg_time
+            * (apparent_temp_c(conditions.temperature_c, conditions.humidity_pct) - WEATHER_T_OPT)
+                .max(0.0);
         state.d_eff_m += seg_dist_km * 1000.0 * factors.terrain;
+        if let (Some(on_segment), Some(sample)) = (on_segment.as_mut(), sample) {
+            on_segment(&sample, state);
+        }
     }
 
     SegmentMetrics {
@@ -82,13 +324,20 @@ pub fn compute(
         max_slope,
         total_time,
         total_weighted_dist_km,
+        altitude_time_s,
+        darkness_time_s,
+        surface_time_s,
+        classified_dist_km,
+        technicality_dist_km,
+        metabolic_j_per_kg,
+        heat_load_c_s,
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::pace_model::{weather_factor, WEATHER_NEUTRAL};
+    use crate::pace_model::{weather_factor, MinettiModel, WEATHER_NEUTRAL};
     use crate::Location;
 
     fn flat_trace(n: usize) -> Trace {
@@ -102,22 +351,26 @@ mod tests {
         Trace::new(&locs).unwrap()
     }
 
-    fn default_params(weather: WeatherConditions) -> SegmentParams {
+    fn default_params(weather: WeatherConditions) -> SegmentParams<'static> {
         SegmentParams {
             base_pace_s_per_km: 500.0,
             k_fatigue: crate::pace_model::K_FATIGUE,
             clock_start: None,
             weather,
+            forecast: None,
+            pace_model: &MinettiModel,
+            altitude_acclimatization_m: None,
+            darkness: false,
+            surfaces: None,
+            rested_until: None,
+            weather_scale: 1.0,
         }
     }
 
     #[test]
     fn flat_range_neutral_pace_factor() {
         let trace = flat_trace(5);
-        let mut state = SegmentState {
-            d_eff_m: 0.0,
-            elapsed_s: 0.0,
-        };
+        let mut state = SegmentState::new();
         let params = default_params(WEATHER_NEUTRAL);
         let m = compute(&trace, 0, trace.locations.len() - 1, &params, &mut state);
         assert!((m.min_elevation - 100.0).abs() < 0.001);
@@ -139,10 +392,7 @@ mod tests {
         let last = trace.locations.len() - 1;
         let mid = last / 2;
 
-        let mut state = SegmentState {
-            d_eff_m: 0.0,
-            elapsed_s: 0.0,
-        };
+        let mut state = SegmentState::new();
         let params = default_params(WEATHER_NEUTRAL);
         compute(&trace, 0, mid, &params, &mut state);
         let d_after_first = state.d_eff_m;
@@ -151,6 +401,84 @@ mod tests {
         assert!(state.elapsed_s > 0.0);
     }
 
+    #[test]
+    fn wind_direction_is_resolved_against_segment_bearing() {
+        // flat_trace runs due east.
+        let trace = flat_trace(5);
+        let last = trace.locations.len() - 1;
+        let run = |wind_dir_deg| {
+            let weather = WeatherConditions {
+                wind_kmh: 30.0,
+                wind_dir_deg,
+                ..WEATHER_NEUTRAL
+            };
+            let mut state = SegmentState::new();
+            compute(&trace, 0, last, &default_params(weather), &mut state).total_time
+        };
+        let neutral = run(None);
+        let head = run(Some(90.0));
+        let tail = run(Some(270.0));
+        let cross = run(Some(0.0));
+        assert!(head > neutral);
+        // A tailwind beats still air, not just the speed-only penalty.
+        assert!(tail < neutral / crate::pace_model::wind_factor(30.0));
+        assert!(cross > tail && cross < head);
+    }
+
+    #[test]
+    fn circadian_bump_follows_local_solar_time() {
+        // 90° E: 21:30 UTC is ~03:30 local solar time, 03:30 UTC is ~09:30.
+        let locs: Vec<Location> = (0..5)
+            .map(|i| Location {
+                longitude: 90.0 + i as f64 * 0.001,
+                latitude: 0.0,
+                altitude: 100.0,
+            })
+            .collect();
+        let trace = Trace::new(&locs).unwrap();
+        let last = trace.locations.len() - 1;
+        let run = |clock_start| {
+            let params = SegmentParams {
+                clock_start: Some(clock_start),
+                ..default_params(WEATHER_NEUTRAL)
+            };
+            let mut state = SegmentState::new();
+            compute(&trace, 0, last, &params, &mut state).total_time
+        };
+        let baseline = default_params(WEATHER_NEUTRAL);
+        let mut state = SegmentState::new();
+        let untimed = compute(&trace, 0, last, &baseline, &mut state).total_time;
+        assert!(run(21 * 3600 + 30 * 60) > untimed * 1.1);
+        assert!((run(3 * 3600 + 30 * 60) - untimed).abs() < 1e-9);
+    }
+
+    #[test]
+    fn rested_until_suspends_circadian_penalty() {
+        let trace = flat_trace(5);
+        let last = trace.locations.len() - 1;
+        // ~03:30 local solar time at longitude 0.
+        let night = 3 * 3600 + 30 * 60;
+        let run = |rested_until| {
+            let params = SegmentParams {
+                clock_start: Some(night),
+                rested_until,
+                ..default_params(WEATHER_NEUTRAL)
+            };
+            let mut state = SegmentState::new();
+            compute(&trace, 0, last, &params, &mut state).total_time
+        };
+        let untimed = compute(
+            &trace,
+            0,
+            last,
+            &default_params(WEATHER_NEUTRAL),
+            &mut SegmentState::new(),
+        )
+        .total_time;
+        assert!(run(None) > untimed * 1.1);
+        assert!((run(Some(night + 3600)) - untimed).abs() < 1e-9);
+    }
+
     #[test]
     fn adverse_weather_increases_time() {
         let trace = flat_trace(5);
@@ -159,11 +487,9 @@ mod tests {
             humidity_pct: 85.0,
             wind_kmh: 35.0,
             precip_prob_pct: 80.0,
+            wind_dir_deg: None,
         };
-        let mut state_a = SegmentState {
-            d_eff_m: 0.0,
-            elapsed_s: 0.0,
-        };
+        let mut state_a = SegmentState::new();
         let neutral = compute(
             &trace,
             0,
@@ -171,10 +497,7 @@ mod tests {
             &default_params(WEATHER_NEUTRAL),
             &mut state_a,
         );
-        let mut state_b = SegmentState {
-            d_eff_m: 0.0,
-            elapsed_s: 0.0,
-        };
+        let mut state_b = SegmentState::new();
         let adverse = compute(
             &trace,
             0,
@@ -201,10 +524,7 @@ mod tests {
         let flat = flat_trace(5);
         let last = flat.locations.len() - 1;
 
-        let mut state_d = SegmentState {
-            d_eff_m: 0.0,
-            elapsed_s: 0.0,
-        };
+        let mut state_d = SegmentState::new();
         let desc_m = compute(
             &descending,
             0,
@@ -212,10 +532,7 @@ mod tests {
             &default_params(WEATHER_NEUTRAL),
             &mut state_d,
         );
-        let mut state_f = SegmentState {
-            d_eff_m: 0.0,
-            elapsed_s: 0.0,
-        };
+        let mut state_f = SegmentState::new();
         let flat_m = compute(
             &flat,
             0,
@@ -227,6 +544,34 @@ mod tests {
         assert!(desc_m.total_time < flat_m.total_time);
     }
 
+    #[test]
+    fn pace_model_drives_terrain_cost() {
+        #[derive(Debug)]
+        struct FlatTerrain;
+        impl PaceModel for FlatTerrain {
+            fn terrain_factor(&self, _slope_frac: f64) -> f64 {
+                1.0
+            }
+        }
+
+        let locs: Vec<Location> = (0..5)
+            .map(|i| Location {
+                longitude: i as f64 * 0.001,
+                latitude: 0.0,
+                altitude: i as f64 * 20.0,
+            })
+            .collect();
+        let climb = Trace::new(&locs).unwrap();
+        let last = climb.locations.len() - 1;
+        let params = SegmentParams {
+            pace_model: &FlatTerrain,
+            ..default_params(WEATHER_NEUTRAL)
+        };
+        let mut state = SegmentState::new();
+        let m = compute(&climb, 0, last, &params, &mut state);
+        assert!((m.total_weighted_dist_km - climb.total_distance).abs() < 1e-9);
+    }
+
     #[test]
     fn max_slope_tracked_correctly() {
         let locs: Vec<Location> = (0..4)
@@ -237,10 +582,7 @@ mod tests {
             })
             .collect();
         let trace = Trace::new(&locs).unwrap();
-        let mut state = SegmentState {
-            d_eff_m: 0.0,
-            elapsed_s: 0.0,
-        };
+        let mut state = SegmentState::new();
         let m = compute(&trace, 0, 3, &default_params(WEATHER_NEUTRAL), &mut state);
         assert!(m.max_slope > 10.0);
         assert_eq!(m.min_elevation, 0.0);
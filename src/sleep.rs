@@ -0,0 +1,294 @@
+//! Planned sleep for multi-day races.
+//!
+//! A [`SleepBlock`] is taken at a named checkpoint, or at the first boundary
+//! of the analysis (section or stage) reached at or after a clock time.
+//! Sleeping adds its duration to the stop there, recovers more effort
+//! distance than a LifeBase stop alone ([`sleep_recovery`]), and suspends the
+//! circadian penalty for [`SLEEP_CIRCADIAN_RESET_S`] after waking.
+
+use crate::cutoff::CutoffRisk;
+use crate::pace_model::{AnalysisOptions, RECOVERY_LIFE_BASE};
+use crate::section;
+use crate::trace::Trace;
+use crate::waypoint::Waypoint;
+
+/// Extra fraction of effort distance recovered per hour of sleep, on top of
+/// [`RECOVERY_LIFE_BASE`].
+pub const SLEEP_RECOVERY_PER_H: f64 = 0.25;
+/// Upper bound on the fraction of effort distance a sleep block recovers.
+pub const SLEEP_RECOVERY_MAX: f64 = 0.8;
+/// Awake time after a sleep block during which the circadian penalty is not applied (s).
+pub const SLEEP_CIRCADIAN_RESET_S: i64 = 16 * 3600;
+
+/// Where a sleep block is taken.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SleepAt {
+    /// At the section boundary with this waypoint name.
+    Checkpoint(String),
+    /// At the first boundary reached at or after this Unix time.
+    /// Needs a Start `<time>`.
+    NotBefore(i64),
+}
+
+/// One planned sleep.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SleepBlock {
+    pub at: SleepAt,
+    pub duration_s: u32,
+}
+
+impl SleepBlock {
+    pub fn at_checkpoint(name: impl Into<String>, duration_s: u32) -> Self {
+        Self {
+            at: SleepAt::Checkpoint(name.into()),
+            duration_s,
+        }
+    }
+
+    pub fn not_before(unix_time_s: i64, duration_s: u32) -> Self {
+        Self {
+            at: SleepAt::NotBefore(unix_time_s),
+            duration_s,
+        }
+    }
+}
+
+/// The sleep blocks planned for a race. Empty by default.
+#[derive(Debug, Clone, Default)]
+pub struct SleepPlan {
+    blocks: Vec<SleepBlock>,
+}
+
+impl SleepPlan {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a sleep block.
+    pub fn block(mut self, block: SleepBlock) -> Self {
+        self.blocks.push(block);
+        self
+    }
+
+    pub fn blocks(&self) -> &[SleepBlock] {
+        &self.blocks
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+    }
+}
+
+/// Fraction of effort distance (`d_eff`) recovered by sleeping `duration_s`.
+pub fn sleep_recovery(duration_s: u32) -> f64 {
+    let hours = duration_s as f64 / 3600.0;
+    (RECOVERY_LIFE_BASE + SLEEP_RECOVERY_PER_H * hours).min(SLEEP_RECOVERY_MAX)
+}
+
+/// Fraction of `d_eff` recovered at a checkpoint: the larger of the LifeBase
+/// recovery and the sleep recovery, 0 when neither applies.
+pub(crate) fn end_recovery(is_life_base: bool, sleep_s: u32) -> f64 {
+    let life_base = if is_life_base {
+        RECOVERY_LIFE_BASE
+    } else {
+        0.0
+    };
+    if sleep_s > 0 {
+        life_base.max(sleep_recovery(sleep_s))
+    } else {
+        life_base
+    }
+}
+
+/// Epoch until which the circadian penalty is suspended after leaving the
+/// sleep checkpoint at `wake_race_s` on the race clock
+/// ([`SegmentState::race_s`](crate::segment::SegmentState::race_s)).
+pub(crate) fn rested_until(clock_start: Option<i64>, wake_race_s: f64) -> Option<i64> {
+    clock_start.map(|t0| t0 + wake_race_s as i64 + SLEEP_CIRCADIAN_RESET_S)
+}
+
+/// Tracks which blocks of a plan have been taken while walking the route.
+pub(crate) struct SleepTracker<'a> {
+    plan: &'a SleepPlan,
+    taken: Vec<bool>,
+    /// Circadian penalty suspended before this epoch (see [`rested_until`]).
+    pub rested_until: Option<i64>,
+}
+
+impl<'a> SleepTracker<'a> {
+    pub(crate) fn new(plan: &'a SleepPlan) -> Self {
+        Self {
+            plan,
+            taken: vec![false; plan.blocks.len()],
+            rested_until: None,
+        }
+    }
+
+    /// Sleep seconds taken on arriving at checkpoint `name` at `arrival`
+    /// (epoch, if the race start is known). Marks the matching blocks taken.
+    pub(crate) fn at_checkpoint(&mut self, name: &str, arrival: Option<i64>) -> u32 {
+        let mut sleep_s = 0u32;
+        for (block, taken) in self.plan.blocks.iter().zip(self.taken.iter_mut()) {
+            if *taken {
+                continue;
+            }
+            let due = match &block.at {
+                SleepAt::Checkpoint(n) => n == name,
+                SleepAt::NotBefore(t) => arrival.is_some_and(|a| a >= *t),
+            };
+            if due {
+                *taken = true;
+                sleep_s += block.duration_s;
+            }
+        }
+        sleep_s
+    }
+}
+
+/// One candidate sleep location from [`suggest`].
+#[derive(Debug, Clone)]
+pub struct SleepSuggestion {
+    /// Checkpoint (section boundary) to sleep at.
+    pub checkpoint: String,
+    /// Predicted race time to the final checkpoint with this sleep added (s).
+    pub finish_s: f64,
+    /// Smallest cutoff slack across all sections (minutes); `None` without cutoffs.
+    pub min_slack_min: Option<f64>,
+    /// Every section cutoff is still met.
+    pub meets_cutoffs: bool,
+}
+
+/// Rank the intermediate section boundaries as places to add a sleep of
+/// `duration_s` to the plan already in `options`.
+///
+/// Each candidate is evaluated with a full section analysis. Candidates that
+/// keep every cutoff come first, fastest finish first; the rest follow by
+/// largest worst-case slack. Call again with the chosen block added to plan
+/// further sleeps. Empty when fewer than 3 section boundaries exist.
+pub fn suggest(
+    trace: &Trace,
+    waypoints: &[Waypoint],
+    options: &AnalysisOptions,
+    duration_s: u32,
+) -> Vec<SleepSuggestion> {
+    let boundaries: Vec<&Waypoint> = waypoints
+        .iter()
+        .filter(|w| w.is_section_boundary())
+        .collect();
+    if boundaries.len() < 3 {
+        return Vec::new();
+    }
+
+    let mut suggestions: Vec<SleepSuggestion> = boundaries[1..boundaries.len() - 1]
+        .iter()
+        .filter_map(|wpt| {
+            let mut opts = options.clone();
+            opts.sleep = opts
+                .sleep
+                .block(SleepBlock::at_checkpoint(wpt.name.clone(), duration_s));
+            let sections = section::compute_from_waypoints(trace, waypoints, &opts)?;
+            let last = sections.last()?;
+            let min_slack_min = sections
+                .iter()
+                .filter_map(|s| s.cutoff_slack_min)
+                .reduce(f64::min);
+            Some(SleepSuggestion {
+                checkpoint: wpt.name.clone(),
+                finish_s: last.predicted_arrival_s,
+                min_slack_min,
+                meets_cutoffs: sections
+                    .iter()
+                    .all(|s| s.cutoff_risk != Some(CutoffRisk::Missed)),
+            })
+        })
+        .collect();
+
+    suggestions.sort_by(|a, b| {
+        b.meets_cutoffs.cmp(&a.meets_cutoffs).then_with(|| {
+            if a.meets_cutoffs {
+                a.finish_s.total_cmp(&b.finish_s)
+            } else {
+                let slack = |s: &SleepSuggestion| s.min_slack_min.unwrap_or(f64::NEG_INFINITY);
+                slack(b).total_cmp(&slack(a))
+            }
+        })
+    });
+    suggestions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recovery_grows_with_duration_up_to_cap() {
+        assert!((sleep_recovery(0) - RECOVERY_LIFE_BASE).abs() < 1e-12);
+        assert!(sleep_recovery(5400) > sleep_recovery(1800));
+        assert!((sleep_recovery(24 * 3600) - SLEEP_RECOVERY_MAX).abs() < 1e-12);
+        assert_eq!(end_recovery(false, 0), 0.0);
+        assert_eq!(end_recovery(true, 0), RECOVERY_LIFE_BASE);
+        assert!(end_recovery(true, 3600) > RECOVERY_LIFE_BASE);
+    }
+
+    #[test]
+    fn tracker_takes_each_block_once() {
+        let plan = SleepPlan::new()
+            .block(SleepBlock::at_checkpoint("Base", 1800))
+            .block(SleepBlock::not_before(10_000, 3600));
+        let mut tracker = SleepTracker::new(&plan);
+        assert_eq!(tracker.at_checkpoint("CP1", Some(5_000)), 0);
+        assert_eq!(tracker.at_checkpoint("Base", Some(8_000)), 1800);
+        assert_eq!(tracker.at_checkpoint("Base", Some(9_000)), 0);
+        assert_eq!(tracker.at_checkpoint("CP2", None), 0);
+        assert_eq!(tracker.at_checkpoint("CP3", Some(12_000)), 3600);
+        assert_eq!(tracker.at_checkpoint("CP4", Some(20_000)), 0);
+    }
+
+    #[test]
+    fn suggest_ranks_intermediate_boundaries() {
+        use crate::location::Location;
+
+        let locs: Vec<Location> = (0..41)
+            .map(|i| Location {
+                longitude: 0.0,
+                latitude: i as f64 * 0.001,
+                altitude: 100.0 + i as f64 * 5.0,
+            })
+            .collect();
+        let trace = Trace::new(&locs).unwrap();
+        let wpt = |lat: f64, name: &str, wpt_type: &str, time: Option<i64>| Waypoint {
+            latitude: lat,
+            longitude: 0.0,
+            elevation: None,
+            name: name.to_string(),
+            description: None,
+            comment: None,
+            symbol: None,
+            wpt_type: Some(wpt_type.to_string()),
+            time,
+            stop_duration: None,
+            surface: None,
+            crew: false,
+        };
+        let mut waypoints = vec![
+            wpt(0.0, "Start", "Start", Some(0)),
+            wpt(0.010, "CP1", "TimeBarrier", None),
+            wpt(0.025, "Base", "LifeBase", None),
+            wpt(0.040, "Finish", "Arrival", None),
+        ];
+        let options = AnalysisOptions::default().fatigue(0.5);
+
+        let ranked = suggest(&trace, &waypoints, &options, 3600);
+        assert_eq!(ranked.len(), 2);
+        assert!(ranked.iter().all(|s| s.meets_cutoffs));
+        assert!(ranked[0].finish_s <= ranked[1].finish_s);
+
+        // An impossible finish cutoff: nothing qualifies, ranked by slack.
+        waypoints[3].time = Some(60);
+        let ranked = suggest(&trace, &waypoints, &options, 3600);
+        assert!(ranked.iter().all(|s| !s.meets_cutoffs));
+        assert!(ranked[0].min_slack_min.unwrap() >= ranked[1].min_slack_min.unwrap());
+
+        assert!(suggest(&trace, &waypoints[..2], &options, 3600).is_empty());
+    }
+}
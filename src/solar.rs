@@ -0,0 +1,165 @@
+//! Solar position: sun elevation, sunrise/sunset, civil twilight and local
+//! solar time, after the NOAA solar calculator (accurate to about a minute
+//! between 1800 and 2100).
+
+use crate::location::Location;
+
+/// Sun elevation at sunrise/sunset: upper limb on the horizon, with refraction.
+pub const SUNRISE_ELEVATION_DEG: f64 = -0.833;
+
+/// Sun elevation at the start of civil dawn / end of civil dusk.
+pub const CIVIL_TWILIGHT_ELEVATION_DEG: f64 = -6.0;
+
+/// Sun events for the local solar day containing an epoch (all Unix seconds).
+/// Event fields are `None` when the sun never crosses that elevation that day
+/// (polar day or night).
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "wasm", derive(serde::Serialize))]
+#[cfg_attr(feature = "wasm", serde(rename_all = "camelCase"))]
+pub struct SunTimes {
+    pub solar_noon: i64,
+    pub civil_dawn: Option<i64>,
+    pub sunrise: Option<i64>,
+    pub sunset: Option<i64>,
+    pub civil_dusk: Option<i64>,
+}
+
+/// Declination (radians) and equation of time (minutes) at `unix_time_s`.
+fn declination_and_eot(unix_time_s: i64) -> (f64, f64) {
+    let julian_day = unix_time_s as f64 / 86_400.0 + 2_440_587.5;
+    let t = (julian_day - 2_451_545.0) / 36_525.0;
+
+    let mean_long = (280.46646 + t * (36000.76983 + t * 0.000_303_2)).rem_euclid(360.0);
+    let mean_anom = 357.52911 + t * (35999.05029 - 0.000_153_7 * t);
+    let ecc = 0.016_708_634 - t * (0.000_042_037 + 0.000_000_126_7 * t);
+    let m = mean_anom.to_radians();
+    let center = m.sin() * (1.914_602 - t * (0.004_817 + 0.000_014 * t))
+        + (2.0 * m).sin() * (0.019_993 - 0.000_101 * t)
+        + (3.0 * m).sin() * 0.000_289;
+    let omega = (125.04 - 1934.136 * t).to_radians();
+    let apparent_long = (mean_long + center - 0.005_69 - 0.004_78 * omega.sin()).to_radians();
+    let mean_obliq =
+        23.0 + (26.0 + (21.448 - t * (46.815 + t * (0.000_59 - t * 0.001_813))) / 60.0) / 60.0;
+    let obliq = (mean_obliq + 0.002_56 * omega.cos()).to_radians();
+
+    let declination = (obliq.sin() * apparent_long.sin()).asin();
+
+    let y = (obliq / 2.0).tan().powi(2);
+    let l0 = mean_long.to_radians();
+    let eot_rad = y * (2.0 * l0).sin() - 2.0 * ecc * m.sin()
+        + 4.0 * ecc * y * m.sin() * (2.0 * l0).cos()
+        - 0.5 * y * y * (4.0 * l0).sin()
+        - 1.25 * ecc * ecc * (2.0 * m).sin();
+    (declination, 4.0 * eot_rad.to_degrees())
+}
+
+/// Seconds to add to a UTC epoch to get local apparent solar time at
+/// `longitude` (longitude offset plus the equation of time).
+pub fn local_solar_offset_s(longitude: f64, unix_time_s: i64) -> i64 {
+    let (_, eot_min) = declination_and_eot(unix_time_s);
+    ((longitude * 4.0 + eot_min) * 60.0).round() as i64
+}
+
+/// Local apparent solar time of day at `longitude`, in hours `[0, 24)`.
+pub fn local_solar_hours(longitude: f64, unix_time_s: i64) -> f64 {
+    ((unix_time_s + local_solar_offset_s(longitude, unix_time_s)) as f64 / 3600.0).rem_euclid(24.0)
+}
+
+/// Geometric sun elevation above the horizon (degrees) at `location`.
+pub fn sun_elevation_deg(location: &Location, unix_time_s: i64) -> f64 {
+    let (declination, _) = declination_and_eot(unix_time_s);
+    let hour_angle =
+        ((local_solar_hours(location.longitude, unix_time_s) - 12.0) * 15.0).to_radians();
+    let lat = location.latitude.to_radians();
+    let cos_zenith =
+        lat.sin() * declination.sin() + lat.cos() * declination.cos() * hour_angle.cos();
+    90.0 - cos_zenith.clamp(-1.0, 1.0).acos().to_degrees()
+}
+
+/// Sunrise, sunset and civil twilight for the local solar day containing `unix_time_s`.
+pub fn sun_times(location: &Location, unix_time_s: i64) -> SunTimes {
+    let lon_offset_s = (location.longitude * 240.0).round() as i64;
+    let day = (unix_time_s + lon_offset_s).div_euclid(86_400);
+    let mean_noon = day * 86_400 + 43_200 - lon_offset_s;
+    let (declination, eot_min) = declination_and_eot(mean_noon);
+    let solar_noon = mean_noon - (eot_min * 60.0).round() as i64;
+
+    let lat = location.latitude.to_radians();
+    let crossing = |elevation_deg: f64| -> Option<(i64, i64)> {
+        let cos_ha = (elevation_deg.to_radians().sin() - lat.sin() * declination.sin())
+            / (lat.cos() * declination.cos());
+        if !(-1.0..=1.0).contains(&cos_ha) {
+            return None;
+        }
+        let half_day_s = (cos_ha.acos().to_degrees() * 240.0).round() as i64;
+        Some((solar_noon - half_day_s, solar_noon + half_day_s))
+    };
+    let sun = crossing(SUNRISE_ELEVATION_DEG);
+    let civil = crossing(CIVIL_TWILIGHT_ELEVATION_DEG);
+
+    SunTimes {
+        solar_noon,
+        civil_dawn: civil.map(|(rise, _)| rise),
+        sunrise: sun.map(|(rise, _)| rise),
+        sunset: sun.map(|(_, set)| set),
+        civil_dusk: civil.map(|(_, set)| set),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paris() -> Location {
+        Location {
+            longitude: 2.3522,
+            latitude: 48.8566,
+            altitude: 35.0,
+        }
+    }
+
+    // 2025-06-21T12:00:00Z
+    const SOLSTICE_NOON: i64 = 1_750_507_200;
+
+    #[test]
+    fn paris_summer_solstice_matches_almanac() {
+        // Almanac: sunrise 03:47 UTC, sunset 19:58 UTC, civil dusk 20:39 UTC.
+        let times = sun_times(&paris(), SOLSTICE_NOON);
+        let day_start = SOLSTICE_NOON - 12 * 3600;
+        let at = |h: i64, m: i64| day_start + h * 3600 + m * 60;
+        assert!((times.sunrise.unwrap() - at(3, 47)).abs() <= 120);
+        assert!((times.sunset.unwrap() - at(19, 58)).abs() <= 120);
+        assert!((times.civil_dusk.unwrap() - at(20, 39)).abs() <= 180);
+        assert!(times.civil_dawn.unwrap() < times.sunrise.unwrap());
+    }
+
+    #[test]
+    fn elevation_is_high_at_noon_and_negative_at_midnight() {
+        let times = sun_times(&paris(), SOLSTICE_NOON);
+        // 90 − 48.86 + 23.44 ≈ 64.6°
+        assert!((sun_elevation_deg(&paris(), times.solar_noon) - 64.6).abs() < 0.3);
+        assert!(sun_elevation_deg(&paris(), times.solar_noon + 12 * 3600) < -15.0);
+        let at_sunrise = sun_elevation_deg(&paris(), times.sunrise.unwrap());
+        assert!((at_sunrise - SUNRISE_ELEVATION_DEG).abs() < 0.2);
+    }
+
+    #[test]
+    fn polar_day_has_no_sunset() {
+        let tromso = Location {
+            longitude: 18.96,
+            latitude: 69.65,
+            altitude: 0.0,
+        };
+        let times = sun_times(&tromso, SOLSTICE_NOON);
+        assert!(times.sunrise.is_none() && times.sunset.is_none());
+    }
+
+    #[test]
+    fn local_solar_time_follows_longitude() {
+        // 90° east is six hours ahead, give or take the equation of time (< 17 min).
+        let hours = local_solar_hours(90.0, SOLSTICE_NOON);
+        assert!((hours - 18.0).abs() < 17.0 / 60.0);
+        let west = local_solar_hours(-90.0, SOLSTICE_NOON);
+        assert!((west - 6.0).abs() < 17.0 / 60.0);
+    }
+}
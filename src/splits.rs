@@ -0,0 +1,211 @@
+use crate::pace_model::AnalysisOptions;
+use crate::segment::{self, SegmentParams, SegmentState};
+use crate::trace::Trace;
+
+/// One statute mile in km — pass as `split_km` for mile splits.
+pub const MILE_KM: f64 = 1.609_344;
+
+#[cfg_attr(feature = "wasm", derive(serde::Serialize))]
+pub struct SplitStats {
+    pub split_id: usize,
+    pub start_index: usize,
+    pub end_index: usize,
+    /// Distance from the trace start to the split start (km).
+    pub start_distance_km: f64,
+    pub distance_km: f64,
+    pub elevation_gain_m: f64,
+    pub elevation_loss_m: f64,
+    /// Net grade over the split (%).
+    pub avg_slope: f64,
+    /// Average terrain pace factor (distance-weighted).
+    pub pace_factor: f64,
+    /// Predicted moving time for this split (seconds).
+    pub estimated_duration_s: f64,
+    /// Predicted moving time from the trace start to the end of this split.
+    pub cumulative_duration_s: f64,
+}
+
+/// Partition `trace` every `split_km` (e.g. `1.0`, or [`MILE_KM`]) and predict
+/// each split with the pace model.
+///
+/// Boundaries snap to the first trace point at or beyond each multiple of
+/// `split_km`; the last split ends at the final point and may be shorter.
+/// Fatigue and circadian state carry across splits; `clock_start` is the race
+/// start (epoch seconds) for the circadian factor. Weather is neutral — splits
+/// have no checkpoint name to look a forecast up by — and no stops apply.
+///
+/// Returns an empty vector when `split_km` is not positive or the trace has a
+/// single point.
+pub fn compute(
+    trace: &Trace,
+    split_km: f64,
+    options: &AnalysisOptions,
+    clock_start: Option<i64>,
+) -> Vec<SplitStats> {
+    let last = trace.locations.len() - 1;
+    if split_km <= 0.0 || !split_km.is_finite() || last == 0 {
+        return Vec::new();
+    }
+
+    let params = SegmentParams::from_options(options, clock_start, Some(&options.surfaces));
+    let mut state = SegmentState::new();
+
+    // Every split covers at least one segment.
+    let expected = (trace.total_distance / split_km).ceil() as usize;
+    let mut splits = Vec::with_capacity(expected.min(last));
+    let mut start_index = 0usize;
+    let mut cumulative = 0.0_f64;
+
+    while start_index < last {
+        // Next multiple of `split_km` past this split's start, so a long gap
+        // between points skips the marks it spans.
+        let start_km = trace.cumulative_distances[start_index];
+        let target_km = ((start_km / split_km).floor() + 1.0) * split_km;
+        let end_index = trace
+            .index_at_distance(target_km)
+            .clamp(start_index + 1, last);
+
+        let metrics = segment::compute(trace, start_index, end_index, &params, &mut state);
+        let dist_km =
+            trace.cumulative_distances[end_index] - trace.cumulative_distances[start_index];
+        let gain = trace.cumulative_elevation_gains[end_index]
+            - trace.cumulative_elevation_gains[start_index];
+        let loss = trace.cumulative_elevation_losses[end_index]
+            - trace.cumulative_elevation_losses[start_index];
+        cumulative += metrics.total_time;
+
+        splits.push(SplitStats {
+            split_id: splits.len(),
+            start_index,
+            end_index,
+            start_distance_km: trace.cumulative_distances[start_index],
+            distance_km: dist_km,
+            elevation_gain_m: gain,
+            elevation_loss_m: loss,
+            avg_slope: if dist_km > 0.0 {
+                (gain - loss) / (dist_km * 1000.0) * 100.0
+            } else {
+                0.0
+            },
+            pace_factor: if dist_km > 0.0 {
+                metrics.total_weighted_dist_km / dist_km
+            } else {
+                1.0
+            },
+            estimated_duration_s: metrics.total_time,
+            cumulative_duration_s: cumulative,
+        });
+        start_index = end_index;
+    }
+
+    splits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::location::Location;
+
+    // ~111 m between points along a meridian.
+    fn make_trace(n: usize, climb_per_point: f64) -> Trace {
+        let locs: Vec<Location> = (0..n)
+            .map(|i| Location {
+                longitude: 0.0,
+                latitude: i as f64 * 0.001,
+                altitude: 100.0 + i as f64 * climb_per_point,
+            })
+            .collect();
+        Trace::new(&locs).unwrap()
+    }
+
+    #[test]
+    fn splits_tile_the_trace() {
+        // ~10.45 km: ten full splits and a shorter remainder.
+        let trace = make_trace(95, 0.0);
+        let splits = compute(&trace, 1.0, &AnalysisOptions::default(), None);
+        assert_eq!(splits.len(), 11);
+        assert_eq!(splits[0].start_index, 0);
+        assert_eq!(splits.last().unwrap().end_index, 94);
+        assert!(splits.last().unwrap().distance_km < 0.5);
+        for w in splits.windows(2) {
+            assert_eq!(w[0].end_index, w[1].start_index);
+        }
+        let total: f64 = splits.iter().map(|s| s.distance_km).sum();
+        assert!((total - trace.total_distance()).abs() < 1e-9);
+        assert!(splits
+            .iter()
+            .all(|s| s.distance_km >= 0.999 || s.split_id == splits.len() - 1));
+    }
+
+    #[test]
+    fn carried_state_matches_a_single_pass() {
+        let trace = make_trace(200, 3.0);
+        let options = AnalysisOptions::default();
+        let splits = compute(&trace, MILE_KM, &options, Some(1_763_606_000));
+        let params =
+            SegmentParams::from_options(&options, Some(1_763_606_000), Some(&options.surfaces));
+        let mut state = SegmentState::new();
+        let whole = segment::compute(&trace, 0, 199, &params, &mut state);
+        let last = splits.last().unwrap();
+        assert!((last.cumulative_duration_s - whole.total_time).abs() < 1e-6);
+    }
+
+    #[test]
+    fn fatigue_slows_later_flat_splits() {
+        let trace = make_trace(100, 0.0);
+        let splits = compute(&trace, 1.0, &AnalysisOptions::default(), None);
+        let first = &splits[0];
+        let later = &splits[splits.len() - 2];
+        assert!(
+            later.estimated_duration_s / later.distance_km
+                > first.estimated_duration_s / first.distance_km
+        );
+    }
+
+    #[test]
+    fn climbing_split_reports_gain_grade_and_factor() {
+        let trace = make_trace(50, 11.1);
+        let splits = compute(&trace, 1.0, &AnalysisOptions::default(), None);
+        assert!(splits[0].elevation_gain_m > 0.0);
+        assert!(splits[0].avg_slope > 5.0);
+        assert!(splits[0].pace_factor > 1.0);
+    }
+
+    #[test]
+    fn invalid_split_length_or_single_point_yields_empty() {
+        let trace = make_trace(10, 0.0);
+        assert!(compute(&trace, 0.0, &AnalysisOptions::default(), None).is_empty());
+        assert!(compute(&trace, f64::NAN, &AnalysisOptions::default(), None).is_empty());
+        let single = make_trace(1, 0.0);
+        assert!(compute(&single, 1.0, &AnalysisOptions::default(), None).is_empty());
+    }
+
+    #[test]
+    fn tiny_split_length_gives_one_split_per_segment() {
+        let trace = make_trace(10, 0.0);
+        let splits = compute(&trace, 1e-300, &AnalysisOptions::default(), None);
+        assert_eq!(splits.len(), 9);
+        assert!(splits.iter().all(|s| s.end_index == s.start_index + 1));
+    }
+
+    #[test]
+    fn long_gap_between_points_keeps_boundaries_on_the_marks() {
+        // One point at ~2.8 km, then ~111 m steps to ~5.6 km.
+        let locs: Vec<Location> = std::iter::once(0.0)
+            .chain((25..=50).map(|i| i as f64 * 0.001))
+            .map(|latitude| Location {
+                longitude: 0.0,
+                latitude,
+                altitude: 100.0,
+            })
+            .collect();
+        let trace = Trace::new(&locs).unwrap();
+        let splits = compute(&trace, 1.0, &AnalysisOptions::default(), None);
+
+        let marks: Vec<f64> = splits.iter().map(|s| s.start_distance_km.floor()).collect();
+        assert_eq!(marks, [0.0, 2.0, 3.0, 4.0, 5.0]);
+        for s in &splits[2..] {
+            assert!(s.start_distance_km - s.start_distance_km.floor() < 0.12);
+        }
+    }
+}
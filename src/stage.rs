@@ -1,5 +1,7 @@
+use crate::calibration::BoundaryKind;
 use crate::interval::{compute_intervals, IntervalMetrics};
 use crate::pace_model::AnalysisOptions;
+use crate::route::ResolvedRoute;
 use crate::trace::Trace;
 use crate::waypoint::Waypoint;
 
@@ -21,7 +23,7 @@ pub struct StageStats {
     pub start_time: Option<i64>,
     pub end_time: Option<i64>,
     pub bearing: f64,
-    /// 1–5 based on average Minetti pace factor.
+    /// 1–5 from the average Minetti pace factor and the surface technicality.
     pub difficulty: u8,
     /// Moving time + planned stop at end checkpoint (seconds).
     pub estimated_duration_s: f64,
@@ -70,13 +72,15 @@ pub fn compute_from_waypoints(
     waypoints: &[Waypoint],
     options: &AnalysisOptions,
 ) -> Option<Vec<StageStats>> {
-    let intervals = compute_intervals(
-        trace,
-        waypoints,
-        options,
-        Waypoint::is_stage_boundary,
-        false,
-    )?;
+    compute_from_route(&ResolvedRoute::new(trace, waypoints), options)
+}
+
+/// [`compute_from_waypoints`] on a route already resolved.
+pub fn compute_from_route(
+    route: &ResolvedRoute<'_>,
+    options: &AnalysisOptions,
+) -> Option<Vec<StageStats>> {
+    let intervals = compute_intervals(route, options, BoundaryKind::Stage)?;
     Some(intervals.into_iter().map(StageStats::from).collect())
 }
 
@@ -108,6 +112,8 @@ mod tests {
             wpt_type: wpt_type.map(String::from),
             time,
             stop_duration: None,
+            surface: None,
+            crew: false,
         }
     }
 
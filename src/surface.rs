@@ -0,0 +1,271 @@
+//! Surface / technicality classes and their pace cost.
+//!
+//! Grade alone does not tell a road from a boulder field. A [`Surface`] can be
+//! attached to a waypoint (`<surface>` in the GPX, applying from that waypoint
+//! to the next one that sets a surface) or to explicit trace index ranges
+//! through [`AnalysisOptions::surfaces`](crate::AnalysisOptions::surfaces).
+//! Unclassified ground is priced as [`Surface::Trail`].
+
+use std::str::FromStr;
+
+use crate::location::Location;
+use crate::trace::Trace;
+use crate::waypoint::Waypoint;
+
+/// Surface class, from smooth to technical.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "wasm", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "wasm", serde(rename_all = "camelCase"))]
+pub enum Surface {
+    /// Paved road or smooth gravel track.
+    Road,
+    /// Runnable single track — the neutral baseline.
+    Trail,
+    /// Roots, steps, loose stones: footwork slows the stride.
+    Technical,
+    /// Scree or rocky ridge, hands occasionally needed.
+    Rocky,
+    /// Boulder field or scrambling.
+    Boulder,
+}
+
+impl Surface {
+    /// Pace multiplier relative to [`Surface::Trail`] at the same grade.
+    pub fn pace_factor(self) -> f64 {
+        match self {
+            Surface::Road => 0.95,
+            Surface::Trail => 1.0,
+            Surface::Technical => 1.15,
+            Surface::Rocky => 1.35,
+            Surface::Boulder => 1.7,
+        }
+    }
+
+    /// Technicality level, 1 (road) to 5 (boulder field).
+    pub fn level(self) -> u8 {
+        match self {
+            Surface::Road => 1,
+            Surface::Trail => 2,
+            Surface::Technical => 3,
+            Surface::Rocky => 4,
+            Surface::Boulder => 5,
+        }
+    }
+}
+
+impl FromStr for Surface {
+    type Err = ();
+
+    /// Accepts the class name (case-insensitive) or its level `"1"`–`"5"`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "road" | "1" => Ok(Surface::Road),
+            "trail" | "2" => Ok(Surface::Trail),
+            "technical" | "3" => Ok(Surface::Technical),
+            "rocky" | "scree" | "4" => Ok(Surface::Rocky),
+            "boulder" | "scramble" | "5" => Ok(Surface::Boulder),
+            _ => Err(()),
+        }
+    }
+}
+
+/// A surface over the trace segments `[start_index, end_index)`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SurfaceRange {
+    pub start_index: usize,
+    pub end_index: usize,
+    pub surface: Surface,
+}
+
+/// Surface ranges over trace indices. Later ranges take precedence where they overlap.
+#[derive(Debug, Clone, Default)]
+pub struct SurfaceMap {
+    ranges: Vec<SurfaceRange>,
+}
+
+impl SurfaceMap {
+    /// A map with no classified ranges.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Classify the segments `[start_index, end_index)` as `surface`.
+    pub fn with_range(mut self, start_index: usize, end_index: usize, surface: Surface) -> Self {
+        self.insert(start_index, end_index, surface);
+        self
+    }
+
+    /// Classify the segments `[start_index, end_index)` as `surface`.
+    pub fn insert(&mut self, start_index: usize, end_index: usize, surface: Surface) {
+        if start_index < end_index {
+            self.ranges.push(SurfaceRange {
+                start_index,
+                end_index,
+                surface,
+            });
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    pub fn ranges(&self) -> &[SurfaceRange] {
+        &self.ranges
+    }
+
+    /// Surface of the segment starting at trace point `index`, if classified.
+    pub fn surface_at(&self, index: usize) -> Option<Surface> {
+        self.ranges
+            .iter()
+            .rev()
+            .find(|r| r.start_index <= index && index < r.end_index)
+            .map(|r| r.surface)
+    }
+
+    /// Ranges from waypoint `<surface>` tags: each one applies from its
+    /// waypoint's closest trace point to the next surface-carrying waypoint,
+    /// or to the end of the trace.
+    pub fn from_waypoints(trace: &Trace, waypoints: &[Waypoint]) -> Self {
+        Self::from_pinned_waypoints(trace, waypoints, &[])
+    }
+
+    /// [`SurfaceMap::from_waypoints`], with waypoint `i` placed at trace
+    /// index `pinned[i]` when set instead of snapped.
+    pub(crate) fn from_pinned_waypoints(
+        trace: &Trace,
+        waypoints: &[Waypoint],
+        pinned: &[Option<usize>],
+    ) -> Self {
+        let mut map = Self::new();
+        let mut search_start = 0usize;
+        let mut open: Option<(usize, Surface)> = None;
+        for (i, wpt) in waypoints.iter().enumerate() {
+            if wpt.surface.is_none() {
+                continue;
+            }
+            let index = match pinned.get(i).copied().flatten() {
+                Some(index) => index,
+                None => {
+                    let target = Location {
+                        longitude: wpt.longitude,
+                        latitude: wpt.latitude,
+                        altitude: 0.0,
+                    };
+                    let Some((_, index, _)) = trace.find_closest_point_from(&target, search_start)
+                    else {
+                        continue;
+                    };
+                    index
+                }
+            };
+            search_start = index;
+            if let Some((start, surface)) = open {
+                map.insert(start, index, surface);
+            }
+            open = wpt.surface.map(|s| (index, s));
+        }
+        if let Some((start, surface)) = open {
+            map.insert(start, trace.locations.len() - 1, surface);
+        }
+        map
+    }
+
+    /// These ranges overlaid with `overrides` (typically
+    /// [`AnalysisOptions::surfaces`](crate::AnalysisOptions::surfaces)).
+    pub(crate) fn overlaid(&self, overrides: &SurfaceMap) -> Self {
+        let mut map = self.clone();
+        map.ranges.extend_from_slice(&overrides.ranges);
+        map
+    }
+}
+
+/// Difficulty (1–5) from the grade-based level and the interval's technicality:
+/// the harder of the two, one level higher when both are at least moderate (3).
+pub fn combined_difficulty(grade_level: u8, technicality: Option<u8>) -> u8 {
+    let Some(tech) = technicality else {
+        return grade_level;
+    };
+    let bump = u8::from(grade_level >= 3 && tech >= 3);
+    (grade_level.max(tech) + bump).clamp(1, 5)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_trace(n: usize) -> Trace {
+        let locs: Vec<Location> = (0..n)
+            .map(|i| Location {
+                longitude: 0.0,
+                latitude: i as f64 * 0.001,
+                altitude: 100.0,
+            })
+            .collect();
+        Trace::new(&locs).unwrap()
+    }
+
+    fn make_waypoint(lat: f64, surface: Option<Surface>) -> Waypoint {
+        Waypoint {
+            latitude: lat,
+            longitude: 0.0,
+            elevation: None,
+            name: String::new(),
+            description: None,
+            comment: None,
+            symbol: None,
+            wpt_type: None,
+            time: None,
+            stop_duration: None,
+            surface,
+            crew: false,
+        }
+    }
+
+    #[test]
+    fn parses_names_and_levels() {
+        assert_eq!("Rocky".parse(), Ok(Surface::Rocky));
+        assert_eq!(" boulder ".parse(), Ok(Surface::Boulder));
+        assert_eq!("3".parse(), Ok(Surface::Technical));
+        assert!("swamp".parse::<Surface>().is_err());
+        assert_eq!(Surface::Road.level(), 1);
+        assert!(Surface::Boulder.pace_factor() > Surface::Rocky.pace_factor());
+    }
+
+    #[test]
+    fn later_ranges_override_earlier_ones() {
+        let map = SurfaceMap::new()
+            .with_range(0, 10, Surface::Trail)
+            .with_range(4, 6, Surface::Boulder);
+        assert_eq!(map.surface_at(3), Some(Surface::Trail));
+        assert_eq!(map.surface_at(4), Some(Surface::Boulder));
+        assert_eq!(map.surface_at(6), Some(Surface::Trail));
+        assert_eq!(map.surface_at(10), None);
+    }
+
+    #[test]
+    fn waypoint_surface_runs_to_next_surface_waypoint() {
+        let trace = make_trace(11);
+        let waypoints = vec![
+            make_waypoint(0.002, Some(Surface::Rocky)),
+            make_waypoint(0.004, None),
+            make_waypoint(0.006, Some(Surface::Road)),
+        ];
+        let map = SurfaceMap::from_waypoints(&trace, &waypoints);
+        assert_eq!(map.surface_at(1), None);
+        assert_eq!(map.surface_at(2), Some(Surface::Rocky));
+        assert_eq!(map.surface_at(5), Some(Surface::Rocky));
+        assert_eq!(map.surface_at(6), Some(Surface::Road));
+        assert_eq!(map.surface_at(9), Some(Surface::Road));
+        assert_eq!(map.surface_at(10), None);
+    }
+
+    #[test]
+    fn difficulty_combines_grade_and_technicality() {
+        assert_eq!(combined_difficulty(2, None), 2);
+        assert_eq!(combined_difficulty(1, Some(5)), 5);
+        assert_eq!(combined_difficulty(4, Some(1)), 4);
+        assert_eq!(combined_difficulty(3, Some(3)), 4);
+        assert_eq!(combined_difficulty(5, Some(5)), 5);
+    }
+}
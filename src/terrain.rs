@@ -0,0 +1,269 @@
+//! Alternative terrain-cost functions alongside [`crate::minetti`].
+//!
+//! Every function maps a slope (rise/run, not %) to a pace factor relative to
+//! flat ground — the same `slope -> pace_factor` contract as
+//! [`minetti::pace_factor`]. [`TerrainCost`] selects one at runtime and plugs
+//! into [`AnalysisOptions`](crate::AnalysisOptions) as a [`PaceModel`].
+
+use crate::minetti;
+use crate::pace_model::PaceModel;
+use crate::trace::Trace;
+
+/// Slope domain shared with Minetti — the empirical rules below are not
+/// meaningful beyond ±45 %.
+const SLOPE_LIMIT: f64 = 0.45;
+
+/// Naismith: 5 km/h flat + 600 m/h climbing → +1/120 h per metre per flat km.
+const NAISMITH_CLIMB_PER_SLOPE: f64 = 1000.0 / 600.0 * 5.0;
+
+/// Langmuir descent band boundaries: tan 5° and tan 12°.
+const LANGMUIR_GENTLE_MIN: f64 = 0.087_488_663_525_924;
+const LANGMUIR_STEEP_MIN: f64 = 0.212_556_560_672_76;
+/// Langmuir: ±10 min per 300 m of descent, relative to the 12 min flat km.
+const LANGMUIR_DESCENT_PER_SLOPE: f64 = 1000.0 / 300.0 * 10.0 / 12.0;
+
+/// SchweizMobil hiking time (Wanderland Schweiz, 2010): minutes per horizontal
+/// km as a degree-15 polynomial in `slope × 10` (i.e. per 10 % of grade),
+/// lowest order first. Fitted over ±40 %.
+const SWISS_MIN_PER_KM: [f64; 16] = [
+    14.271,
+    3.6992,
+    2.5922,
+    -1.4384,
+    0.32105,
+    0.81542,
+    -0.090_261,
+    -0.20757,
+    0.010_192,
+    0.028_588,
+    -0.000_574_66,
+    -0.002_184_2,
+    1.5176e-5,
+    8.6894e-5,
+    -1.3584e-7,
+    -1.4026e-6,
+];
+/// The polynomial diverges outside its fitted range.
+const SWISS_SLOPE_LIMIT: f64 = 0.40;
+
+/// Tobler's hiking function as a pace factor: `v(0) / v(slope)` with
+/// `v = 6·exp(−3.5·|slope + 0.05|)`. Fastest at a −5 % grade.
+pub fn tobler_pace_factor(slope: f64) -> f64 {
+    let s = slope.clamp(-SLOPE_LIMIT, SLOPE_LIMIT);
+    (3.5 * ((s + 0.05).abs() - 0.05)).exp()
+}
+
+/// Plain Naismith (the rule `leg` uses): climbing costs time, descending is free.
+pub fn naismith_pace_factor(slope: f64) -> f64 {
+    let s = slope.clamp(-SLOPE_LIMIT, SLOPE_LIMIT);
+    1.0 + NAISMITH_CLIMB_PER_SLOPE * s.max(0.0)
+}
+
+/// Naismith with Langmuir's descent corrections: descents between 5° and 12°
+/// save 10 min per 300 m, descents steeper than 12° cost 10 min per 300 m.
+pub fn langmuir_pace_factor(slope: f64) -> f64 {
+    let s = slope.clamp(-SLOPE_LIMIT, SLOPE_LIMIT);
+    if s >= 0.0 {
+        return naismith_pace_factor(s);
+    }
+    let descent = -s;
+    if descent > LANGMUIR_STEEP_MIN {
+        1.0 + LANGMUIR_DESCENT_PER_SLOPE * descent
+    } else if descent >= LANGMUIR_GENTLE_MIN {
+        1.0 - LANGMUIR_DESCENT_PER_SLOPE * descent
+    } else {
+        1.0
+    }
+}
+
+/// Swiss hiking time (SchweizMobil): the official minutes-per-km polynomial
+/// relative to its flat value, so ~0.93 at −5 %, 1.40 at +10 %, 2.27 at +20 %.
+/// Slopes are clamped to ±40 %.
+pub fn swiss_pace_factor(slope: f64) -> f64 {
+    let s = slope.clamp(-SWISS_SLOPE_LIMIT, SWISS_SLOPE_LIMIT) * 10.0;
+    let minutes = SWISS_MIN_PER_KM
+        .iter()
+        .rev()
+        .fold(0.0, |acc, &c| acc * s + c);
+    minutes / SWISS_MIN_PER_KM[0]
+}
+
+/// Selectable terrain-cost function.
+///
+/// Implements [`PaceModel`] by swapping `terrain_factor` only — fatigue,
+/// circadian and weather keep their built-in behaviour.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "wasm", derive(serde::Serialize))]
+pub enum TerrainCost {
+    Minetti,
+    Tobler,
+    Naismith,
+    Langmuir,
+    SwissHiking,
+}
+
+impl TerrainCost {
+    /// Every model, in the order [`compare`] reports them.
+    pub const ALL: [TerrainCost; 5] = [
+        TerrainCost::Minetti,
+        TerrainCost::Tobler,
+        TerrainCost::Naismith,
+        TerrainCost::Langmuir,
+        TerrainCost::SwissHiking,
+    ];
+
+    /// Pace factor relative to flat terrain for `slope` (rise/run).
+    pub fn pace_factor(self, slope: f64) -> f64 {
+        match self {
+            TerrainCost::Minetti => minetti::pace_factor(slope),
+            TerrainCost::Tobler => tobler_pace_factor(slope),
+            TerrainCost::Naismith => naismith_pace_factor(slope),
+            TerrainCost::Langmuir => langmuir_pace_factor(slope),
+            TerrainCost::SwissHiking => swiss_pace_factor(slope),
+        }
+    }
+}
+
+impl PaceModel for TerrainCost {
+    fn terrain_factor(&self, slope_frac: f64) -> f64 {
+        self.pace_factor(slope_frac)
+    }
+}
+
+/// Terrain-only prediction for a whole trace under one [`TerrainCost`].
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "wasm", derive(serde::Serialize))]
+#[cfg_attr(feature = "wasm", serde(rename_all = "camelCase"))]
+pub struct TerrainComparison {
+    pub model: TerrainCost,
+    /// Σ seg_dist × pace factor — the flat distance of equal effort (km).
+    pub effort_distance_km: f64,
+    /// `effort_distance_km / total_distance`.
+    pub avg_pace_factor: f64,
+    /// Moving time at `base_pace_s_per_km`, no fatigue/circadian/weather (s).
+    pub estimated_duration_s: f64,
+    /// Part of `estimated_duration_s` spent on rising segments (s).
+    pub uphill_duration_s: f64,
+    /// Part of `estimated_duration_s` spent on falling segments (s).
+    pub downhill_duration_s: f64,
+}
+
+/// Report every [`TerrainCost`] for `trace` at a flat pace of
+/// `base_pace_s_per_km`, so the models can be compared side by side.
+pub fn compare(trace: &Trace, base_pace_s_per_km: f64) -> Vec<TerrainComparison> {
+    TerrainCost::ALL
+        .iter()
+        .map(|&model| {
+            let mut effort_distance_km = 0.0_f64;
+            let mut uphill_duration_s = 0.0_f64;
+            let mut downhill_duration_s = 0.0_f64;
+
+            for j in 0..trace.locations.len() - 1 {
+                let slope_frac = trace.slopes[j] / 100.0;
+                let seg_dist_km = trace.cumulative_distances[j + 1] - trace.cumulative_distances[j];
+                let weighted = seg_dist_km * model.pace_factor(slope_frac);
+                effort_distance_km += weighted;
+                if slope_frac > 0.0 {
+                    uphill_duration_s += weighted * base_pace_s_per_km;
+                } else if slope_frac < 0.0 {
+                    downhill_duration_s += weighted * base_pace_s_per_km;
+                }
+            }
+
+            TerrainComparison {
+                model,
+                effort_distance_km,
+                avg_pace_factor: if trace.total_distance > 0.0 {
+                    effort_distance_km / trace.total_distance
+                } else {
+                    1.0
+                },
+                estimated_duration_s: effort_distance_km * base_pace_s_per_km,
+                uphill_duration_s,
+                downhill_duration_s,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Location;
+
+    #[test]
+    fn every_model_is_neutral_on_flat() {
+        for model in TerrainCost::ALL {
+            assert!(
+                (model.pace_factor(0.0) - 1.0).abs() < 1e-9,
+                "{model:?} is not 1.0 on flat"
+            );
+        }
+    }
+
+    #[test]
+    fn tobler_is_fastest_at_minus_five_percent() {
+        assert!(tobler_pace_factor(-0.05) < 1.0);
+        assert!(tobler_pace_factor(-0.05) < tobler_pace_factor(-0.04));
+        assert!(tobler_pace_factor(-0.05) < tobler_pace_factor(-0.06));
+    }
+
+    #[test]
+    fn naismith_ignores_descent() {
+        assert!((naismith_pace_factor(-0.3) - 1.0).abs() < 1e-9);
+        // +10 % → 100 m per flat km → +10 min on a 12 min km.
+        assert!((naismith_pace_factor(0.10) - (1.0 + 10.0 / 12.0)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn langmuir_rewards_gentle_and_penalises_steep_descent() {
+        assert!((langmuir_pace_factor(-0.05) - 1.0).abs() < 1e-9);
+        assert!(langmuir_pace_factor(-0.15) < 1.0);
+        assert!(langmuir_pace_factor(-0.30) > 1.0);
+        assert_eq!(langmuir_pace_factor(0.2), naismith_pace_factor(0.2));
+    }
+
+    #[test]
+    fn swiss_follows_the_schweizmobil_polynomial() {
+        // 14.27 min/km flat, 20.0 at +10 %, 13.24 at −5 %, 27.5 at −30 %.
+        assert!((swiss_pace_factor(0.10) - 20.0 / 14.271).abs() < 1e-3);
+        assert!((swiss_pace_factor(-0.05) - 13.24 / 14.271).abs() < 1e-3);
+        assert!((swiss_pace_factor(-0.30) - 27.52 / 14.271).abs() < 1e-3);
+        // Clamped where the polynomial would turn back down.
+        assert_eq!(swiss_pace_factor(0.45), swiss_pace_factor(0.40));
+    }
+
+    #[test]
+    fn minetti_variant_matches_minetti_module() {
+        assert_eq!(
+            TerrainCost::Minetti.pace_factor(0.12),
+            minetti::pace_factor(0.12)
+        );
+    }
+
+    #[test]
+    fn compare_reports_every_model() {
+        let locs: Vec<Location> = (0..20)
+            .map(|i| Location {
+                longitude: 0.0,
+                latitude: i as f64 * 0.001,
+                altitude: if i < 10 {
+                    i as f64 * 15.0
+                } else {
+                    (19 - i) as f64 * 15.0
+                },
+            })
+            .collect();
+        let trace = Trace::new(&locs).unwrap();
+        let report = compare(&trace, 500.0);
+        assert_eq!(report.len(), TerrainCost::ALL.len());
+        for r in &report {
+            assert!(r.estimated_duration_s > 0.0);
+            assert!(r.uphill_duration_s + r.downhill_duration_s <= r.estimated_duration_s + 1e-9);
+            assert!((r.effort_distance_km * 500.0 - r.estimated_duration_s).abs() < 1e-6);
+        }
+        let naismith = &report[2];
+        let langmuir = &report[3];
+        assert!((naismith.uphill_duration_s - langmuir.uphill_duration_s).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,157 @@
+//! Per-point pace model state along a whole trace, for charts.
+//!
+//! Where sections and splits aggregate, the timeline keeps one value per trace
+//! point: the effort-weighted distance (`d_eff`) that drives fatigue, the
+//! combined pace factor with its terrain / fatigue / circadian / weather /
+//! altitude / darkness / surface breakdown, and the predicted moving time.
+
+use crate::pace_model::AnalysisOptions;
+use crate::segment::{self, SegmentParams, SegmentState};
+use crate::trace::Trace;
+
+/// Column-oriented series, each the same length as `trace.locations()`.
+///
+/// Factor columns hold the factor of the segment starting at each point; the
+/// last point repeats the final segment's. Distance and time columns are
+/// cumulative and start at 0.
+#[derive(Debug, Clone, Default)]
+pub struct Timeline {
+    /// Effort-weighted distance from the start (km) — the fatigue model's `d_eff`.
+    pub effort_distance_km: Vec<f64>,
+    /// Every multiplier applied to base pace.
+    pub pace_factor: Vec<f64>,
+    pub terrain_factor: Vec<f64>,
+    pub fatigue_factor: Vec<f64>,
+    pub circadian_factor: Vec<f64>,
+    pub weather_factor: Vec<f64>,
+    pub altitude_factor: Vec<f64>,
+    pub darkness_factor: Vec<f64>,
+    pub surface_factor: Vec<f64>,
+    /// Predicted moving time from the start (seconds).
+    pub cumulative_time_s: Vec<f64>,
+}
+
+impl Timeline {
+    pub fn len(&self) -> usize {
+        self.cumulative_time_s.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cumulative_time_s.is_empty()
+    }
+}
+
+/// Run the pace model over every segment of `trace`.
+///
+/// Same conventions as [`splits::compute`](crate::splits::compute): fatigue
+/// and circadian state carry along the whole trace from `clock_start`,
+/// weather is neutral (no checkpoint names to look a forecast up by) and no
+/// stops apply.
+pub fn compute(trace: &Trace, options: &AnalysisOptions, clock_start: Option<i64>) -> Timeline {
+    let n = trace.locations.len();
+    let params = SegmentParams::from_options(options, clock_start, Some(&options.surfaces));
+    let mut state = SegmentState::new();
+
+    let mut timeline = Timeline {
+        effort_distance_km: Vec::with_capacity(n),
+        pace_factor: Vec::with_capacity(n),
+        terrain_factor: Vec::with_capacity(n),
+        fatigue_factor: Vec::with_capacity(n),
+        circadian_factor: Vec::with_capacity(n),
+        weather_factor: Vec::with_capacity(n),
+        altitude_factor: Vec::with_capacity(n),
+        darkness_factor: Vec::with_capacity(n),
+        surface_factor: Vec::with_capacity(n),
+        cumulative_time_s: Vec::with_capacity(n),
+    };
+    timeline.effort_distance_km.push(0.0);
+    timeline.cumulative_time_s.push(0.0);
+
+    segment::compute_traced(trace, 0, n - 1, &params, &mut state, &mut |s, state| {
+        timeline.pace_factor.push(s.combined);
+        timeline.terrain_factor.push(s.terrain);
+        timeline.fatigue_factor.push(s.fatigue);
+        timeline.circadian_factor.push(s.circadian);
+        timeline.weather_factor.push(s.weather);
+        timeline.altitude_factor.push(s.altitude);
+        timeline.darkness_factor.push(s.darkness);
+        timeline.surface_factor.push(s.surface);
+        timeline.effort_distance_km.push(state.d_eff_m / 1000.0);
+        timeline.cumulative_time_s.push(state.elapsed_s);
+    });
+
+    for column in [
+        &mut timeline.pace_factor,
+        &mut timeline.terrain_factor,
+        &mut timeline.fatigue_factor,
+        &mut timeline.circadian_factor,
+        &mut timeline.weather_factor,
+        &mut timeline.altitude_factor,
+        &mut timeline.darkness_factor,
+        &mut timeline.surface_factor,
+    ] {
+        column.push(column.last().copied().unwrap_or(1.0));
+    }
+    timeline
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::location::Location;
+    use crate::splits;
+    use crate::surface::{Surface, SurfaceMap};
+
+    fn make_trace(n: usize, climb_per_point: f64) -> Trace {
+        let locs: Vec<Location> = (0..n)
+            .map(|i| Location {
+                longitude: 0.0,
+                latitude: i as f64 * 0.001,
+                altitude: 100.0 + i as f64 * climb_per_point,
+            })
+            .collect();
+        Trace::new(&locs).unwrap()
+    }
+
+    #[test]
+    fn columns_match_the_trace_and_the_split_prediction() {
+        let trace = make_trace(40, 6.0);
+        let options = AnalysisOptions::default().fatigue(0.05);
+        let timeline = compute(&trace, &options, None);
+        assert_eq!(timeline.len(), trace.locations.len());
+        assert_eq!(timeline.pace_factor.len(), timeline.len());
+        assert_eq!(timeline.cumulative_time_s[0], 0.0);
+
+        let total = splits::compute(&trace, 100.0, &options, None)[0].cumulative_duration_s;
+        assert!((timeline.cumulative_time_s.last().unwrap() - total).abs() < 1e-6);
+
+        // d_eff grows faster than distance on a climb, and fatigue with it.
+        assert!(*timeline.effort_distance_km.last().unwrap() > trace.total_distance());
+        assert!(timeline.fatigue_factor[38] > timeline.fatigue_factor[0]);
+        assert!(timeline.effort_distance_km.windows(2).all(|w| w[1] > w[0]));
+    }
+
+    #[test]
+    fn breakdown_multiplies_to_the_combined_factor() {
+        const T0: i64 = 1_763_625_600 - 6 * 3600; // 02:00 UTC, inside the circadian window
+        let trace = make_trace(20, 3.0);
+        let options = AnalysisOptions::default()
+            .surfaces(SurfaceMap::new().with_range(5, 10, Surface::Rocky))
+            .darkness(true);
+        let timeline = compute(&trace, &options, Some(T0));
+        assert!(timeline.circadian_factor[0] > 1.0);
+        assert!(timeline.darkness_factor[0] > 1.0);
+        assert_eq!(timeline.surface_factor[5], Surface::Rocky.pace_factor());
+        assert_eq!(timeline.surface_factor[10], 1.0);
+        for i in 0..timeline.len() {
+            let product = timeline.terrain_factor[i]
+                * timeline.fatigue_factor[i]
+                * timeline.circadian_factor[i]
+                * timeline.weather_factor[i]
+                * timeline.altitude_factor[i]
+                * timeline.darkness_factor[i]
+                * timeline.surface_factor[i];
+            assert!((product - timeline.pace_factor[i]).abs() < 1e-12);
+        }
+    }
+}
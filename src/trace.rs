@@ -1,9 +1,10 @@
 use crate::area::Area;
 use crate::climbs::{detect_climbs, ClimbStats};
 use crate::elevation::{
-    compute_gain_loss, compute_slopes, cumulative_horizontal_distances, ELEV_MEDIAN_RADIUS_KM,
-    ELEV_NOISE_THRESHOLD_M,
+    compute_gain_loss, compute_slopes, cumulative_horizontal_distances, fill_missing_altitudes,
+    ElevationFill, ELEV_MEDIAN_RADIUS_KM, ELEV_NOISE_THRESHOLD_M,
 };
+use crate::energy::{EnergyCurve, Gait};
 use crate::extrema::{find_peaks, find_valleys};
 use crate::simplify::douglas_peucker_indices;
 use crate::{Elevation, Location, TraceError};
@@ -43,16 +44,44 @@ pub struct Trace {
     pub(crate) total_elevation_loss: f64,
     pub(crate) area: Area,
     pub(crate) elevation: Elevation,
+    /// `true` where the altitude was synthesised by the `ElevationFill` policy.
+    pub(crate) filled_altitudes: Vec<bool>,
+    /// Index into the raw input that each working location was taken from.
+    pub(crate) source_indices: Vec<usize>,
 }
 
 impl Trace {
     /// Builds a trace from raw locations. Fails with [`TraceError::EmptyTrace`]
     /// if `raw` is empty — a `Trace` is never empty once constructed.
+    ///
+    /// Missing altitudes are interpolated from their neighbours — see
+    /// [`Trace::with_elevation_fill`].
     pub fn new(raw: &[Location]) -> Result<Self, TraceError> {
+        Self::with_elevation_fill(raw, ElevationFill::default())
+    }
+
+    /// Like [`Trace::new`], resolving missing (`NaN`) altitudes with `fill`.
+    ///
+    /// Synthesised altitudes (see [`Trace::filled_altitudes`]) drive slopes and
+    /// the pace model, but are excluded from denoised gain/loss and from
+    /// peak/valley detection so they never invent climbs.
+    pub fn with_elevation_fill(raw: &[Location], fill: ElevationFill) -> Result<Self, TraceError> {
         if raw.is_empty() {
             return Err(TraceError::EmptyTrace);
         }
 
+        // Only copy the input when there is something to fill.
+        let filled_raw: Vec<Location>;
+        let raw_synthetic: Vec<bool>;
+        let raw = if raw.iter().all(Location::has_altitude) {
+            raw_synthetic = vec![false; raw.len()];
+            raw
+        } else {
+            let mut copy = raw.to_vec();
+            raw_synthetic = fill_missing_altitudes(&mut copy, fill);
+            filled_raw = copy;
+            filled_raw.as_slice()
+        };
         let n = raw.len();
 
         // Simplify large datasets to keep per-point math cheap.
@@ -102,21 +131,46 @@ impl Trace {
                 },
             );
 
-        // Denoised elevation computed on the full-resolution raw signal.
-        let gain_loss = compute_gain_loss(raw, ELEV_MEDIAN_RADIUS_KM, ELEV_NOISE_THRESHOLD_M);
+        // Denoised elevation computed on the full-resolution raw signal, with
+        // synthesised altitudes put back to `NaN` so only measured data counts.
+        let gain_loss = if raw_synthetic.contains(&true) {
+            let measured: Vec<Location> = raw
+                .iter()
+                .zip(&raw_synthetic)
+                .map(|(loc, &synthetic)| Location {
+                    altitude: if synthetic { f64::NAN } else { loc.altitude },
+                    ..*loc
+                })
+                .collect();
+            compute_gain_loss(&measured, ELEV_MEDIAN_RADIUS_KM, ELEV_NOISE_THRESHOLD_M)
+        } else {
+            compute_gain_loss(raw, ELEV_MEDIAN_RADIUS_KM, ELEV_NOISE_THRESHOLD_M)
+        };
 
         // Map raw cumulative values back to the (possibly simplified) working set.
         let n_pts = src_indices.len();
         let mut cumulative_elevation_gains = Vec::with_capacity(n_pts);
         let mut cumulative_elevation_losses = Vec::with_capacity(n_pts);
+        let mut filled_altitudes = Vec::with_capacity(n_pts);
         for &src in &src_indices {
             cumulative_elevation_gains.push(gain_loss.cum_gain[src]);
             cumulative_elevation_losses.push(gain_loss.cum_loss[src]);
+            filled_altitudes.push(raw_synthetic[src]);
         }
 
         let slopes = compute_slopes(&locations, &cumulative_distances);
 
-        let elevations: Vec<f32> = locations.iter().map(|l| l.altitude as f32).collect();
+        let elevations: Vec<f32> = locations
+            .iter()
+            .zip(&filled_altitudes)
+            .map(|(l, &synthetic)| {
+                if synthetic {
+                    f32::NAN
+                } else {
+                    l.altitude as f32
+                }
+            })
+            .collect();
         let peaks = if locations.len() >= 3 {
             find_peaks(&elevations)
         } else {
@@ -144,6 +198,8 @@ impl Trace {
             total_elevation_loss: gain_loss.total_loss,
             area,
             elevation,
+            filled_altitudes,
+            source_indices: src_indices,
         })
     }
 
@@ -189,6 +245,12 @@ impl Trace {
         &self.climbs
     }
 
+    /// Cumulative metabolic energy and equivalent flat distance at each point
+    /// for a runner of `mass_kg` (see [`EnergyCurve`]).
+    pub fn energy_curve(&self, mass_kg: f64, gait: Gait) -> EnergyCurve {
+        EnergyCurve::compute(self, mass_kg, gait)
+    }
+
     /// Total trace distance in km.
     pub fn total_distance(&self) -> f64 {
         self.total_distance
@@ -204,6 +266,18 @@ impl Trace {
         self.total_elevation_loss
     }
 
+    /// `true` at each point whose altitude was synthesised (interpolated or
+    /// zeroed) because the source had none. Same length as `locations()`.
+    pub fn filled_altitudes(&self) -> &[bool] {
+        &self.filled_altitudes
+    }
+
+    /// Raw-input index of each working location (identity unless the trace
+    /// was Douglas-Peucker simplified). Same length as `locations()`.
+    pub fn source_indices(&self) -> &[usize] {
+        &self.source_indices
+    }
+
     // ── Distance helpers ──────────────────────────────────────────────────────
 
     /// Index of the first location at or beyond `dist_km` (binary search).
@@ -367,6 +441,52 @@ mod tests {
         assert_eq!(trace.slopes.len(), trace.locations.len());
     }
 
+    #[test]
+    fn missing_altitudes_are_filled_and_flagged() {
+        let locations = [
+            Location {
+                longitude: 0.0,
+                latitude: 0.0,
+                altitude: 100.0,
+            },
+            Location {
+                longitude: 0.0,
+                latitude: 0.001,
+                altitude: f64::NAN,
+            },
+            Location {
+                longitude: 0.0,
+                latitude: 0.002,
+                altitude: 120.0,
+            },
+        ];
+        let trace = Trace::new(&locations).unwrap();
+        assert_eq!(trace.filled_altitudes(), &[false, true, false]);
+        assert!((trace.locations[1].altitude - 110.0).abs() < 1e-6);
+        assert!(trace.slopes.iter().all(|s| s.is_finite()));
+
+        let zeroed = Trace::with_elevation_fill(&locations, ElevationFill::Zero).unwrap();
+        assert_eq!(zeroed.locations[1].altitude, 0.0);
+        // The zeroed point is synthetic, so it never shows up as a 100 m drop.
+        assert_eq!(zeroed.total_elevation_loss, 0.0);
+        assert!(zeroed.valleys.is_empty());
+    }
+
+    #[test]
+    fn trace_without_any_altitude_is_flat() {
+        let locations: Vec<Location> = (0..5)
+            .map(|i| Location {
+                longitude: 0.0,
+                latitude: i as f64 * 0.001,
+                altitude: f64::NAN,
+            })
+            .collect();
+        let trace = Trace::new(&locations).unwrap();
+        assert!(trace.filled_altitudes().iter().all(|&f| f));
+        assert_eq!(trace.total_elevation_gain, 0.0);
+        assert!(trace.slopes.iter().all(|&s| s == 0.0));
+    }
+
     // ── Precomputed arrays ────────────────────────────────────────────────────
 
     #[test]
@@ -605,5 +725,19 @@ mod tests {
         assert_eq!(trace.total_distance(), trace.total_distance);
         assert_eq!(trace.total_elevation_gain(), trace.total_elevation_gain);
         assert_eq!(trace.total_elevation_loss(), trace.total_elevation_loss);
+        assert_eq!(trace.filled_altitudes(), &trace.filled_altitudes);
+        assert_eq!(trace.source_indices(), &trace.source_indices);
+    }
+
+    #[test]
+    fn source_indices_point_back_into_raw_input() {
+        let locations = helper::get_locations();
+        let trace = Trace::new(&locations).unwrap();
+        assert_eq!(trace.source_indices().len(), trace.locations().len());
+        for (loc, &src) in trace.locations().iter().zip(trace.source_indices()) {
+            assert_eq!(*loc, locations[src]);
+        }
+        assert_eq!(trace.source_indices()[0], 0);
+        assert_eq!(*trace.source_indices().last().unwrap(), locations.len() - 1);
     }
 }
@@ -1,17 +1,28 @@
 use wasm_bindgen::prelude::*;
 
 use crate::calibration::{self, BoundaryKind};
+use crate::crew::{self, CrewItinerary};
+use crate::route::Pin;
 use crate::{build_trace as core_build_trace, Location};
 
 mod dto;
 mod options;
+mod schedule;
+mod timeline;
 mod trace;
 
 use dto::{
-    WasmGpxFull, WasmGpxMetadata, WasmRecalibration, WasmRouteAnalysis, WasmSectionStats,
+    WasmGpxFull, WasmGpxMetadata, WasmHistoryRecalibration, WasmPassageRecalibration,
+    WasmRecalibration, WasmRouteAnalysis, WasmSectionStats, WasmSleepSuggestion, WasmSplitStats,
     WasmStageStats, WasmTraceSummary, WasmWaypoint,
 };
-use options::{WasmAnalyzeOptions, WasmRecalibrateOptions};
+use options::{
+    WasmAnalyzeOptions, WasmCrewOptions, WasmHistoryRecalibrateOptions,
+    WasmPassagesRecalibrateOptions, WasmPin, WasmRecalibrateOptions, WasmScheduleOptions,
+    WasmSplitsOptions, WasmTimelineOptions,
+};
+pub use schedule::Schedule;
+pub use timeline::Timeline;
 pub use trace::Trace;
 
 // ── Console warning helper (no web-sys dep) ──────────────────────────────────
@@ -107,10 +118,23 @@ pub fn parse_metadata_js(bytes: &[u8]) -> JsValue {
 /// [`parseGpxAll`] rather than calling this function (which
 /// discards the `Trace` handle, returning only JSON).
 ///
-/// `options` — a JS object: `{ basePaceSPerKm, kFatigue, lifeBaseStopS, weather? }`.
+/// `options` — a JS object: `{ basePaceSPerKm, kFatigue, lifeBaseStopS, weather?, forecast?,
+/// altitudeAcclimatizationM?, darkness?, surfaces?, sleep?, nutrition? }`. Setting `altitudeAcclimatizationM` (m; `0` for a
+/// sea-level runner) enables the altitude factor; `darkness: true` enables the
+/// darkness factor (needs a Start `<time>`). `surfaces` is an optional array of
+/// `{ startIndex, endIndex, surface }` with `surface` one of `"road"`, `"trail"`,
+/// `"technical"`, `"rocky"`, `"boulder"`; it overrides waypoint `<surface>` tags.
+/// `sleep` is an optional array of `{ checkpoint?, notBefore?, durationS }`
+/// planned sleep blocks, taken at the named checkpoint or at the first
+/// checkpoint reached at or after the `notBefore` epoch time. `nutrition` is
+/// an optional `{ massKg, fluidCapacityL?, foodCapacityKcal? }` that adds
+/// energy, fluid and sodium needs to each section.
 /// `weather` is an optional array of
-/// `{ name, temperatureC, humidityPct, windKmh, precipProbPct }`, matched against
-/// checkpoint names; unmatched checkpoints use neutral weather.
+/// `{ name, temperatureC, humidityPct, windKmh, precipProbPct, windDirDeg? }`, matched against
+/// checkpoint names; unmatched checkpoints use neutral weather. `forecast` is an
+/// optional array of `{ name, samples: [{ time, temperatureC, humidityPct,
+/// windKmh, precipProbPct }, …] }` (epoch-second `time`), interpolated to each
+/// segment's predicted clock time; it overrides `weather` where present.
 ///
 /// Returns a JS object with `{ trace, waypoints, legs, sections, stages, metadata }`
 /// or `null` when the GPX contains no valid track-points or `options` is malformed.
@@ -161,6 +185,21 @@ pub fn build_trace(flat: &[f64]) -> Option<Trace> {
     })
 }
 
+/// Sun events for the local solar day containing `time` (epoch seconds) at a
+/// position: `{ solarNoon, civilDawn, sunrise, sunset, civilDusk }`, all epoch
+/// seconds. Event fields are `null` during polar day or night.
+#[wasm_bindgen(js_name = "sunTimes")]
+pub fn sun_times(longitude: f64, latitude: f64, time: f64) -> JsValue {
+    let location = Location {
+        longitude,
+        latitude,
+        altitude: 0.0,
+    };
+    // `f64`, not `i64`: wasm-bindgen maps `i64` to BigInt, and JS epochs are numbers.
+    serde_wasm_bindgen::to_value(&crate::solar::sun_times(&location, time as i64))
+        .unwrap_or(JsValue::UNDEFINED)
+}
+
 /// Parse GPX `bytes` into a `Trace` that carries **all three** — track-points,
 /// waypoints and metadata — in a single (triple-scan) pass.
 ///
@@ -176,11 +215,10 @@ fn parse_wasm_trace(bytes: &[u8]) -> Option<Trace> {
 fn compute_route_analysis(trace: &Trace, options: &WasmAnalyzeOptions) -> WasmRouteAnalysis {
     let analysis_opts = options.to_analysis_options();
 
-    let legs = crate::leg::compute_from_waypoints(trace.inner(), trace.waypoints());
-    let sections =
-        crate::section::compute_from_waypoints(trace.inner(), trace.waypoints(), &analysis_opts);
-    let stages =
-        crate::stage::compute_from_waypoints(trace.inner(), trace.waypoints(), &analysis_opts);
+    let route = trace.route();
+    let legs = crate::leg::compute_from_route(&route);
+    let sections = crate::section::compute_from_route(&route, &analysis_opts);
+    let stages = crate::stage::compute_from_route(&route, &analysis_opts);
 
     WasmRouteAnalysis::new(
         trace.waypoints().iter().map(Into::into).collect(),
@@ -191,26 +229,84 @@ fn compute_route_analysis(trace: &Trace, options: &WasmAnalyzeOptions) -> WasmRo
     )
 }
 
+/// Shared implementation behind `Trace::splits`.
+fn compute_splits(trace: &Trace, options: &WasmSplitsOptions) -> Vec<WasmSplitStats> {
+    let clock_start = options
+        .start_time()
+        .or_else(|| trace.waypoints().first().and_then(|w| w.time));
+    crate::splits::compute(
+        trace.inner(),
+        options.split_km(),
+        &options.to_analysis_options(),
+        clock_start,
+    )
+    .into_iter()
+    .map(Into::into)
+    .collect()
+}
+
+/// Shared implementation behind `Trace::timeline`.
+fn compute_timeline(trace: &Trace, options: &WasmTimelineOptions) -> Timeline {
+    let clock_start = options
+        .start_time()
+        .or_else(|| trace.waypoints().first().and_then(|w| w.time));
+    Timeline::new(crate::timeline::compute(
+        trace.inner(),
+        &options.to_analysis_options(),
+        clock_start,
+    ))
+}
+
+/// Shared implementation behind `Trace::schedule`. `None` without a start
+/// time in the options or on the first waypoint.
+fn compute_schedule(trace: &Trace, options: &WasmScheduleOptions) -> Option<Schedule> {
+    let start_time = options
+        .start_time()
+        .or_else(|| trace.waypoints().first().and_then(|w| w.time))?;
+    Some(Schedule::new(crate::schedule::ArrivalSchedule::compute(
+        trace.inner(),
+        trace.waypoints(),
+        &options.to_analysis_options(),
+        start_time,
+    )))
+}
+
+/// Shared implementation behind `Trace::suggest_sleep`.
+fn compute_sleep_suggestions(
+    trace: &Trace,
+    options: &WasmAnalyzeOptions,
+    duration_s: u32,
+) -> Vec<WasmSleepSuggestion> {
+    crate::sleep::suggest(
+        trace.inner(),
+        trace.waypoints(),
+        &options.to_analysis_options(),
+        duration_s,
+    )
+    .into_iter()
+    .map(Into::into)
+    .collect()
+}
+
 /// Shared implementation behind `Trace::recalibrate`.
 ///
-/// Runs `recalibrate_from_current` once per boundary kind — sections
+/// Runs `recalibrate_on_route` once per boundary kind — sections
 /// (checkpoint granularity) and stages (LifeBase granularity) — since each
 /// re-solves its own calibration factor against its own weather-per-range
 /// lookups rather than sharing one result.
 fn compute_recalibration(trace: &Trace, options: &WasmRecalibrateOptions) -> WasmRecalibration {
     let analysis_opts = options.to_analysis_options();
 
-    let sections = calibration::recalibrate_from_current(
-        trace.inner(),
-        trace.waypoints(),
+    let route = trace.route();
+    let sections = calibration::recalibrate_on_route(
+        &route,
         BoundaryKind::Section,
         options.current_index(),
         options.actual_elapsed_s(),
         &analysis_opts,
     );
-    let stages = calibration::recalibrate_from_current(
-        trace.inner(),
-        trace.waypoints(),
+    let stages = calibration::recalibrate_on_route(
+        &route,
         BoundaryKind::Stage,
         options.current_index(),
         options.actual_elapsed_s(),
@@ -220,6 +316,82 @@ fn compute_recalibration(trace: &Trace, options: &WasmRecalibrateOptions) -> Was
     WasmRecalibration::new(sections, stages)
 }
 
+/// Shared implementation behind `Trace::recalibrateFromHistory`; sections
+/// and stages fit separately, as in [`compute_recalibration`].
+fn compute_history_recalibration(
+    trace: &Trace,
+    options: &WasmHistoryRecalibrateOptions,
+) -> WasmHistoryRecalibration {
+    let analysis_opts = options.to_analysis_options();
+    let history = options.history();
+    let run = |kind| {
+        calibration::history::recalibrate_from_history(
+            trace.inner(),
+            trace.waypoints(),
+            kind,
+            &history,
+            &analysis_opts,
+        )
+    };
+    WasmHistoryRecalibration::new(run(BoundaryKind::Section), run(BoundaryKind::Stage))
+}
+
+/// Shared implementation behind `Trace::recalibrateFromPassages`.
+fn compute_passage_recalibration(
+    trace: &Trace,
+    options: &WasmPassagesRecalibrateOptions,
+) -> WasmPassageRecalibration {
+    let analysis_opts = options.to_analysis_options();
+    let run = |kind| {
+        calibration::passages::recalibrate_from_passages(
+            trace.inner(),
+            trace.waypoints(),
+            kind,
+            options.passages(),
+            &analysis_opts,
+        )
+    };
+    WasmPassageRecalibration::new(run(BoundaryKind::Section), run(BoundaryKind::Stage))
+}
+
+/// Shared implementation behind `Trace::crewPlan`: section predictions, as
+/// in [`compute_route_analysis`], turned into crew windows. `None` without a start
+/// time or without sections.
+fn compute_crew_plan(trace: &Trace, options: &WasmCrewOptions) -> Option<CrewItinerary> {
+    let start_time = options
+        .start_time()
+        .or_else(|| trace.waypoints().first().and_then(|w| w.time))?;
+    let sections =
+        crate::section::compute_from_route(&trace.route(), &options.to_analysis_options())?;
+    let mut waypoints = trace.waypoints().to_vec();
+    for wpt in &mut waypoints {
+        wpt.crew |= options.crew_points().contains(&wpt.name);
+    }
+    Some(crew::plan(
+        &waypoints,
+        &crew::windows_from_sections(&sections, options.spread()),
+        &options.drive_times(),
+        start_time,
+        options.setup_s(),
+    ))
+}
+
+/// Shared implementation behind `Trace::pinWaypoints`: pins by waypoint
+/// name, the first waypoint of that name. Returns the pins alongside the
+/// names skipped for being unknown or missing a position.
+fn resolve_pins<'a>(trace: &Trace, pins: &'a [WasmPin]) -> (Vec<(usize, Pin)>, Vec<&'a str>) {
+    let mut resolved = Vec::with_capacity(pins.len());
+    let mut skipped = Vec::new();
+    for p in pins {
+        let wpt = trace.waypoints().iter().position(|w| w.name == p.name());
+        match wpt.zip(p.pin()) {
+            Some(pin) => resolved.push(pin),
+            None => skipped.push(p.name()),
+        }
+    }
+    (resolved, skipped)
+}
+
 #[cfg(test)]
 mod pipeline_tests {
     use super::*;
@@ -298,6 +470,8 @@ mod pipeline_tests {
         assert_eq!(sections[0]["stageIdx"], 0);
         // Start (06:00) -> LifeBase (10:00): a 4h cutoff window.
         assert_eq!(sections[0]["maxCompletionTime"], 4 * 3600);
+        assert!(sections[0]["cutoffSlackMin"].as_f64().is_some());
+        assert!(sections[0]["cutoffRisk"].is_string());
 
         let stages = json["stages"]
             .as_array()
@@ -335,6 +509,8 @@ mod pipeline_tests {
             .expect("Start/LifeBase/Arrival should yield section etas");
         assert_eq!(sections["etas"].as_array().unwrap().len(), 2);
         assert!(sections["calibrationFactor"].as_f64().unwrap() > 0.0);
+        let last_eta = sections["etas"].as_array().unwrap().last().unwrap();
+        assert!(last_eta["cutoffSlackMin"].as_f64().is_some());
 
         let stages = json["stages"]
             .as_object()
@@ -342,6 +518,52 @@ mod pipeline_tests {
         assert_eq!(stages["etas"].as_array().unwrap().len(), 2);
     }
 
+    #[test]
+    fn splits_tile_the_parsed_trace() {
+        let trace = parse_wasm_trace(SAMPLE_GPX).expect("sample GPX should parse");
+        let splits = compute_splits(&trace, &WasmSplitsOptions::sample(1.0));
+        let json = serde_json::to_value(&splits).expect("splits should serialize");
+
+        let rows = json.as_array().unwrap();
+        assert!(rows.len() > 1);
+        assert_eq!(rows[0]["startIndex"], 0);
+        assert_eq!(rows.last().unwrap()["endIndex"], 4);
+        let total: f64 = rows.iter().map(|r| r["distanceKm"].as_f64().unwrap()).sum();
+        assert!((total - trace.inner().total_distance()).abs() < 1e-9);
+        assert!(compute_splits(&trace, &WasmSplitsOptions::sample(0.0)).is_empty());
+    }
+
+    #[test]
+    fn timeline_columns_cover_every_trace_point() {
+        let trace = parse_wasm_trace(SAMPLE_GPX).expect("sample GPX should parse");
+        let timeline = compute_timeline(&trace, &WasmTimelineOptions::sample());
+        let n = trace.inner().locations().len();
+
+        assert_eq!(timeline.length() as usize, n);
+        assert_eq!(timeline.pace_factors().len(), n);
+        assert_eq!(timeline.effort_distances()[0], 0.0);
+        let splits = compute_splits(&trace, &WasmSplitsOptions::sample(100.0));
+        let total = serde_json::to_value(&splits).unwrap()[0]["cumulativeDurationS"]
+            .as_f64()
+            .unwrap();
+        assert!((timeline.cumulative_times()[n - 1] - total).abs() < 1e-6);
+    }
+
+    #[test]
+    fn schedule_needs_a_start_time() {
+        let trace = parse_wasm_trace(SAMPLE_GPX).expect("sample GPX should parse");
+        let schedule = compute_schedule(&trace, &WasmScheduleOptions::sample(Some(1_000)))
+            .expect("explicit start time");
+        let arrivals = schedule.arrival_times();
+        assert_eq!(arrivals.len(), trace.inner().locations().len());
+        assert_eq!(arrivals[0], 1_000.0);
+        assert!(schedule.finish_time() > 1_000.0);
+
+        let flat = [7.0, 45.0, 1000.0, 7.01, 45.01, 1100.0];
+        let bare = build_trace(&flat).expect("flat coords should build a trace");
+        assert!(compute_schedule(&bare, &WasmScheduleOptions::sample(None)).is_none());
+    }
+
     #[test]
     fn recalibrate_returns_null_for_both_kinds_with_fewer_than_two_boundaries() {
         let flat = [7.0, 45.0, 1000.0, 7.01, 45.01, 1100.0];
@@ -352,4 +574,113 @@ mod pipeline_tests {
         assert!(json["sections"].is_null());
         assert!(json["stages"].is_null());
     }
+
+    #[test]
+    fn recalibrate_from_history_reports_multipliers_and_banded_etas() {
+        let trace = parse_wasm_trace(SAMPLE_GPX).expect("sample GPX should parse");
+        // 06:00 start, Life Base (third point) two hours later.
+        const T0: i64 = 1_763_618_400;
+        let options = WasmHistoryRecalibrateOptions::sample(&[
+            (7.0, 45.0, T0),
+            (7.01, 45.01, T0 + 3600),
+            (7.02, 45.02, T0 + 7200),
+        ]);
+        let recalibration = compute_history_recalibration(&trace, &options);
+        let json = serde_json::to_value(&recalibration).expect("recalibration should serialize");
+
+        let sections = json["sections"]
+            .as_object()
+            .expect("Start/LifeBase/Arrival should yield section etas");
+        assert_eq!(sections["currentIndex"], 2);
+        assert_eq!(sections["actualElapsedS"], 7200.0);
+        assert!(sections["multipliers"]["uphill"].as_f64().unwrap() > 0.0);
+        let last_eta = sections["etas"].as_array().unwrap().last().unwrap();
+        // RecalibratedEta fields are flattened next to the band.
+        let remaining = last_eta["cumulativeRemainingS"].as_f64().unwrap();
+        assert!(last_eta["earliestRemainingS"].as_f64().unwrap() < remaining);
+        assert!(last_eta["latestRemainingS"].as_f64().unwrap() > remaining);
+        assert!(json["stages"].is_object());
+    }
+
+    #[test]
+    fn recalibrate_from_passages_reports_actual_sections_and_etas() {
+        use crate::calibration::passages::Passage;
+
+        let trace = parse_wasm_trace(SAMPLE_GPX).expect("sample GPX should parse");
+        const T0: i64 = 1_763_618_400; // 06:00, the Start waypoint's time
+        let options = WasmPassagesRecalibrateOptions::sample(vec![
+            Passage::new("Start", T0),
+            Passage::new("Life Base", T0 + 7200),
+            Passage::new("Life Base", T0 + 7200 + 900),
+        ]);
+        let recalibration = compute_passage_recalibration(&trace, &options);
+        let json = serde_json::to_value(&recalibration).expect("recalibration should serialize");
+
+        let sections = json["sections"]
+            .as_object()
+            .expect("Start/LifeBase/Arrival should yield section etas");
+        assert_eq!(sections["actualElapsedS"], 8100.0);
+        assert_eq!(sections["sections"][0]["durationS"], 7200.0);
+        assert_eq!(sections["sections"][0]["dwellS"], 900.0);
+        let etas = sections["etas"].as_array().unwrap();
+        assert_eq!(etas[0]["remainingDurationS"], 0.0);
+        assert!(etas[1]["cutoffSlackMin"].as_f64().is_some());
+        assert!(json["stages"].is_object());
+    }
+
+    #[test]
+    fn crew_plan_flags_a_transfer_slower_than_the_runner() {
+        let trace = parse_wasm_trace(SAMPLE_GPX).expect("sample GPX should parse");
+        let options = WasmCrewOptions::sample(
+            &["Life Base", "Arrival"],
+            &[("Life Base", "Arrival", 3.0 * 3600.0)],
+        );
+        let plan = compute_crew_plan(&trace, &options).expect("Start has a time");
+        let json = serde_json::to_value(&plan).expect("itinerary should serialize");
+
+        let stops = json["stops"].as_array().unwrap();
+        assert_eq!(stops.len(), 2);
+        assert_eq!(stops[0]["name"], "Life Base");
+        let earliest = stops[0]["earliestArrival"].as_i64().unwrap();
+        assert_eq!(stops[0]["beThereBy"].as_i64().unwrap(), earliest - 900);
+        let stop = stops[0]["likelyDeparture"].as_i64().unwrap()
+            - stops[0]["likelyArrival"].as_i64().unwrap();
+        assert_eq!(stop, 1800);
+        assert_eq!(json["transfers"][0]["risk"], "impossible");
+        assert!(json["transfers"][0]["slackS"].as_f64().unwrap() < 0.0);
+    }
+
+    #[test]
+    fn pinned_waypoints_show_up_in_the_snap_report() {
+        let mut trace = parse_wasm_trace(SAMPLE_GPX).expect("sample GPX should parse");
+        let pins: Vec<WasmPin> = serde_json::from_str(
+            r#"[{"name": "Life Base", "index": 3}, {"name": "Nowhere", "index": 1}]"#,
+        )
+        .unwrap();
+        let (pins, skipped) = resolve_pins(&trace, &pins);
+        assert_eq!(pins, vec![(1, Pin::Index(3))]);
+        assert_eq!(skipped, vec!["Nowhere"]);
+
+        trace.set_pins(&pins);
+        let json = serde_json::to_value(trace.route().diagnostics()).unwrap();
+        let life_base = &json["waypoints"][1];
+        assert_eq!(life_base["index"], 3);
+        assert_eq!(life_base["pinned"], true);
+        assert_eq!(json["waypoints"][0]["pinned"], false);
+        assert!(json["droppedSections"].as_array().unwrap().is_empty());
+    }
+
+    #[test]
+    fn lint_flags_a_pinned_waypoint_off_the_track() {
+        let mut trace = parse_wasm_trace(SAMPLE_GPX).expect("sample GPX should parse");
+        assert!(crate::lint::lint(&trace.route()).is_clean());
+
+        // Pinned to the Arrival's point: a long way from the Life Base marker.
+        trace.set_pins(&[(1, Pin::Index(4))]);
+        let json = serde_json::to_value(crate::lint::lint(&trace.route())).unwrap();
+        let diagnostics = json["diagnostics"].as_array().unwrap();
+        assert!(diagnostics.iter().any(|d| d["code"] == "farFromTrack"
+            && d["severity"] == "warning"
+            && d["waypoint"] == 1));
+    }
 }
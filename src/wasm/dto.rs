@@ -1,8 +1,15 @@
+use crate::calibration::history::HistoryRecalibration;
+use crate::calibration::passages::PassageRecalibration;
 use crate::calibration::Recalibration;
+use crate::cutoff::CutoffRisk;
 use crate::gpx::GpxMetadata;
 use crate::leg::LegStats;
+use crate::nutrition::SectionNutrition;
 use crate::section::SectionStats;
+use crate::sleep::SleepSuggestion;
+use crate::splits::SplitStats;
 use crate::stage::StageStats;
+use crate::surface::Surface;
 use crate::waypoint::Waypoint;
 
 // ── Serializable output types for analyzeGpx / Trace::analyze ────────────────
@@ -37,6 +44,8 @@ pub(crate) struct WasmWaypoint {
     wpt_type: Option<String>,
     time: Option<i64>,
     stop_duration: Option<u32>,
+    surface: Option<Surface>,
+    crew: bool,
 }
 
 impl From<&Waypoint> for WasmWaypoint {
@@ -49,6 +58,8 @@ impl From<&Waypoint> for WasmWaypoint {
             wpt_type: w.wpt_type.clone(),
             time: w.time,
             stop_duration: w.stop_duration,
+            surface: w.surface,
+            crew: w.crew,
         }
     }
 }
@@ -123,6 +134,16 @@ pub(crate) struct WasmSectionStats {
     max_completion_time: Option<i64>,
     cutoff_ratio: Option<f64>,
     stop_duration: Option<u32>,
+    predicted_arrival_s: f64,
+    cutoff_slack_min: Option<f64>,
+    cutoff_risk: Option<CutoffRisk>,
+    altitude_factor: f64,
+    altitude_time_s: f64,
+    darkness_time_s: f64,
+    surface_factor: f64,
+    technicality: Option<u8>,
+    sleep_s: f64,
+    nutrition: Option<SectionNutrition>,
 }
 
 impl From<SectionStats> for WasmSectionStats {
@@ -150,6 +171,16 @@ impl From<SectionStats> for WasmSectionStats {
             max_completion_time: s.max_completion_time,
             cutoff_ratio: s.cutoff_ratio,
             stop_duration: s.stop_duration,
+            predicted_arrival_s: s.predicted_arrival_s,
+            cutoff_slack_min: s.cutoff_slack_min,
+            cutoff_risk: s.cutoff_risk,
+            altitude_factor: s.altitude_factor,
+            altitude_time_s: s.altitude_time_s,
+            darkness_time_s: s.darkness_time_s,
+            surface_factor: s.surface_factor,
+            technicality: s.technicality,
+            sleep_s: s.sleep_s,
+            nutrition: s.nutrition,
         }
     }
 }
@@ -208,6 +239,60 @@ impl From<StageStats> for WasmStageStats {
     }
 }
 
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct WasmSplitStats {
+    id: u32,
+    start_index: u32,
+    end_index: u32,
+    start_distance_km: f64,
+    distance_km: f64,
+    elevation_gain_m: f64,
+    elevation_loss_m: f64,
+    avg_slope: f64,
+    pace_factor: f64,
+    estimated_duration_s: f64,
+    cumulative_duration_s: f64,
+}
+
+impl From<SplitStats> for WasmSplitStats {
+    fn from(s: SplitStats) -> Self {
+        Self {
+            id: s.split_id as u32,
+            start_index: s.start_index as u32,
+            end_index: s.end_index as u32,
+            start_distance_km: s.start_distance_km,
+            distance_km: s.distance_km,
+            elevation_gain_m: s.elevation_gain_m,
+            elevation_loss_m: s.elevation_loss_m,
+            avg_slope: s.avg_slope,
+            pace_factor: s.pace_factor,
+            estimated_duration_s: s.estimated_duration_s,
+            cumulative_duration_s: s.cumulative_duration_s,
+        }
+    }
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct WasmSleepSuggestion {
+    checkpoint: String,
+    finish_s: f64,
+    min_slack_min: Option<f64>,
+    meets_cutoffs: bool,
+}
+
+impl From<SleepSuggestion> for WasmSleepSuggestion {
+    fn from(s: SleepSuggestion) -> Self {
+        Self {
+            checkpoint: s.checkpoint,
+            finish_s: s.finish_s,
+            min_slack_min: s.min_slack_min,
+            meets_cutoffs: s.meets_cutoffs,
+        }
+    }
+}
+
 #[derive(serde::Serialize)]
 #[serde(rename_all = "camelCase")]
 pub(crate) struct WasmGpxMetadata {
@@ -295,3 +380,42 @@ impl WasmRecalibration {
         Self { sections, stages }
     }
 }
+
+// ── Output type for Trace::recalibrateFromHistory ─────────────────────────────
+
+/// History recalibration at both granularities, each fitting its own
+/// multipliers like [`WasmRecalibration`].
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct WasmHistoryRecalibration {
+    sections: Option<HistoryRecalibration>,
+    stages: Option<HistoryRecalibration>,
+}
+
+impl WasmHistoryRecalibration {
+    pub(crate) fn new(
+        sections: Option<HistoryRecalibration>,
+        stages: Option<HistoryRecalibration>,
+    ) -> Self {
+        Self { sections, stages }
+    }
+}
+
+// ── Output type for Trace::recalibrateFromPassages ────────────────────────────
+
+/// Passage recalibration at both granularities, as [`WasmRecalibration`].
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct WasmPassageRecalibration {
+    sections: Option<PassageRecalibration>,
+    stages: Option<PassageRecalibration>,
+}
+
+impl WasmPassageRecalibration {
+    pub(crate) fn new(
+        sections: Option<PassageRecalibration>,
+        stages: Option<PassageRecalibration>,
+    ) -> Self {
+        Self { sections, stages }
+    }
+}
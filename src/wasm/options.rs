@@ -1,4 +1,14 @@
+use crate::calibration::history::ETA_SPREAD_START;
+use crate::calibration::passages::Passage;
+use crate::crew::{DriveTimes, DEFAULT_SETUP_S};
+use crate::forecast::{ForecastSeries, WeatherForecast};
+use crate::gpx::TimedTrack;
+use crate::location::Location;
+use crate::nutrition::NutritionProfile;
 use crate::pace_model::{AnalysisOptions, WeatherConditions, WeatherLookup};
+use crate::route::Pin;
+use crate::sleep::{SleepBlock, SleepPlan};
+use crate::surface::{Surface, SurfaceMap};
 
 // ── Options for analyzeGpx / Trace::analyze / Trace::recalibrate ─────────────
 
@@ -10,6 +20,9 @@ struct WasmWeatherEntry {
     humidity_pct: f64,
     wind_kmh: f64,
     precip_prob_pct: f64,
+    /// Direction the wind blows from (degrees); enables the directional wind factor.
+    #[serde(default)]
+    wind_dir_deg: Option<f64>,
 }
 
 /// Shared by `WasmAnalyzeOptions` and `WasmRecalibrateOptions` — both carry an
@@ -28,12 +41,107 @@ fn weather_lookup_from(entries: &[WasmWeatherEntry]) -> WeatherLookup {
             humidity_pct: w.humidity_pct,
             wind_kmh: w.wind_kmh,
             precip_prob_pct: w.precip_prob_pct,
+            wind_dir_deg: w.wind_dir_deg,
         });
     }
 
     WeatherLookup::new(names, values)
 }
 
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct WasmForecastSample {
+    time: i64,
+    temperature_c: f64,
+    humidity_pct: f64,
+    wind_kmh: f64,
+    precip_prob_pct: f64,
+    /// Direction the wind blows from (degrees); enables the directional wind factor.
+    #[serde(default)]
+    wind_dir_deg: Option<f64>,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct WasmForecastEntry {
+    name: String,
+    samples: Vec<WasmForecastSample>,
+}
+
+/// Shared by `WasmAnalyzeOptions` and `WasmRecalibrateOptions` — the optional
+/// time-varying `forecast` array.
+fn weather_forecast_from(entries: &[WasmForecastEntry]) -> WeatherForecast {
+    if entries.is_empty() {
+        return WeatherForecast::empty();
+    }
+
+    let names = entries.iter().map(|e| e.name.clone()).collect();
+    let series = entries
+        .iter()
+        .map(|e| {
+            ForecastSeries::new(
+                e.samples
+                    .iter()
+                    .map(|s| {
+                        (
+                            s.time,
+                            WeatherConditions {
+                                temperature_c: s.temperature_c,
+                                humidity_pct: s.humidity_pct,
+                                wind_kmh: s.wind_kmh,
+                                precip_prob_pct: s.precip_prob_pct,
+                                wind_dir_deg: s.wind_dir_deg,
+                            },
+                        )
+                    })
+                    .collect(),
+            )
+        })
+        .collect();
+
+    WeatherForecast::new(names, series)
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct WasmSurfaceRange {
+    start_index: u32,
+    end_index: u32,
+    surface: Surface,
+}
+
+/// Shared by `WasmAnalyzeOptions` and `WasmRecalibrateOptions` — the optional
+/// `surfaces` array of trace index ranges.
+fn surface_map_from(entries: &[WasmSurfaceRange]) -> SurfaceMap {
+    entries.iter().fold(SurfaceMap::new(), |map, r| {
+        map.with_range(r.start_index as usize, r.end_index as usize, r.surface)
+    })
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct WasmSleepBlock {
+    /// Checkpoint name to sleep at; takes precedence over `notBefore`.
+    #[serde(default)]
+    checkpoint: Option<String>,
+    /// Sleep at the first boundary reached at or after this Unix time (s).
+    #[serde(default)]
+    not_before: Option<i64>,
+    duration_s: u32,
+}
+
+/// Shared by `WasmAnalyzeOptions` and `WasmRecalibrateOptions` — the optional
+/// `sleep` array. Blocks with neither `checkpoint` nor `notBefore` are ignored.
+fn sleep_plan_from(entries: &[WasmSleepBlock]) -> SleepPlan {
+    entries.iter().fold(SleepPlan::new(), |plan, b| {
+        match (&b.checkpoint, b.not_before) {
+            (Some(name), _) => plan.block(SleepBlock::at_checkpoint(name.clone(), b.duration_s)),
+            (None, Some(t)) => plan.block(SleepBlock::not_before(t, b.duration_s)),
+            (None, None) => plan,
+        }
+    })
+}
+
 #[derive(serde::Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub(crate) struct WasmAnalyzeOptions {
@@ -42,6 +150,23 @@ pub(crate) struct WasmAnalyzeOptions {
     life_base_stop_s: u32,
     #[serde(default)]
     weather: Vec<WasmWeatherEntry>,
+    #[serde(default)]
+    forecast: Vec<WasmForecastEntry>,
+    /// Enables the altitude factor for a runner acclimatized to this altitude (m).
+    #[serde(default)]
+    altitude_acclimatization_m: Option<f64>,
+    /// Enables the darkness factor (needs a race start time).
+    #[serde(default)]
+    darkness: bool,
+    /// Surface classes over trace index ranges; override waypoint `<surface>` tags.
+    #[serde(default)]
+    surfaces: Vec<WasmSurfaceRange>,
+    /// Planned sleep blocks.
+    #[serde(default)]
+    sleep: Vec<WasmSleepBlock>,
+    /// Runner mass and carrying capacity; enables per-section nutrition.
+    #[serde(default)]
+    nutrition: Option<NutritionProfile>,
 }
 
 impl WasmAnalyzeOptions {
@@ -51,6 +176,13 @@ impl WasmAnalyzeOptions {
             k_fatigue: self.k_fatigue,
             life_base_stop_s: self.life_base_stop_s,
             weather: weather_lookup_from(&self.weather),
+            forecast: weather_forecast_from(&self.forecast),
+            altitude_acclimatization_m: self.altitude_acclimatization_m,
+            darkness: self.darkness,
+            surfaces: surface_map_from(&self.surfaces),
+            sleep: sleep_plan_from(&self.sleep),
+            nutrition: self.nutrition,
+            ..AnalysisOptions::default()
         }
     }
 }
@@ -67,6 +199,23 @@ pub(crate) struct WasmRecalibrateOptions {
     actual_elapsed_s: f64,
     #[serde(default)]
     weather: Vec<WasmWeatherEntry>,
+    #[serde(default)]
+    forecast: Vec<WasmForecastEntry>,
+    /// Enables the altitude factor for a runner acclimatized to this altitude (m).
+    #[serde(default)]
+    altitude_acclimatization_m: Option<f64>,
+    /// Enables the darkness factor (needs a race start time).
+    #[serde(default)]
+    darkness: bool,
+    /// Surface classes over trace index ranges; override waypoint `<surface>` tags.
+    #[serde(default)]
+    surfaces: Vec<WasmSurfaceRange>,
+    /// Planned sleep blocks.
+    #[serde(default)]
+    sleep: Vec<WasmSleepBlock>,
+    /// Runner mass and carrying capacity; enables per-section nutrition.
+    #[serde(default)]
+    nutrition: Option<NutritionProfile>,
 }
 
 impl WasmRecalibrateOptions {
@@ -84,6 +233,336 @@ impl WasmRecalibrateOptions {
             k_fatigue: self.k_fatigue,
             life_base_stop_s: self.life_base_stop_s,
             weather: weather_lookup_from(&self.weather),
+            forecast: weather_forecast_from(&self.forecast),
+            altitude_acclimatization_m: self.altitude_acclimatization_m,
+            darkness: self.darkness,
+            surfaces: surface_map_from(&self.surfaces),
+            sleep: sleep_plan_from(&self.sleep),
+            nutrition: self.nutrition,
+            ..AnalysisOptions::default()
+        }
+    }
+}
+
+// ── Options for Trace::recalibrateFromHistory ─────────────────────────────────
+
+/// One recorded GPS fix.
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct WasmFix {
+    longitude: f64,
+    latitude: f64,
+    #[serde(default)]
+    altitude: f64,
+    /// Epoch s.
+    time: i64,
+}
+
+/// The analyze options plus the runner's recorded track so far.
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct WasmHistoryRecalibrateOptions {
+    #[serde(flatten)]
+    analyze: WasmAnalyzeOptions,
+    history: Vec<WasmFix>,
+}
+
+impl WasmHistoryRecalibrateOptions {
+    pub(crate) fn history(&self) -> TimedTrack {
+        TimedTrack {
+            locations: self
+                .history
+                .iter()
+                .map(|f| Location {
+                    longitude: f.longitude,
+                    latitude: f.latitude,
+                    altitude: f.altitude,
+                })
+                .collect(),
+            times: self.history.iter().map(|f| Some(f.time)).collect(),
+        }
+    }
+
+    pub(crate) fn to_analysis_options(&self) -> AnalysisOptions {
+        self.analyze.to_analysis_options()
+    }
+}
+
+// ── Options for Trace::recalibrateFromPassages ────────────────────────────────
+
+/// The analyze options plus timing-mat passages.
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct WasmPassagesRecalibrateOptions {
+    #[serde(flatten)]
+    analyze: WasmAnalyzeOptions,
+    passages: Vec<Passage>,
+}
+
+impl WasmPassagesRecalibrateOptions {
+    pub(crate) fn passages(&self) -> &[Passage] {
+        &self.passages
+    }
+
+    pub(crate) fn to_analysis_options(&self) -> AnalysisOptions {
+        self.analyze.to_analysis_options()
+    }
+}
+
+// ── Options for Trace::crewPlan ───────────────────────────────────────────────
+
+/// Drive-time matrix between crew points.
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct WasmDriveTimes {
+    points: Vec<String>,
+    /// `seconds[i][j]` drives from `points[i]` to `points[j]`; `null` when no road.
+    seconds: Vec<Vec<Option<f64>>>,
+}
+
+/// The analyze options plus what the crew planner needs.
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct WasmCrewOptions {
+    #[serde(flatten)]
+    analyze: WasmAnalyzeOptions,
+    /// Race start (epoch s); falls back to the first waypoint's `time`.
+    #[serde(default)]
+    start_time: Option<i64>,
+    /// Waypoint names to treat as crew-accessible, on top of `<crew>` flags.
+    #[serde(default)]
+    crew_points: Vec<String>,
+    drive_times: WasmDriveTimes,
+    #[serde(default)]
+    setup_s: Option<f64>,
+    /// Arrival window either side of the prediction, as a fraction.
+    #[serde(default)]
+    spread: Option<f64>,
+}
+
+impl WasmCrewOptions {
+    pub(crate) fn start_time(&self) -> Option<i64> {
+        self.start_time
+    }
+
+    pub(crate) fn crew_points(&self) -> &[String] {
+        &self.crew_points
+    }
+
+    pub(crate) fn drive_times(&self) -> DriveTimes {
+        DriveTimes::new(
+            self.drive_times.points.clone(),
+            self.drive_times.seconds.clone(),
+        )
+    }
+
+    pub(crate) fn setup_s(&self) -> f64 {
+        self.setup_s.unwrap_or(DEFAULT_SETUP_S)
+    }
+
+    pub(crate) fn spread(&self) -> f64 {
+        self.spread.unwrap_or(ETA_SPREAD_START)
+    }
+
+    pub(crate) fn to_analysis_options(&self) -> AnalysisOptions {
+        self.analyze.to_analysis_options()
+    }
+}
+
+// ── Options for Trace::pinWaypoints ───────────────────────────────────────────
+
+/// One waypoint placed on the trace by hand: `index` wins over `distanceKm`.
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct WasmPin {
+    name: String,
+    #[serde(default)]
+    index: Option<usize>,
+    #[serde(default)]
+    distance_km: Option<f64>,
+}
+
+impl WasmPin {
+    pub(crate) fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// `None` when neither `index` nor `distanceKm` is set.
+    pub(crate) fn pin(&self) -> Option<Pin> {
+        self.index
+            .map(Pin::Index)
+            .or(self.distance_km.map(Pin::DistanceKm))
+    }
+}
+
+// ── Options for Trace::splits ─────────────────────────────────────────────────
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct WasmSplitsOptions {
+    base_pace_s_per_km: f64,
+    k_fatigue: f64,
+    split_km: f64,
+    /// Race start (epoch s); falls back to the first waypoint's `time`.
+    #[serde(default)]
+    start_time: Option<i64>,
+}
+
+impl WasmSplitsOptions {
+    pub(crate) fn split_km(&self) -> f64 {
+        self.split_km
+    }
+
+    pub(crate) fn start_time(&self) -> Option<i64> {
+        self.start_time
+    }
+
+    pub(crate) fn to_analysis_options(&self) -> AnalysisOptions {
+        AnalysisOptions::default()
+            .base_pace(self.base_pace_s_per_km)
+            .fatigue(self.k_fatigue)
+    }
+}
+
+// ── Options for Trace::schedule ───────────────────────────────────────────────
+
+/// The analyze options plus a race start.
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct WasmScheduleOptions {
+    #[serde(flatten)]
+    analyze: WasmAnalyzeOptions,
+    /// Race start (epoch s); falls back to the first waypoint's `time`.
+    #[serde(default)]
+    start_time: Option<i64>,
+}
+
+impl WasmScheduleOptions {
+    pub(crate) fn start_time(&self) -> Option<i64> {
+        self.start_time
+    }
+
+    pub(crate) fn to_analysis_options(&self) -> AnalysisOptions {
+        self.analyze.to_analysis_options()
+    }
+}
+
+// ── Options for Trace::timeline ───────────────────────────────────────────────
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct WasmTimelineOptions {
+    base_pace_s_per_km: f64,
+    k_fatigue: f64,
+    /// Race start (epoch s); falls back to the first waypoint's `time`.
+    #[serde(default)]
+    start_time: Option<i64>,
+}
+
+impl WasmTimelineOptions {
+    pub(crate) fn start_time(&self) -> Option<i64> {
+        self.start_time
+    }
+
+    pub(crate) fn to_analysis_options(&self) -> AnalysisOptions {
+        AnalysisOptions::default()
+            .base_pace(self.base_pace_s_per_km)
+            .fatigue(self.k_fatigue)
+    }
+}
+
+#[cfg(test)]
+impl WasmSplitsOptions {
+    /// Used by `wasm.rs`'s pipeline tests.
+    pub(crate) fn sample(split_km: f64) -> Self {
+        Self {
+            base_pace_s_per_km: 500.0,
+            k_fatigue: 0.002,
+            split_km,
+            start_time: None,
+        }
+    }
+}
+
+#[cfg(test)]
+impl WasmScheduleOptions {
+    /// Used by `wasm.rs`'s pipeline tests.
+    pub(crate) fn sample(start_time: Option<i64>) -> Self {
+        Self {
+            analyze: WasmAnalyzeOptions::sample(),
+            start_time,
+        }
+    }
+}
+
+#[cfg(test)]
+impl WasmHistoryRecalibrateOptions {
+    /// Used by `wasm.rs`'s pipeline tests: one fix per `(longitude, latitude, time)`.
+    pub(crate) fn sample(fixes: &[(f64, f64, i64)]) -> Self {
+        Self {
+            analyze: WasmAnalyzeOptions::sample(),
+            history: fixes
+                .iter()
+                .map(|&(longitude, latitude, time)| WasmFix {
+                    longitude,
+                    latitude,
+                    altitude: 0.0,
+                    time,
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+impl WasmPassagesRecalibrateOptions {
+    /// Used by `wasm.rs`'s pipeline tests.
+    pub(crate) fn sample(passages: Vec<Passage>) -> Self {
+        Self {
+            analyze: WasmAnalyzeOptions::sample(),
+            passages,
+        }
+    }
+}
+
+#[cfg(test)]
+impl WasmCrewOptions {
+    /// Used by `wasm.rs`'s pipeline tests: drive times as `(from, to, seconds)`.
+    pub(crate) fn sample(crew_points: &[&str], drives: &[(&str, &str, f64)]) -> Self {
+        let points: Vec<String> = crew_points.iter().map(|p| p.to_string()).collect();
+        let seconds = points
+            .iter()
+            .map(|from| {
+                points
+                    .iter()
+                    .map(|to| {
+                        drives
+                            .iter()
+                            .find(|(f, t, _)| f == from && t == to)
+                            .map(|&(_, _, s)| s)
+                    })
+                    .collect()
+            })
+            .collect();
+        Self {
+            analyze: WasmAnalyzeOptions::sample(),
+            start_time: None,
+            crew_points: points.clone(),
+            drive_times: WasmDriveTimes { points, seconds },
+            setup_s: None,
+            spread: None,
+        }
+    }
+}
+
+#[cfg(test)]
+impl WasmTimelineOptions {
+    /// Used by `wasm.rs`'s pipeline tests.
+    pub(crate) fn sample() -> Self {
+        Self {
+            base_pace_s_per_km: 500.0,
+            k_fatigue: 0.002,
+            start_time: None,
         }
     }
 }
@@ -98,6 +577,12 @@ impl WasmAnalyzeOptions {
             k_fatigue: 0.002,
             life_base_stop_s: 3600,
             weather: Vec::new(),
+            forecast: Vec::new(),
+            altitude_acclimatization_m: None,
+            darkness: false,
+            surfaces: Vec::new(),
+            sleep: Vec::new(),
+            nutrition: None,
         }
     }
 }
@@ -114,6 +599,12 @@ impl WasmRecalibrateOptions {
             current_index,
             actual_elapsed_s,
             weather: Vec::new(),
+            forecast: Vec::new(),
+            altitude_acclimatization_m: None,
+            darkness: false,
+            surfaces: Vec::new(),
+            sleep: Vec::new(),
+            nutrition: None,
         }
     }
 }
@@ -129,6 +620,12 @@ mod tests {
             k_fatigue: 0.002,
             life_base_stop_s: 3600,
             weather: Vec::new(),
+            forecast: Vec::new(),
+            altitude_acclimatization_m: None,
+            darkness: false,
+            surfaces: Vec::new(),
+            sleep: Vec::new(),
+            nutrition: None,
         };
         assert!((options.to_analysis_options().weather.factor_for("anything") - 1.0).abs() < 1e-9);
     }
@@ -145,10 +642,183 @@ mod tests {
                 humidity_pct: 85.0,
                 wind_kmh: 35.0,
                 precip_prob_pct: 80.0,
+                wind_dir_deg: None,
             }],
+            forecast: Vec::new(),
+            altitude_acclimatization_m: None,
+            darkness: false,
+            surfaces: Vec::new(),
+            sleep: Vec::new(),
+            nutrition: None,
         };
         let lookup = &options.to_analysis_options().weather;
         assert!(lookup.factor_for("Chamonix") > 1.0);
         assert!((lookup.factor_for("Unknown") - 1.0).abs() < 1e-9);
     }
+
+    #[test]
+    fn forecast_entries_convert_to_series_by_name() {
+        let options = WasmAnalyzeOptions {
+            base_pace_s_per_km: 500.0,
+            k_fatigue: 0.002,
+            life_base_stop_s: 3600,
+            weather: Vec::new(),
+            forecast: vec![WasmForecastEntry {
+                name: "Chamonix".into(),
+                samples: vec![
+                    WasmForecastSample {
+                        time: 3600,
+                        temperature_c: 20.0,
+                        humidity_pct: 50.0,
+                        wind_kmh: 0.0,
+                        precip_prob_pct: 0.0,
+                        wind_dir_deg: None,
+                    },
+                    WasmForecastSample {
+                        time: 0,
+                        temperature_c: 10.0,
+                        humidity_pct: 50.0,
+                        wind_kmh: 0.0,
+                        precip_prob_pct: 0.0,
+                        wind_dir_deg: None,
+                    },
+                ],
+            }],
+            altitude_acclimatization_m: None,
+            darkness: false,
+            surfaces: Vec::new(),
+            sleep: Vec::new(),
+            nutrition: None,
+        };
+        let forecast = options.to_analysis_options().forecast;
+        let series = forecast.find("Chamonix").unwrap();
+        assert!((series.at(1800).unwrap().temperature_c - 15.0).abs() < 1e-9);
+        assert!(forecast.find("Unknown").is_none());
+    }
+
+    #[test]
+    fn surface_ranges_convert_to_map() {
+        let options: WasmAnalyzeOptions = serde_json::from_str(
+            r#"{"basePaceSPerKm": 500, "kFatigue": 0.002, "lifeBaseStopS": 3600,
+                "surfaces": [{"startIndex": 2, "endIndex": 5, "surface": "rocky"}]}"#,
+        )
+        .unwrap();
+        let surfaces = options.to_analysis_options().surfaces;
+        assert_eq!(surfaces.surface_at(1), None);
+        assert_eq!(surfaces.surface_at(2), Some(Surface::Rocky));
+        assert!(serde_json::from_str::<WasmAnalyzeOptions>(
+            r#"{"basePaceSPerKm": 500, "kFatigue": 0.002, "lifeBaseStopS": 3600,
+                "surfaces": [{"startIndex": 0, "endIndex": 1, "surface": "swamp"}]}"#,
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn sleep_blocks_convert_to_plan() {
+        let options: WasmAnalyzeOptions = serde_json::from_str(
+            r#"{"basePaceSPerKm": 500, "kFatigue": 0.002, "lifeBaseStopS": 3600,
+                "sleep": [{"checkpoint": "Base", "durationS": 3600},
+                          {"notBefore": 1700000000, "durationS": 1800},
+                          {"durationS": 900}]}"#,
+        )
+        .unwrap();
+        let plan = options.to_analysis_options().sleep;
+        assert_eq!(
+            plan.blocks(),
+            &[
+                SleepBlock::at_checkpoint("Base", 3600),
+                SleepBlock::not_before(1_700_000_000, 1800),
+            ]
+        );
+    }
+
+    #[test]
+    fn nutrition_profile_fills_defaults() {
+        let options: WasmAnalyzeOptions = serde_json::from_str(
+            r#"{"basePaceSPerKm": 500, "kFatigue": 0.002, "lifeBaseStopS": 3600,
+                "nutrition": {"massKg": 62, "fluidCapacityL": 2}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            options.to_analysis_options().nutrition,
+            Some(NutritionProfile::new(62.0).fluid_capacity(2.0))
+        );
+    }
+
+    #[test]
+    fn schedule_options_flatten_analyze_options() {
+        let options: WasmScheduleOptions = serde_json::from_str(
+            r#"{"basePaceSPerKm": 420, "kFatigue": 0.002, "lifeBaseStopS": 1800,
+                "darkness": true, "startTime": 1700000000}"#,
+        )
+        .unwrap();
+        assert_eq!(options.start_time(), Some(1_700_000_000));
+        let analysis = options.to_analysis_options();
+        assert_eq!(analysis.base_pace_s_per_km, 420.0);
+        assert!(analysis.darkness);
+    }
+
+    #[test]
+    fn history_fixes_convert_to_a_timed_track() {
+        let options: WasmHistoryRecalibrateOptions = serde_json::from_str(
+            r#"{"basePaceSPerKm": 420, "kFatigue": 0.002, "lifeBaseStopS": 1800,
+                "history": [{"longitude": 7.0, "latitude": 45.0, "time": 1700000000},
+                            {"longitude": 7.01, "latitude": 45.01, "altitude": 1100,
+                             "time": 1700000600}]}"#,
+        )
+        .unwrap();
+        let track = options.history();
+        assert_eq!(track.locations.len(), 2);
+        assert_eq!(track.locations[1].altitude, 1100.0);
+        assert_eq!(track.times, vec![Some(1_700_000_000), Some(1_700_000_600)]);
+        assert_eq!(options.to_analysis_options().base_pace_s_per_km, 420.0);
+    }
+
+    #[test]
+    fn passages_deserialize_by_name_and_time() {
+        let options: WasmPassagesRecalibrateOptions = serde_json::from_str(
+            r#"{"basePaceSPerKm": 420, "kFatigue": 0.002, "lifeBaseStopS": 1800,
+                "passages": [{"name": "Start", "time": 1700000000},
+                             {"name": "Life Base", "time": 1700007200}]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            options.passages(),
+            [
+                Passage::new("Start", 1_700_000_000),
+                Passage::new("Life Base", 1_700_007_200),
+            ]
+        );
+    }
+
+    #[test]
+    fn crew_options_default_setup_and_spread() {
+        let options: WasmCrewOptions = serde_json::from_str(
+            r#"{"basePaceSPerKm": 420, "kFatigue": 0.002, "lifeBaseStopS": 1800,
+                "crewPoints": ["Life Base"],
+                "driveTimes": {"points": ["Life Base", "Arrival"],
+                               "seconds": [[0, 1200], [null, 0]]}}"#,
+        )
+        .unwrap();
+        assert_eq!(options.crew_points(), ["Life Base"]);
+        assert_eq!(options.setup_s(), DEFAULT_SETUP_S);
+        assert_eq!(options.spread(), ETA_SPREAD_START);
+        let drives = options.drive_times();
+        assert_eq!(drives.between("Life Base", "Arrival"), Some(1200.0));
+        assert_eq!(drives.between("Arrival", "Life Base"), None);
+    }
+
+    #[test]
+    fn pins_prefer_the_index() {
+        let pins: Vec<WasmPin> = serde_json::from_str(
+            r#"[{"name": "Bridge", "index": 30, "distanceKm": 3.3},
+                {"name": "Col", "distanceKm": 12.5},
+                {"name": "Nowhere"}]"#,
+        )
+        .unwrap();
+        assert_eq!(pins[0].name(), "Bridge");
+        assert_eq!(pins[0].pin(), Some(Pin::Index(30)));
+        assert_eq!(pins[1].pin(), Some(Pin::DistanceKm(12.5)));
+        assert_eq!(pins[2].pin(), None);
+    }
 }
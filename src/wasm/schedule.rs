@@ -0,0 +1,52 @@
+use wasm_bindgen::prelude::*;
+
+/// Opaque handle to a predicted arrival schedule (see `Trace.schedule()`).
+/// Times are epoch seconds as plain numbers. Call `.free()` when done.
+#[wasm_bindgen]
+pub struct Schedule {
+    inner: crate::schedule::ArrivalSchedule,
+}
+
+impl Schedule {
+    pub(crate) fn new(inner: crate::schedule::ArrivalSchedule) -> Self {
+        Self { inner }
+    }
+}
+
+#[wasm_bindgen]
+impl Schedule {
+    #[wasm_bindgen(getter, js_name = "finishTime")]
+    pub fn finish_time(&self) -> f64 {
+        self.inner.finish_time() as f64
+    }
+
+    /// Predicted arrival at each trace point, before any stop there, as `Float64Array`.
+    #[wasm_bindgen(js_name = "getArrivalTimes")]
+    pub fn arrival_times(&self) -> Vec<f64> {
+        let t0 = self.inner.start_time as f64;
+        self.inner.arrival_s.iter().map(|t| t0 + t).collect()
+    }
+
+    /// Predicted departure from each trace point (after checkpoint stops), as `Float64Array`.
+    #[wasm_bindgen(js_name = "getDepartureTimes")]
+    pub fn departure_times(&self) -> Vec<f64> {
+        let t0 = self.inner.start_time as f64;
+        self.inner.departure_s.iter().map(|t| t0 + t).collect()
+    }
+
+    /// `{ index, distanceKm, location: { longitude, latitude, altitude }, stopped }`
+    /// at `time`, or `undefined` before the start or after the finish.
+    #[wasm_bindgen(js_name = "positionAt")]
+    pub fn position_at(&self, time: f64) -> JsValue {
+        match self.inner.position_at(time as i64) {
+            Some(pos) => serde_wasm_bindgen::to_value(&pos).unwrap_or(JsValue::UNDEFINED),
+            None => JsValue::UNDEFINED,
+        }
+    }
+
+    /// Predicted arrival at `distanceKm` from the start, or `undefined` outside the trace.
+    #[wasm_bindgen(js_name = "timeAtDistance")]
+    pub fn time_at_distance(&self, distance_km: f64) -> Option<f64> {
+        self.inner.time_at_distance(distance_km).map(|t| t as f64)
+    }
+}
@@ -0,0 +1,77 @@
+use wasm_bindgen::prelude::*;
+
+/// Opaque handle to a per-point pace-model timeline (see `Trace.timeline()`).
+///
+/// Every accessor copies one column out as a `Float64Array` the same length
+/// as the trace's locations. Call `.free()` when done.
+#[wasm_bindgen]
+pub struct Timeline {
+    inner: crate::timeline::Timeline,
+}
+
+impl Timeline {
+    pub(crate) fn new(inner: crate::timeline::Timeline) -> Self {
+        Self { inner }
+    }
+}
+
+#[wasm_bindgen]
+impl Timeline {
+    #[wasm_bindgen(getter)]
+    pub fn length(&self) -> u32 {
+        self.inner.len() as u32
+    }
+
+    /// Cumulative effort-weighted distance, km (`d_eff`).
+    #[wasm_bindgen(js_name = "getEffortDistances")]
+    pub fn effort_distances(&self) -> Vec<f64> {
+        self.inner.effort_distance_km.clone()
+    }
+
+    /// Combined pace factor of the segment starting at each point.
+    #[wasm_bindgen(js_name = "getPaceFactors")]
+    pub fn pace_factors(&self) -> Vec<f64> {
+        self.inner.pace_factor.clone()
+    }
+
+    #[wasm_bindgen(js_name = "getTerrainFactors")]
+    pub fn terrain_factors(&self) -> Vec<f64> {
+        self.inner.terrain_factor.clone()
+    }
+
+    #[wasm_bindgen(js_name = "getFatigueFactors")]
+    pub fn fatigue_factors(&self) -> Vec<f64> {
+        self.inner.fatigue_factor.clone()
+    }
+
+    #[wasm_bindgen(js_name = "getCircadianFactors")]
+    pub fn circadian_factors(&self) -> Vec<f64> {
+        self.inner.circadian_factor.clone()
+    }
+
+    #[wasm_bindgen(js_name = "getWeatherFactors")]
+    pub fn weather_factors(&self) -> Vec<f64> {
+        self.inner.weather_factor.clone()
+    }
+
+    #[wasm_bindgen(js_name = "getAltitudeFactors")]
+    pub fn altitude_factors(&self) -> Vec<f64> {
+        self.inner.altitude_factor.clone()
+    }
+
+    #[wasm_bindgen(js_name = "getDarknessFactors")]
+    pub fn darkness_factors(&self) -> Vec<f64> {
+        self.inner.darkness_factor.clone()
+    }
+
+    #[wasm_bindgen(js_name = "getSurfaceFactors")]
+    pub fn surface_factors(&self) -> Vec<f64> {
+        self.inner.surface_factor.clone()
+    }
+
+    /// Predicted cumulative moving time, seconds.
+    #[wasm_bindgen(js_name = "getCumulativeTimes")]
+    pub fn cumulative_times(&self) -> Vec<f64> {
+        self.inner.cumulative_time_s.clone()
+    }
+}
@@ -1,8 +1,14 @@
 use wasm_bindgen::prelude::*;
 
-use crate::Location;
+use crate::{Gait, Location};
 
-use super::options::{WasmAnalyzeOptions, WasmRecalibrateOptions};
+use super::options::{
+    WasmAnalyzeOptions, WasmCrewOptions, WasmHistoryRecalibrateOptions,
+    WasmPassagesRecalibrateOptions, WasmPin, WasmRecalibrateOptions, WasmScheduleOptions,
+    WasmSplitsOptions, WasmTimelineOptions,
+};
+use super::schedule::Schedule;
+use super::timeline::Timeline;
 
 // ── Trace class ───────────────────────────────────────────────────────────
 
@@ -21,6 +27,8 @@ pub struct Trace {
     inner: crate::trace::Trace,
     waypoints: Vec<crate::waypoint::Waypoint>,
     metadata: crate::gpx::GpxMetadata,
+    /// Waypoints resolved onto `inner` once, for every analysis call.
+    route: crate::route::RouteIndex,
 }
 
 // Plain (non-wasm_bindgen) constructor and accessors used internally by
@@ -32,10 +40,12 @@ impl Trace {
         waypoints: Vec<crate::waypoint::Waypoint>,
         metadata: crate::gpx::GpxMetadata,
     ) -> Self {
+        let route = crate::route::RouteIndex::new(&inner, &waypoints, &[]);
         Self {
             inner,
             waypoints,
             metadata,
+            route,
         }
     }
 
@@ -50,6 +60,15 @@ impl Trace {
     pub(crate) fn metadata(&self) -> &crate::gpx::GpxMetadata {
         &self.metadata
     }
+
+    /// Re-resolve the waypoints with `pins`, replacing any earlier ones.
+    pub(crate) fn set_pins(&mut self, pins: &[(usize, crate::route::Pin)]) {
+        self.route = crate::route::RouteIndex::new(&self.inner, &self.waypoints, pins);
+    }
+
+    pub(crate) fn route(&self) -> crate::route::ResolvedRoute<'_> {
+        crate::route::ResolvedRoute::with_index(&self.inner, &self.waypoints, &self.route)
+    }
 }
 
 #[wasm_bindgen]
@@ -122,6 +141,23 @@ impl Trace {
         self.inner.valleys.iter().map(|&i| i as u32).collect()
     }
 
+    /// Cumulative metabolic energy (kJ) at each point for a runner of
+    /// `mass_kg`, as `Float64Array`. `gait` is `"running"` or `"walking"`;
+    /// returns `undefined` for an unknown gait.
+    #[wasm_bindgen(js_name = "getCumulativeEnergyKj")]
+    pub fn cumulative_energy_kj(&self, mass_kg: f64, gait: &str) -> Option<Vec<f64>> {
+        let gait: Gait = gait.parse().ok()?;
+        Some(self.inner.energy_curve(mass_kg, gait).cumulative_kj)
+    }
+
+    /// Cumulative equivalent flat distance (km) at each point, as
+    /// `Float64Array` — the effort-weighted distance under `gait`'s cost curve.
+    #[wasm_bindgen(js_name = "getEquivalentFlatDistances")]
+    pub fn equivalent_flat_distances(&self, gait: &str) -> Option<Vec<f64>> {
+        let gait: Gait = gait.parse().ok()?;
+        Some(self.inner.energy_curve(0.0, gait).cumulative_flat_km)
+    }
+
     // ── Query methods — scalars in, cheap crossing; complex results via serde ──
 
     /// Index of the first location at or beyond `dist_km`.
@@ -232,6 +268,76 @@ impl Trace {
             .ok()
     }
 
+    /// Fixed-distance split table with model-predicted times.
+    ///
+    /// `options` — a JS object: `{ basePaceSPerKm, kFatigue, splitKm,
+    /// startTime? }`. Pass `splitKm: 1.609344` for mile splits. `startTime`
+    /// (epoch s) drives the circadian factor and defaults to the first
+    /// waypoint's time.
+    ///
+    /// Returns an array of `{ id, startIndex, endIndex, startDistanceKm,
+    /// distanceKm, elevationGainM, elevationLossM, avgSlope, paceFactor,
+    /// estimatedDurationS, cumulativeDurationS }`, or `null` when `options`
+    /// is malformed.
+    pub fn splits(&self, options: JsValue) -> Option<JsValue> {
+        let options: WasmSplitsOptions = serde_wasm_bindgen::from_value(options)
+            .map_err(|e| super::warn(&format!("navigo: splits() options error: {e}")))
+            .ok()?;
+        let splits = super::compute_splits(self, &options);
+        serde_wasm_bindgen::to_value(&splits)
+            .map_err(|e| super::warn(&format!("navigo: splits() serialization error: {e}")))
+            .ok()
+    }
+
+    /// Per-point pace-model timeline: cumulative effort distance, combined
+    /// pace factor with its terrain / fatigue / circadian / weather breakdown,
+    /// and cumulative predicted time, each as a `Float64Array` accessor.
+    ///
+    /// `options` — a JS object: `{ basePaceSPerKm, kFatigue, startTime? }`.
+    /// `startTime` (epoch s) drives the circadian factor and defaults to the
+    /// first waypoint's time. Weather is neutral and no stops apply.
+    ///
+    /// Returns a `Timeline` handle (call `.free()` when done), or `undefined`
+    /// when `options` is malformed.
+    pub fn timeline(&self, options: JsValue) -> Option<Timeline> {
+        let options: WasmTimelineOptions = serde_wasm_bindgen::from_value(options)
+            .map_err(|e| super::warn(&format!("navigo: timeline() options error: {e}")))
+            .ok()?;
+        Some(super::compute_timeline(self, &options))
+    }
+
+    /// Predicted clock time at every trace point, including checkpoint stops
+    /// and sleep, with `positionAt(time)` / `timeAtDistance(km)` lookups.
+    ///
+    /// `options` — the `.analyze()` options plus `startTime?` (epoch s,
+    /// defaults to the first waypoint's time).
+    ///
+    /// Returns a `Schedule` handle (call `.free()` when done), or `undefined`
+    /// when `options` is malformed or no start time is known.
+    pub fn schedule(&self, options: JsValue) -> Option<Schedule> {
+        let options: WasmScheduleOptions = serde_wasm_bindgen::from_value(options)
+            .map_err(|e| super::warn(&format!("navigo: schedule() options error: {e}")))
+            .ok()?;
+        super::compute_schedule(self, &options)
+    }
+
+    /// Rank the intermediate checkpoints as places to add a sleep of
+    /// `durationS` seconds to the plan in `options` (the same object as
+    /// `.analyze()`, including any `sleep` blocks already planned).
+    ///
+    /// Returns an array of `{ checkpoint, finishS, minSlackMin, meetsCutoffs }`,
+    /// best first, or `null` when `options` is malformed.
+    #[wasm_bindgen(js_name = "suggestSleep")]
+    pub fn suggest_sleep(&self, options: JsValue, duration_s: u32) -> Option<JsValue> {
+        let options: WasmAnalyzeOptions = serde_wasm_bindgen::from_value(options)
+            .map_err(|e| super::warn(&format!("navigo: suggestSleep() options error: {e}")))
+            .ok()?;
+        let suggestions = super::compute_sleep_suggestions(self, &options, duration_s);
+        serde_wasm_bindgen::to_value(&suggestions)
+            .map_err(|e| super::warn(&format!("navigo: suggestSleep() serialization error: {e}")))
+            .ok()
+    }
+
     /// Live, mid-race ETA recalibration — corrects the static `.analyze()`
     /// prediction against the runner's actual progress so far.
     ///
@@ -240,7 +346,7 @@ impl Trace {
     /// to stay lean, so `sections`/`stages` would always come back `null`).
     ///
     /// `options` — a JS object: `{ basePaceSPerKm, kFatigue, lifeBaseStopS,
-    /// currentIndex, actualElapsedS, weather? }`. `currentIndex` is the
+    /// currentIndex, actualElapsedS, weather?, forecast?, altitudeAcclimatizationM?, darkness?, surfaces?, sleep?, nutrition? }`. `currentIndex` is the
     /// runner's current point on this trace (e.g. from `find_closest_point`);
     /// `actualElapsedS` is real seconds elapsed since race start.
     ///
@@ -258,6 +364,141 @@ impl Trace {
             .map_err(|e| super::warn(&format!("navigo: recalibrate() serialization error: {e}")))
             .ok()
     }
+
+    /// Recalibration from the runner's recorded track rather than one
+    /// elapsed time: fits separate downhill / flat / uphill multipliers,
+    /// weighting recent stretches more, and reports each ETA with a band
+    /// that narrows as the race progresses.
+    ///
+    /// `options` — the `.analyze()` options plus `history: [{ longitude,
+    /// latitude, altitude?, time }, …]` (epoch s), oldest first. Needs
+    /// waypoints, like `.recalibrate()`.
+    ///
+    /// Returns `{ sections, stages }`, each either `null` (fewer than 2
+    /// boundaries of that kind, or no fix on the trace) or `{ multipliers:
+    /// { downhill, flat, uphill }, currentIndex, actualElapsedS,
+    /// predictedSoFarS, progress, etaSpread, etas: [{ …recalibrated ETA,
+    /// earliestRemainingS, latestRemainingS }, …] }`. Returns `null` when
+    /// `options` is malformed.
+    #[wasm_bindgen(js_name = "recalibrateFromHistory")]
+    pub fn recalibrate_from_history(&self, options: JsValue) -> Option<JsValue> {
+        let options: WasmHistoryRecalibrateOptions = serde_wasm_bindgen::from_value(options)
+            .map_err(|e| {
+                super::warn(&format!(
+                    "navigo: recalibrateFromHistory() options error: {e}"
+                ))
+            })
+            .ok()?;
+        let recalibration = super::compute_history_recalibration(self, &options);
+        serde_wasm_bindgen::to_value(&recalibration)
+            .map_err(|e| {
+                super::warn(&format!(
+                    "navigo: recalibrateFromHistory() serialization error: {e}"
+                ))
+            })
+            .ok()
+    }
+
+    /// Recalibration from official timing-mat passages instead of a GPS
+    /// index: actual section durations and LifeBase dwell times drive the
+    /// calibration factor.
+    ///
+    /// `options` — the `.analyze()` options plus `passages: [{ name, time },
+    /// …]` (waypoint name, epoch s). Two passages at one checkpoint are mat in
+    /// and mat out. Needs waypoints, like `.recalibrate()`.
+    ///
+    /// Returns `{ sections, stages }`, each either `null` (fewer than 2
+    /// boundaries of that kind, or no start time) or the `.recalibrate()`
+    /// shape plus `sections: [{ id, durationS, dwellS }, …]` for the
+    /// intervals already run. Returns `null` when `options` is malformed.
+    #[wasm_bindgen(js_name = "recalibrateFromPassages")]
+    pub fn recalibrate_from_passages(&self, options: JsValue) -> Option<JsValue> {
+        let options: WasmPassagesRecalibrateOptions = serde_wasm_bindgen::from_value(options)
+            .map_err(|e| {
+                super::warn(&format!(
+                    "navigo: recalibrateFromPassages() options error: {e}"
+                ))
+            })
+            .ok()?;
+        let recalibration = super::compute_passage_recalibration(self, &options);
+        serde_wasm_bindgen::to_value(&recalibration)
+            .map_err(|e| {
+                super::warn(&format!(
+                    "navigo: recalibrateFromPassages() serialization error: {e}"
+                ))
+            })
+            .ok()
+    }
+
+    /// Crew itinerary over the crew-accessible checkpoints: when to be in
+    /// place at each and whether the drive to the next beats the runner.
+    ///
+    /// `options` — the `.analyze()` options plus `driveTimes: { points,
+    /// seconds }` (`seconds[i][j]` from `points[i]` to `points[j]`, `null`
+    /// when no road), `crewPoints?` (names to treat as crew points besides
+    /// `<crew>true</crew>` waypoints), `startTime?` (epoch s, defaults to the
+    /// first waypoint's time), `setupS?` (default 900) and `spread?` (arrival
+    /// window either side of the prediction, default 0.25).
+    ///
+    /// Returns `{ stops: [{ name, beThereBy, earliestArrival, likelyArrival,
+    /// latestArrival, likelyDeparture, latestDeparture }, …], transfers:
+    /// [{ from, to, driveS, slackS, risk }, …] }` with epoch-s times and
+    /// `risk` one of `"safe"`, `"tight"`, `"impossible"`; or `null` when
+    /// `options` is malformed, no start time is known or there are no sections.
+    #[wasm_bindgen(js_name = "crewPlan")]
+    pub fn crew_plan(&self, options: JsValue) -> Option<JsValue> {
+        let options: WasmCrewOptions = serde_wasm_bindgen::from_value(options)
+            .map_err(|e| super::warn(&format!("navigo: crewPlan() options error: {e}")))
+            .ok()?;
+        let plan = super::compute_crew_plan(self, &options)?;
+        serde_wasm_bindgen::to_value(&plan)
+            .map_err(|e| super::warn(&format!("navigo: crewPlan() serialization error: {e}")))
+            .ok()
+    }
+
+    /// How each waypoint snapped onto the track: `{ waypoints: [{ waypoint,
+    /// name, index, snapDistanceKm, pinned, passes, ambiguous, outOfOrder },
+    /// …], droppedLegs, droppedSections, droppedStages }`. `ambiguous` marks a
+    /// waypoint the track passes more than once (loops, out-and-backs);
+    /// `dropped*` list the ids of intervals missing from `.analyze()`.
+    #[wasm_bindgen(js_name = "snapReport")]
+    pub fn snap_report(&self) -> Option<JsValue> {
+        serde_wasm_bindgen::to_value(&self.route().diagnostics())
+            .map_err(|e| super::warn(&format!("navigo: snapReport() serialization error: {e}")))
+            .ok()
+    }
+
+    /// Place waypoints on the track by hand instead of snapping them:
+    /// `pins` — `[{ name, index?, distanceKm? }, …]` (trace index, or km from
+    /// the start; `index` wins). Replaces earlier pins and applies to every
+    /// later analysis on this trace; `[]` clears them. Unknown names are
+    /// skipped with a console warning.
+    ///
+    /// Returns the `.snapReport()` after pinning, or `null` when `pins` is malformed.
+    #[wasm_bindgen(js_name = "pinWaypoints")]
+    pub fn pin_waypoints(&mut self, pins: JsValue) -> Option<JsValue> {
+        let pins: Vec<WasmPin> = serde_wasm_bindgen::from_value(pins)
+            .map_err(|e| super::warn(&format!("navigo: pinWaypoints() pins error: {e}")))
+            .ok()?;
+        let (resolved, skipped) = super::resolve_pins(self, &pins);
+        for name in skipped {
+            super::warn(&format!("navigo: pinWaypoints() skipped \"{name}\""));
+        }
+        self.set_pins(&resolved);
+        self.snap_report()
+    }
+
+    /// Validation report for organisers: `{ diagnostics: [{ severity:
+    /// "error" | "warning", code, waypoint, sectionId, message }, …] }`,
+    /// errors first. Codes: `missingStart`, `missingArrival`, `unknownType`,
+    /// `timeNotIncreasing`, `stopOnNonLifeBase`, `duplicateName`,
+    /// `farFromTrack`, `unplaced`, `infeasibleCutoff` (under default options).
+    #[wasm_bindgen(js_name = "lint")]
+    pub fn lint(&self) -> Option<JsValue> {
+        serde_wasm_bindgen::to_value(&crate::lint::lint(&self.route()))
+            .map_err(|e| super::warn(&format!("navigo: lint() serialization error: {e}")))
+            .ok()
+    }
 }
 
 // ── Private helpers ───────────────────────────────────────────────────────────
@@ -1,3 +1,5 @@
+use crate::surface::Surface;
+
 /// A `<wpt>` element parsed from a GPX file.
 #[derive(Clone)]
 pub struct Waypoint {
@@ -14,6 +16,10 @@ pub struct Waypoint {
     pub time: Option<i64>,
     /// Planned stop duration in seconds, from `<stopDuration>`. LifeBase-only.
     pub stop_duration: Option<u32>,
+    /// Surface from here to the next waypoint that sets one, from `<surface>`.
+    pub surface: Option<Surface>,
+    /// Crew can meet the runner here, from `<crew>true</crew>`.
+    pub crew: bool,
 }
 
 impl Waypoint {
@@ -47,6 +53,8 @@ mod tests {
             wpt_type: wpt_type.map(String::from),
             time: None,
             stop_duration: None,
+            surface: None,
+            crew: false,
         }
     }
 
@@ -0,0 +1,148 @@
+//! Directional wind: head/tail/cross components relative to the route bearing.
+//!
+//! Wind direction follows the meteorological convention — the bearing the
+//! wind blows *from*, in degrees clockwise from north — so a north wind
+//! (`0°`) is a headwind on a northbound segment.
+
+use crate::pace_model::{WeatherConditions, WEATHER_WIND_MAX, WEATHER_WIND_THRESHOLD_KMH};
+use crate::trace::Trace;
+
+/// Pace penalty per km/h of headwind component.
+pub const HEADWIND_PER_KMH: f64 = 0.006;
+/// Pace gain per km/h of tailwind component (smaller than the headwind cost).
+pub const TAILWIND_PER_KMH: f64 = 0.003;
+/// Maximum pace gain from a tailwind.
+pub const TAILWIND_MAX: f64 = 0.08;
+/// Pace penalty per km/h of crosswind above `WEATHER_WIND_THRESHOLD_KMH` (balance, exposure).
+pub const CROSSWIND_PER_KMH: f64 = 0.002;
+
+/// Wind resolved against a direction of travel.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "wasm", derive(serde::Serialize))]
+#[cfg_attr(feature = "wasm", serde(rename_all = "camelCase"))]
+pub struct WindComponents {
+    /// Along-track component (km/h): positive is headwind, negative tailwind.
+    pub headwind_kmh: f64,
+    /// Absolute across-track component (km/h).
+    pub crosswind_kmh: f64,
+}
+
+/// Resolve `wind_kmh` blowing from `wind_from_deg` against travel on `bearing_deg`.
+pub fn components(wind_kmh: f64, wind_from_deg: f64, bearing_deg: f64) -> WindComponents {
+    let angle = (wind_from_deg - bearing_deg).to_radians();
+    WindComponents {
+        headwind_kmh: wind_kmh * angle.cos(),
+        crosswind_kmh: (wind_kmh * angle.sin()).abs(),
+    }
+}
+
+/// Pace multiplier for a wind resolved into components. Below 1.0 with a
+/// tailwind; capped at `1 + WEATHER_WIND_MAX` like the speed-only factor.
+pub fn directional_wind_factor(w: WindComponents) -> f64 {
+    let along = if w.headwind_kmh >= 0.0 {
+        w.headwind_kmh * HEADWIND_PER_KMH
+    } else {
+        -(-w.headwind_kmh * TAILWIND_PER_KMH).min(TAILWIND_MAX)
+    };
+    let across = (w.crosswind_kmh - WEATHER_WIND_THRESHOLD_KMH).max(0.0) * CROSSWIND_PER_KMH;
+    1.0 + (along + across).min(WEATHER_WIND_MAX)
+}
+
+/// Per-segment wind components along `trace` (one per consecutive point pair).
+/// Empty when `conditions` has no `wind_dir_deg`.
+pub fn along_trace(trace: &Trace, conditions: &WeatherConditions) -> Vec<WindComponents> {
+    let Some(from_deg) = conditions.wind_dir_deg else {
+        return Vec::new();
+    };
+    trace
+        .locations
+        .windows(2)
+        .map(|p| {
+            components(
+                conditions.wind_kmh,
+                from_deg,
+                p[0].calculate_bearing_to(&p[1]),
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::location::Location;
+    use crate::pace_model::WEATHER_NEUTRAL;
+
+    #[test]
+    fn components_resolve_head_tail_and_cross() {
+        let head = components(20.0, 0.0, 0.0);
+        assert!((head.headwind_kmh - 20.0).abs() < 1e-9);
+        assert!(head.crosswind_kmh.abs() < 1e-9);
+
+        let tail = components(20.0, 180.0, 0.0);
+        assert!((tail.headwind_kmh + 20.0).abs() < 1e-9);
+
+        let cross = components(20.0, 270.0, 0.0);
+        assert!(cross.headwind_kmh.abs() < 1e-9);
+        assert!((cross.crosswind_kmh - 20.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn headwind_costs_more_than_tailwind_gives() {
+        let head = directional_wind_factor(components(20.0, 90.0, 90.0));
+        let tail = directional_wind_factor(components(20.0, 270.0, 90.0));
+        assert!(head > 1.0);
+        assert!(tail < 1.0);
+        assert!(head - 1.0 > 1.0 - tail);
+        assert!(
+            (directional_wind_factor(components(1000.0, 0.0, 0.0)) - (1.0 + WEATHER_WIND_MAX))
+                .abs()
+                < 1e-9
+        );
+        assert!(
+            (directional_wind_factor(components(1000.0, 180.0, 0.0)) - (1.0 - TAILWIND_MAX)).abs()
+                < 1e-9
+        );
+    }
+
+    #[test]
+    fn light_crosswind_is_neutral() {
+        assert!((directional_wind_factor(components(10.0, 90.0, 0.0)) - 1.0).abs() < 1e-9);
+        assert!(directional_wind_factor(components(40.0, 90.0, 0.0)) > 1.0);
+    }
+
+    #[test]
+    fn along_trace_follows_segment_bearings() {
+        // North then east.
+        let locs = [
+            Location {
+                longitude: 0.0,
+                latitude: 0.0,
+                altitude: 0.0,
+            },
+            Location {
+                longitude: 0.0,
+                latitude: 0.01,
+                altitude: 0.0,
+            },
+            Location {
+                longitude: 0.01,
+                latitude: 0.01,
+                altitude: 0.0,
+            },
+        ];
+        let trace = Trace::new(&locs).unwrap();
+        let north_wind = WeatherConditions {
+            wind_kmh: 30.0,
+            wind_dir_deg: Some(0.0),
+            ..WEATHER_NEUTRAL
+        };
+        let wind = along_trace(&trace, &north_wind);
+        assert_eq!(wind.len(), 2);
+        assert!((wind[0].headwind_kmh - 30.0).abs() < 1e-6);
+        assert!(wind[1].headwind_kmh.abs() < 0.1);
+        assert!((wind[1].crosswind_kmh - 30.0).abs() < 0.1);
+
+        assert!(along_trace(&trace, &WEATHER_NEUTRAL).is_empty());
+    }
+}